@@ -0,0 +1,28 @@
+use simetry::assetto_corsa_competizione::broadcasting::{Client, Message, Registration};
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    println!("Starting connection to ACC broadcasting...");
+    let mut client = Client::connect(
+        Client::DEFAULT_URI,
+        &Registration::default(),
+        Duration::from_secs(1),
+    )
+    .await;
+    println!("Connected!");
+    loop {
+        match client.next_message().await? {
+            Message::BroadcastingEvent(event) => {
+                println!("{:?}: {}", event.event_type, event.message)
+            }
+            Message::RealtimeUpdate(update) => println!(
+                "{:?} {:.0}s, focused car {}",
+                update.phase,
+                update.session_time / 1000.0,
+                update.focused_car_index
+            ),
+            _ => {}
+        }
+    }
+}
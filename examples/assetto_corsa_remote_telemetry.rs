@@ -0,0 +1,23 @@
+use simetry::assetto_corsa::remote_telemetry::{Client, Subscription};
+use std::env;
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() {
+    let uri = env::args()
+        .nth(1)
+        .unwrap_or_else(|| Client::DEFAULT_URI.to_string());
+    let client = Client::connect(&uri, Subscription::Update, Duration::from_secs(1)).await;
+    println!(
+        "{} driving {} at {}",
+        client.handshake().driver_name,
+        client.handshake().car_name,
+        client.handshake().track_name,
+    );
+    while let Ok(sim_state) = client.next_sim_state().await {
+        println!(
+            "{} km/h @ {} RPM",
+            sim_state.car_info.speed_kmh, sim_state.car_info.rpm,
+        );
+    }
+}
@@ -0,0 +1,20 @@
+use simetry::iracing::commands::RecordingSink;
+use simetry::iracing::{CameraDirector, DirectorConfig, DiskClient};
+use std::env;
+use std::sync::Arc;
+
+fn main() {
+    let mut client =
+        DiskClient::open(env::args().nth(1).expect("Filename argument required")).unwrap();
+    // Only print the chosen shots, instead of switching cameras in a running sim.
+    let sink = Arc::new(RecordingSink::new());
+    let mut director = CameraDirector::with_sink(DirectorConfig::default(), sink);
+    while let Some(sim_state) = client.next_sim_state() {
+        if let Some(shot) = director.update(&sim_state) {
+            println!(
+                "{:.1}: car {} in group {} ({:?})",
+                shot.session_time, shot.car_idx, shot.camera_group, shot.reason,
+            );
+        }
+    }
+}
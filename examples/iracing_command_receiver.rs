@@ -0,0 +1,16 @@
+use simetry::iracing::commands::{default_sink, CommandReceiver};
+use std::env;
+
+/// Pass an address such as `192.168.1.20:24811` to accept commands from other machines.
+#[tokio::main]
+async fn main() {
+    let uri = env::args()
+        .nth(1)
+        .unwrap_or_else(|| CommandReceiver::DEFAULT_URI.to_string());
+    let receiver = CommandReceiver::bind(&uri).await.unwrap();
+    println!(
+        "Forwarding commands received on {}",
+        receiver.local_addr().unwrap()
+    );
+    receiver.forward_to(&*default_sink()).await.unwrap();
+}
@@ -0,0 +1,16 @@
+use simetry::iracing::Client;
+use std::time::Duration;
+
+#[tokio::main]
+async fn main() {
+    let mut client = Client::connect(Duration::from_secs(1)).await;
+    let subscriptions = client.subscriptions_mut();
+    subscriptions.subscribe("PlayerCarMyIncidentCount");
+    subscriptions.subscribe_with_dead_band("dcBrakeBias", 0.1);
+    while let Some(change) = client.next_change().await {
+        println!(
+            "{:.3}: {} changed from {} to {}",
+            change.session_time, change.name, change.old, change.new,
+        );
+    }
+}
@@ -0,0 +1,15 @@
+use simetry::outgauge::Emitter;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let target = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:4444".to_string());
+    println!("Starting connection to any supported sim...");
+    let mut client = simetry::connect().await;
+    println!(
+        "Connected to {}, sending OutGauge to {target}",
+        client.name()
+    );
+    Emitter::connect(&target).await?.run(&mut *client).await
+}
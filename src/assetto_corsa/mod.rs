@@ -7,9 +7,13 @@ use std::borrow::Cow;
 use uom::si::angular_velocity::revolution_per_minute;
 use uom::si::f64::{AngularVelocity, Velocity};
 use uom::si::velocity::kilometer_per_hour;
+#[cfg(target_family = "unix")]
+pub use util::SharedMemoryPages;
 
 mod conversions;
 mod data;
+pub mod remote_telemetry;
+pub mod server_plugin;
 mod shared_memory_data;
 pub(crate) mod util;
 
@@ -0,0 +1,339 @@
+//! Client for the UDP remote telemetry of Assetto Corsa.
+//!
+//! Unlike the shared memory [`Client`](super::Client), this works across machines and from
+//! any operating system.
+
+use crate::{Moment, Pedals, Simetry};
+use anyhow::{bail, Context, Result};
+use byteorder::{ByteOrder, LittleEndian};
+use std::borrow::Cow;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use uom::si::angular_velocity::revolution_per_minute;
+use uom::si::f64::{AngularVelocity, Velocity};
+use uom::si::velocity::kilometer_per_hour;
+
+/// Identifier of the Assetto Corsa devices, for the handshake.
+const IDENTIFIER: i32 = 1;
+const VERSION: i32 = 1;
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+
+const OPERATION_HANDSHAKE: i32 = 0;
+const OPERATION_SUBSCRIBE_UPDATE: i32 = 1;
+const OPERATION_SUBSCRIBE_SPOT: i32 = 2;
+const OPERATION_DISMISS: i32 = 3;
+
+const STRING_LEN: usize = 50;
+const HANDSHAKE_RESPONSE_SIZE: usize = 408;
+const CAR_INFO_SIZE: usize = 328;
+const LAP_SIZE: usize = 212;
+
+/// Kind of updates to receive after the handshake.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Subscription {
+    /// [`CarInfo`] of the player car for every physics step.
+    #[default]
+    Update,
+    /// [`Lap`] for every lap completed by any car.
+    Spot,
+}
+
+/// Response to the handshake, describing the session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Handshake {
+    pub car_name: String,
+    pub driver_name: String,
+    pub identifier: i32,
+    pub version: i32,
+    pub track_name: String,
+    pub track_config: String,
+}
+
+/// Physics of the player car, called `RTCarInfo` by Assetto Corsa.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CarInfo {
+    pub speed_kmh: f32,
+    pub speed_mph: f32,
+    pub speed_ms: f32,
+    pub is_abs_enabled: bool,
+    pub is_abs_in_action: bool,
+    pub is_tc_in_action: bool,
+    pub is_tc_enabled: bool,
+    pub is_in_pit: bool,
+    pub is_engine_limiter_on: bool,
+    pub acc_g_vertical: f32,
+    pub acc_g_horizontal: f32,
+    pub acc_g_frontal: f32,
+    /// Milliseconds.
+    pub lap_time: i32,
+    pub last_lap: i32,
+    pub best_lap: i32,
+    pub lap_count: i32,
+    pub gas: f32,
+    pub brake: f32,
+    pub clutch: f32,
+    pub rpm: f32,
+    pub steer: f32,
+    /// 0 is reverse, 1 is neutral, 2 is first gear.
+    pub gear: i32,
+    pub cg_height: f32,
+    pub wheel_angular_speed: [f32; 4],
+    pub slip_angle: [f32; 4],
+    pub slip_angle_contact_patch: [f32; 4],
+    pub slip_ratio: [f32; 4],
+    pub tyre_slip: [f32; 4],
+    pub nd_slip: [f32; 4],
+    pub load: [f32; 4],
+    pub dy: [f32; 4],
+    pub mz: [f32; 4],
+    pub tyre_dirty_level: [f32; 4],
+    pub camber_rad: [f32; 4],
+    pub tyre_radius: [f32; 4],
+    pub tyre_loaded_radius: [f32; 4],
+    pub suspension_height: [f32; 4],
+    pub car_position_normalized: f32,
+    pub car_slope: f32,
+    pub car_coordinates: [f32; 3],
+}
+
+/// Lap completed by any car, called `RTLap` by Assetto Corsa.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lap {
+    pub car_identifier_number: i32,
+    pub lap: i32,
+    pub driver_name: String,
+    pub car_name: String,
+    /// Milliseconds.
+    pub time: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    CarInfo(Box<CarInfo>),
+    Lap(Lap),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimState {
+    pub handshake: Handshake,
+    pub car_info: CarInfo,
+}
+
+#[derive(Debug)]
+pub struct Client {
+    socket: UdpSocket,
+    handshake: Handshake,
+}
+
+impl Client {
+    pub const DEFAULT_URI: &'static str = "127.0.0.1:9996";
+
+    pub async fn connect(uri: &str, subscription: Subscription, retry_delay: Duration) -> Self {
+        loop {
+            if let Ok(client) = Self::try_connect(uri, subscription).await {
+                return client;
+            }
+            tokio::time::sleep(retry_delay).await;
+        }
+    }
+
+    pub async fn try_connect(uri: &str, subscription: Subscription) -> Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0").await?;
+        socket.connect(uri).await?;
+        socket.send(&operation(OPERATION_HANDSHAKE)).await?;
+        let mut buffer = [0u8; HANDSHAKE_RESPONSE_SIZE];
+        let len = tokio::time::timeout(HANDSHAKE_TIMEOUT, socket.recv(&mut buffer))
+            .await
+            .context("No handshake response")??;
+        let handshake = parse_handshake(&buffer[..len])?;
+        let operation_id = match subscription {
+            Subscription::Update => OPERATION_SUBSCRIBE_UPDATE,
+            Subscription::Spot => OPERATION_SUBSCRIBE_SPOT,
+        };
+        socket.send(&operation(operation_id)).await?;
+        Ok(Self { socket, handshake })
+    }
+
+    pub fn handshake(&self) -> &Handshake {
+        &self.handshake
+    }
+
+    /// Waits for the next update, ignoring packets that are not recognized.
+    pub async fn next_event(&self) -> Result<Event> {
+        let mut buffer = [0u8; CAR_INFO_SIZE + 1];
+        loop {
+            let len = self.socket.recv(&mut buffer).await?;
+            match len {
+                CAR_INFO_SIZE => {
+                    return Ok(Event::CarInfo(Box::new(parse_car_info(&buffer[..len]))))
+                }
+                LAP_SIZE => return Ok(Event::Lap(parse_lap(&buffer[..len]))),
+                _ => log::debug!("Ignoring packet of {len} bytes"),
+            }
+        }
+    }
+
+    /// Waits for the next [`CarInfo`], which requires [`Subscription::Update`].
+    pub async fn next_sim_state(&self) -> Result<SimState> {
+        loop {
+            if let Event::CarInfo(car_info) = self.next_event().await? {
+                return Ok(SimState {
+                    handshake: self.handshake.clone(),
+                    car_info: *car_info,
+                });
+            }
+        }
+    }
+
+    /// Stop the updates, also done when the client is dropped.
+    pub async fn dismiss(&self) -> Result<()> {
+        self.socket.send(&operation(OPERATION_DISMISS)).await?;
+        Ok(())
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        let _ = self.socket.try_send(&operation(OPERATION_DISMISS));
+    }
+}
+
+#[async_trait::async_trait]
+impl Simetry for Client {
+    fn name(&self) -> &str {
+        "AssettoCorsa"
+    }
+
+    async fn next_moment(&mut self) -> Option<Box<dyn Moment + Send + Sync + 'static>> {
+        Some(Box::new(self.next_sim_state().await.ok()?))
+    }
+}
+
+impl Moment for SimState {
+    fn vehicle_gear(&self) -> Option<i8> {
+        Some((self.car_info.gear - 1) as i8)
+    }
+
+    fn vehicle_velocity(&self) -> Option<Velocity> {
+        Some(Velocity::new::<kilometer_per_hour>(
+            self.car_info.speed_kmh as f64,
+        ))
+    }
+
+    fn vehicle_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(
+            self.car_info.rpm as f64,
+        ))
+    }
+
+    fn is_pit_limiter_engaged(&self) -> Option<bool> {
+        Some(self.car_info.is_engine_limiter_on)
+    }
+
+    fn is_vehicle_in_pit_lane(&self) -> Option<bool> {
+        Some(self.car_info.is_in_pit)
+    }
+
+    fn vehicle_unique_id(&self) -> Option<Cow<str>> {
+        Some(self.handshake.car_name.as_str().into())
+    }
+
+    fn pedals(&self) -> Option<Pedals> {
+        Some(Pedals {
+            throttle: self.car_info.gas as f64,
+            brake: self.car_info.brake as f64,
+            clutch: self.car_info.clutch as f64,
+        })
+    }
+}
+
+fn operation(operation_id: i32) -> [u8; 12] {
+    let mut packet = [0u8; 12];
+    LittleEndian::write_i32(&mut packet[0..4], IDENTIFIER);
+    LittleEndian::write_i32(&mut packet[4..8], VERSION);
+    LittleEndian::write_i32(&mut packet[8..12], operation_id);
+    packet
+}
+
+/// Reads a UTF-16 string of [`STRING_LEN`] units, which Assetto Corsa terminates with `%` or null.
+fn read_string(data: &[u8]) -> String {
+    let units = data[..STRING_LEN * 2]
+        .chunks_exact(2)
+        .map(LittleEndian::read_u16)
+        .take_while(|v| *v != 0 && *v != b'%' as u16)
+        .collect::<Vec<_>>();
+    String::from_utf16_lossy(&units)
+}
+
+fn parse_handshake(data: &[u8]) -> Result<Handshake> {
+    if data.len() != HANDSHAKE_RESPONSE_SIZE {
+        bail!("Handshake response has {} bytes", data.len());
+    }
+    Ok(Handshake {
+        car_name: read_string(&data[0..]),
+        driver_name: read_string(&data[100..]),
+        identifier: LittleEndian::read_i32(&data[200..]),
+        version: LittleEndian::read_i32(&data[204..]),
+        track_name: read_string(&data[208..]),
+        track_config: read_string(&data[308..]),
+    })
+}
+
+fn parse_car_info(data: &[u8]) -> CarInfo {
+    let f = |offset: usize| LittleEndian::read_f32(&data[offset..]);
+    let i = |offset: usize| LittleEndian::read_i32(&data[offset..]);
+    let b = |offset: usize| data[offset] != 0;
+    let wheels = |offset: usize| [f(offset), f(offset + 4), f(offset + 8), f(offset + 12)];
+    CarInfo {
+        speed_kmh: f(8),
+        speed_mph: f(12),
+        speed_ms: f(16),
+        is_abs_enabled: b(20),
+        is_abs_in_action: b(21),
+        is_tc_in_action: b(22),
+        is_tc_enabled: b(23),
+        is_in_pit: b(24),
+        is_engine_limiter_on: b(25),
+        acc_g_vertical: f(28),
+        acc_g_horizontal: f(32),
+        acc_g_frontal: f(36),
+        lap_time: i(40),
+        last_lap: i(44),
+        best_lap: i(48),
+        lap_count: i(52),
+        gas: f(56),
+        brake: f(60),
+        clutch: f(64),
+        rpm: f(68),
+        steer: f(72),
+        gear: i(76),
+        cg_height: f(80),
+        wheel_angular_speed: wheels(84),
+        slip_angle: wheels(100),
+        slip_angle_contact_patch: wheels(116),
+        slip_ratio: wheels(132),
+        tyre_slip: wheels(148),
+        nd_slip: wheels(164),
+        load: wheels(180),
+        dy: wheels(196),
+        mz: wheels(212),
+        tyre_dirty_level: wheels(228),
+        camber_rad: wheels(244),
+        tyre_radius: wheels(260),
+        tyre_loaded_radius: wheels(276),
+        suspension_height: wheels(292),
+        car_position_normalized: f(308),
+        car_slope: f(312),
+        car_coordinates: [f(316), f(320), f(324)],
+    }
+}
+
+fn parse_lap(data: &[u8]) -> Lap {
+    Lap {
+        car_identifier_number: LittleEndian::read_i32(&data[0..]),
+        lap: LittleEndian::read_i32(&data[4..]),
+        driver_name: read_string(&data[8..]),
+        car_name: read_string(&data[108..]),
+        time: LittleEndian::read_i32(&data[208..]),
+    }
+}
@@ -0,0 +1,569 @@
+//! UDP plugin protocol of the Assetto Corsa dedicated server.
+//!
+//! The server sends [`Event`]s to `UDP_PLUGIN_ADDRESS` and accepts [`Command`]s on
+//! `UDP_PLUGIN_LOCAL_PORT`, both set in `server_cfg.ini`.
+
+use crate::assetto_corsa::SessionType;
+use crate::byte_reader::ByteReader;
+use anyhow::{bail, Context, Result};
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::net::{ToSocketAddrs, UdpSocket};
+
+const NEW_SESSION: u8 = 50;
+const NEW_CONNECTION: u8 = 51;
+const CONNECTION_CLOSED: u8 = 52;
+const CAR_UPDATE: u8 = 53;
+const CAR_INFO: u8 = 54;
+const END_SESSION: u8 = 55;
+const VERSION: u8 = 56;
+const CHAT: u8 = 57;
+const CLIENT_LOADED: u8 = 58;
+const SESSION_INFO: u8 = 59;
+const ERROR: u8 = 60;
+const LAP_COMPLETED: u8 = 73;
+const CLIENT_EVENT: u8 = 130;
+
+const COLLISION_WITH_CAR: u8 = 10;
+const COLLISION_WITH_ENV: u8 = 11;
+
+const REALTIME_POS_INTERVAL: u8 = 200;
+const GET_CAR_INFO: u8 = 201;
+const SEND_CHAT: u8 = 202;
+const BROADCAST_CHAT: u8 = 203;
+const GET_SESSION_INFO: u8 = 204;
+const KICK_USER: u8 = 206;
+const NEXT_SESSION: u8 = 207;
+const RESTART_SESSION: u8 = 208;
+const ADMIN_COMMAND: u8 = 209;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SessionInfo {
+    pub protocol_version: u8,
+    pub session_index: u8,
+    pub current_session_index: u8,
+    pub session_count: u8,
+    pub server_name: String,
+    pub track: String,
+    pub track_config: String,
+    pub name: String,
+    /// Booking sessions are [`SessionType::Unknown`].
+    pub session_type: SessionType,
+    /// Minutes.
+    pub time: u16,
+    pub laps: u16,
+    /// Seconds.
+    pub wait_time: u16,
+    pub ambient_temp: u8,
+    pub road_temp: u8,
+    pub weather_graphics: String,
+    pub elapsed_ms: i32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Connection {
+    pub driver_name: String,
+    pub driver_guid: String,
+    pub car_id: u8,
+    pub car_model: String,
+    pub car_skin: String,
+}
+
+/// Position of a car, sent at the interval set by [`Command::RealtimePosInterval`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct CarUpdate {
+    pub car_id: u8,
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+    /// 0 is reverse, 1 is neutral, 2 is first gear.
+    pub gear: u8,
+    pub engine_rpm: u16,
+    pub normalized_spline_pos: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CarInfo {
+    pub car_id: u8,
+    pub is_connected: bool,
+    pub car_model: String,
+    pub car_skin: String,
+    pub driver_name: String,
+    pub driver_team: String,
+    pub driver_guid: String,
+}
+
+/// Standing of a car, sent with each completed lap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LeaderboardEntry {
+    pub car_id: u8,
+    /// Milliseconds.
+    pub lap_time: u32,
+    pub laps: u16,
+    pub has_completed_flag: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LapCompleted {
+    pub car_id: u8,
+    /// Milliseconds.
+    pub lap_time: u32,
+    /// Number of times the car left the track, a lap with cuts is not valid.
+    pub cuts: u8,
+    pub leaderboard: Vec<LeaderboardEntry>,
+    pub grip_level: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Collision {
+    pub car_id: u8,
+    /// Other car, or `None` for a collision with the environment.
+    pub other_car_id: Option<u8>,
+    pub impact_speed: f32,
+    pub world_position: [f32; 3],
+    pub relative_position: [f32; 3],
+}
+
+/// Message sent by the server.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    NewSession(SessionInfo),
+    /// Response to [`Command::GetSessionInfo`].
+    SessionInfo(SessionInfo),
+    /// Results file of the session that ended.
+    EndSession(String),
+    NewConnection(Connection),
+    ConnectionClosed(Connection),
+    /// Car of a new connection finished loading.
+    ClientLoaded(u8),
+    CarUpdate(CarUpdate),
+    /// Response to [`Command::GetCarInfo`].
+    CarInfo(CarInfo),
+    LapCompleted(LapCompleted),
+    Collision(Collision),
+    Chat {
+        car_id: u8,
+        message: String,
+    },
+    /// Protocol version of the server.
+    Version(u8),
+    Error(String),
+}
+
+/// Message sent to the server.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    /// Interval of [`Event::CarUpdate`], zero to disable them.
+    RealtimePosInterval(Duration),
+    GetCarInfo(u8),
+    /// Session by index, `None` for the current one.
+    GetSessionInfo(Option<u8>),
+    SendChat {
+        car_id: u8,
+        message: String,
+    },
+    BroadcastChat(String),
+    Kick(u8),
+    NextSession,
+    RestartSession,
+    /// Command like in the admin chat, such as `/ban_id 3`.
+    AdminCommand(String),
+}
+
+impl Event {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut r = ByteReader::new(data, "Server plugin message");
+        Ok(match r.u8()? {
+            NEW_SESSION => Event::NewSession(SessionInfo::read(&mut r)?),
+            SESSION_INFO => Event::SessionInfo(SessionInfo::read(&mut r)?),
+            END_SESSION => Event::EndSession(read_string_w(&mut r)?),
+            NEW_CONNECTION => Event::NewConnection(Connection::read(&mut r)?),
+            CONNECTION_CLOSED => Event::ConnectionClosed(Connection::read(&mut r)?),
+            CLIENT_LOADED => Event::ClientLoaded(r.u8()?),
+            CAR_UPDATE => Event::CarUpdate(CarUpdate {
+                car_id: r.u8()?,
+                position: r.f32s()?,
+                velocity: r.f32s()?,
+                gear: r.u8()?,
+                engine_rpm: r.u16()?,
+                normalized_spline_pos: r.f32()?,
+            }),
+            CAR_INFO => Event::CarInfo(CarInfo {
+                car_id: r.u8()?,
+                is_connected: r.u8()? != 0,
+                car_model: read_string_w(&mut r)?,
+                car_skin: read_string_w(&mut r)?,
+                driver_name: read_string_w(&mut r)?,
+                driver_team: read_string_w(&mut r)?,
+                driver_guid: read_string_w(&mut r)?,
+            }),
+            LAP_COMPLETED => {
+                let car_id = r.u8()?;
+                let lap_time = r.u32()?;
+                let cuts = r.u8()?;
+                let leaderboard = (0..r.u8()?)
+                    .map(|_| {
+                        Ok(LeaderboardEntry {
+                            car_id: r.u8()?,
+                            lap_time: r.u32()?,
+                            laps: r.u16()?,
+                            has_completed_flag: r.u8()? != 0,
+                        })
+                    })
+                    .collect::<Result<_>>()?;
+                Event::LapCompleted(LapCompleted {
+                    car_id,
+                    lap_time,
+                    cuts,
+                    leaderboard,
+                    grip_level: r.f32()?,
+                })
+            }
+            CLIENT_EVENT => {
+                let event_type = r.u8()?;
+                let car_id = r.u8()?;
+                let other_car_id = match event_type {
+                    COLLISION_WITH_CAR => Some(r.u8()?),
+                    COLLISION_WITH_ENV => None,
+                    other => bail!("Unknown client event type {other}"),
+                };
+                Event::Collision(Collision {
+                    car_id,
+                    other_car_id,
+                    impact_speed: r.f32()?,
+                    world_position: r.f32s()?,
+                    relative_position: r.f32s()?,
+                })
+            }
+            CHAT => Event::Chat {
+                car_id: r.u8()?,
+                message: read_string_w(&mut r)?,
+            },
+            VERSION => Event::Version(r.u8()?),
+            ERROR => Event::Error(read_string_w(&mut r)?),
+            other => bail!("Unknown server plugin message type {other}"),
+        })
+    }
+
+    /// Encode like the server, to stand in for it.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w = Vec::new();
+        match self {
+            Event::NewSession(info) => {
+                w.push(NEW_SESSION);
+                info.write(&mut w);
+            }
+            Event::SessionInfo(info) => {
+                w.push(SESSION_INFO);
+                info.write(&mut w);
+            }
+            Event::EndSession(file) => {
+                w.push(END_SESSION);
+                write_string_w(&mut w, file);
+            }
+            Event::NewConnection(connection) => {
+                w.push(NEW_CONNECTION);
+                connection.write(&mut w);
+            }
+            Event::ConnectionClosed(connection) => {
+                w.push(CONNECTION_CLOSED);
+                connection.write(&mut w);
+            }
+            Event::ClientLoaded(car_id) => w.extend([CLIENT_LOADED, *car_id]),
+            Event::CarUpdate(update) => {
+                w.extend([CAR_UPDATE, update.car_id]);
+                write_vector(&mut w, update.position);
+                write_vector(&mut w, update.velocity);
+                w.push(update.gear);
+                w.write_u16::<LittleEndian>(update.engine_rpm).unwrap();
+                w.write_f32::<LittleEndian>(update.normalized_spline_pos)
+                    .unwrap();
+            }
+            Event::CarInfo(info) => {
+                w.extend([CAR_INFO, info.car_id, info.is_connected as u8]);
+                for string in [
+                    &info.car_model,
+                    &info.car_skin,
+                    &info.driver_name,
+                    &info.driver_team,
+                    &info.driver_guid,
+                ] {
+                    write_string_w(&mut w, string);
+                }
+            }
+            Event::LapCompleted(lap) => {
+                w.extend([LAP_COMPLETED, lap.car_id]);
+                w.write_u32::<LittleEndian>(lap.lap_time).unwrap();
+                w.extend([lap.cuts, lap.leaderboard.len() as u8]);
+                for entry in &lap.leaderboard {
+                    w.push(entry.car_id);
+                    w.write_u32::<LittleEndian>(entry.lap_time).unwrap();
+                    w.write_u16::<LittleEndian>(entry.laps).unwrap();
+                    w.push(entry.has_completed_flag as u8);
+                }
+                w.write_f32::<LittleEndian>(lap.grip_level).unwrap();
+            }
+            Event::Collision(collision) => {
+                w.push(CLIENT_EVENT);
+                match collision.other_car_id {
+                    Some(other_car_id) => {
+                        w.extend([COLLISION_WITH_CAR, collision.car_id, other_car_id])
+                    }
+                    None => w.extend([COLLISION_WITH_ENV, collision.car_id]),
+                }
+                w.write_f32::<LittleEndian>(collision.impact_speed).unwrap();
+                write_vector(&mut w, collision.world_position);
+                write_vector(&mut w, collision.relative_position);
+            }
+            Event::Chat { car_id, message } => {
+                w.extend([CHAT, *car_id]);
+                write_string_w(&mut w, message);
+            }
+            Event::Version(version) => w.extend([VERSION, *version]),
+            Event::Error(message) => {
+                w.push(ERROR);
+                write_string_w(&mut w, message);
+            }
+        }
+        w
+    }
+}
+
+impl Command {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut w = Vec::new();
+        match self {
+            Command::RealtimePosInterval(interval) => {
+                w.push(REALTIME_POS_INTERVAL);
+                w.write_u16::<LittleEndian>(interval.as_millis().min(u16::MAX as u128) as u16)
+                    .unwrap();
+            }
+            Command::GetCarInfo(car_id) => w.extend([GET_CAR_INFO, *car_id]),
+            Command::GetSessionInfo(index) => {
+                w.push(GET_SESSION_INFO);
+                w.write_i16::<LittleEndian>(index.map_or(-1, i16::from))
+                    .unwrap();
+            }
+            Command::SendChat { car_id, message } => {
+                w.extend([SEND_CHAT, *car_id]);
+                write_string_w(&mut w, message);
+            }
+            Command::BroadcastChat(message) => {
+                w.push(BROADCAST_CHAT);
+                write_string_w(&mut w, message);
+            }
+            Command::Kick(car_id) => w.extend([KICK_USER, *car_id]),
+            Command::NextSession => w.push(NEXT_SESSION),
+            Command::RestartSession => w.push(RESTART_SESSION),
+            Command::AdminCommand(command) => {
+                w.push(ADMIN_COMMAND);
+                write_string_w(&mut w, command);
+            }
+        }
+        w
+    }
+
+    /// Decode like the server, to stand in for it.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut r = ByteReader::new(data, "Server plugin message");
+        Ok(match r.u8()? {
+            REALTIME_POS_INTERVAL => {
+                Command::RealtimePosInterval(Duration::from_millis(r.u16()? as u64))
+            }
+            GET_CAR_INFO => Command::GetCarInfo(r.u8()?),
+            GET_SESSION_INFO => Command::GetSessionInfo((r.u16()? as i16).try_into().ok()),
+            SEND_CHAT => Command::SendChat {
+                car_id: r.u8()?,
+                message: read_string_w(&mut r)?,
+            },
+            BROADCAST_CHAT => Command::BroadcastChat(read_string_w(&mut r)?),
+            KICK_USER => Command::Kick(r.u8()?),
+            NEXT_SESSION => Command::NextSession,
+            RESTART_SESSION => Command::RestartSession,
+            ADMIN_COMMAND => Command::AdminCommand(read_string_w(&mut r)?),
+            other => bail!("Unknown server plugin command {other}"),
+        })
+    }
+}
+
+impl SessionInfo {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        Ok(Self {
+            protocol_version: r.u8()?,
+            session_index: r.u8()?,
+            current_session_index: r.u8()?,
+            session_count: r.u8()?,
+            server_name: read_string_w(r)?,
+            track: read_string(r)?,
+            track_config: read_string(r)?,
+            name: read_string(r)?,
+            session_type: match r.u8()? {
+                1 => SessionType::Practice,
+                2 => SessionType::Qualify,
+                3 => SessionType::Race,
+                _ => SessionType::Unknown,
+            },
+            time: r.u16()?,
+            laps: r.u16()?,
+            wait_time: r.u16()?,
+            ambient_temp: r.u8()?,
+            road_temp: r.u8()?,
+            weather_graphics: read_string(r)?,
+            elapsed_ms: r.i32()?,
+        })
+    }
+
+    fn write(&self, w: &mut Vec<u8>) {
+        w.extend([
+            self.protocol_version,
+            self.session_index,
+            self.current_session_index,
+            self.session_count,
+        ]);
+        write_string_w(w, &self.server_name);
+        write_string(w, &self.track);
+        write_string(w, &self.track_config);
+        write_string(w, &self.name);
+        w.push(match self.session_type {
+            SessionType::Practice => 1,
+            SessionType::Qualify => 2,
+            SessionType::Race => 3,
+            _ => 0,
+        });
+        for value in [self.time, self.laps, self.wait_time] {
+            w.write_u16::<LittleEndian>(value).unwrap();
+        }
+        w.extend([self.ambient_temp, self.road_temp]);
+        write_string(w, &self.weather_graphics);
+        w.write_i32::<LittleEndian>(self.elapsed_ms).unwrap();
+    }
+}
+
+impl Connection {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        Ok(Self {
+            driver_name: read_string_w(r)?,
+            driver_guid: read_string_w(r)?,
+            car_id: r.u8()?,
+            car_model: read_string(r)?,
+            car_skin: read_string(r)?,
+        })
+    }
+
+    fn write(&self, w: &mut Vec<u8>) {
+        write_string_w(w, &self.driver_name);
+        write_string_w(w, &self.driver_guid);
+        w.push(self.car_id);
+        write_string(w, &self.car_model);
+        write_string(w, &self.car_skin);
+    }
+}
+
+/// Socket receiving the events of a server and sending it commands.
+#[derive(Debug)]
+pub struct Endpoint {
+    socket: UdpSocket,
+    server: SocketAddr,
+}
+
+impl Endpoint {
+    /// Listen on `UDP_PLUGIN_ADDRESS` and send commands to the server at
+    /// `UDP_PLUGIN_LOCAL_PORT`.
+    pub async fn bind<A: ToSocketAddrs, S: ToSocketAddrs>(listen: A, server: S) -> Result<Self> {
+        let socket = UdpSocket::bind(listen).await?;
+        let server = tokio::net::lookup_host(server)
+            .await?
+            .next()
+            .context("No address for the server")?;
+        Ok(Self { socket, server })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Waits for the next event of the server, ignoring packets from other addresses and
+    /// packets that are not recognized.
+    pub async fn next_event(&self) -> Result<Event> {
+        let mut buffer = vec![0u8; u16::MAX as usize];
+        loop {
+            let (len, origin) = self.socket.recv_from(&mut buffer).await?;
+            if origin.ip() != self.server.ip() {
+                log::debug!("Ignoring packet from {origin}");
+                continue;
+            }
+            match Event::decode(&buffer[..len]) {
+                Ok(event) => return Ok(event),
+                Err(err) => log::debug!("Ignoring server plugin message: {err}"),
+            }
+        }
+    }
+
+    pub async fn send(&self, command: &Command) -> Result<()> {
+        self.socket.send_to(&command.encode(), self.server).await?;
+        Ok(())
+    }
+
+    pub async fn send_chat(&self, car_id: u8, message: &str) -> Result<()> {
+        self.send(&Command::SendChat {
+            car_id,
+            message: message.to_string(),
+        })
+        .await
+    }
+
+    pub async fn broadcast_chat(&self, message: &str) -> Result<()> {
+        self.send(&Command::BroadcastChat(message.to_string()))
+            .await
+    }
+
+    pub async fn kick(&self, car_id: u8) -> Result<()> {
+        self.send(&Command::Kick(car_id)).await
+    }
+
+    pub async fn admin_command(&self, command: &str) -> Result<()> {
+        self.send(&Command::AdminCommand(command.to_string())).await
+    }
+}
+
+fn write_vector(w: &mut Vec<u8>, vector: [f32; 3]) {
+    for value in vector {
+        w.write_f32::<LittleEndian>(value).unwrap();
+    }
+}
+
+/// String of single byte characters, prefixed by its length.
+fn write_string(w: &mut Vec<u8>, value: &str) {
+    let bytes = value
+        .chars()
+        .take(u8::MAX as usize)
+        .map(|c| u8::try_from(c).unwrap_or(b'?'))
+        .collect::<Vec<_>>();
+    w.push(bytes.len() as u8);
+    w.extend(bytes);
+}
+
+/// String of UTF-32 characters, prefixed by its length in characters.
+fn write_string_w(w: &mut Vec<u8>, value: &str) {
+    let chars = value.chars().take(u8::MAX as usize).collect::<Vec<_>>();
+    w.push(chars.len() as u8);
+    for c in chars {
+        w.write_u32::<LittleEndian>(c as u32).unwrap();
+    }
+}
+
+fn read_string(r: &mut ByteReader) -> Result<String> {
+    let len = r.u8()? as usize;
+    Ok(r.take(len)?.iter().map(|b| *b as char).collect())
+}
+
+fn read_string_w(r: &mut ByteReader) -> Result<String> {
+    let len = r.u8()? as usize;
+    Ok(r.take(len * 4)?
+        .chunks_exact(4)
+        .map(|c| {
+            char::from_u32(u32::from_le_bytes(c.try_into().unwrap()))
+                .unwrap_or(char::REPLACEMENT_CHARACTER)
+        })
+        .collect())
+}
@@ -71,79 +71,60 @@ fn check_version<Version: AcApiVersion>(data: &PageFileStaticTop) -> anyhow::Res
 
 use anyhow::{bail, Context};
 #[cfg(target_family = "unix")]
-pub use unix::SharedMemoryClient;
+pub use unix::{SharedMemoryClient, SharedMemoryPages};
 #[cfg(target_family = "windows")]
 pub use windows::SharedMemoryClient;
 
 #[cfg(target_family = "unix")]
 mod unix {
-    use anyhow::{Context, Result};
-    use libc::{c_void, shm_open};
-    use std::{
-        ffi::CString,
-        marker::PhantomData,
-        os::fd::{AsRawFd, FromRawFd, OwnedFd},
-        sync::Arc,
-        time::Duration,
-    };
+    use anyhow::{bail, Result};
+    use std::{marker::PhantomData, sync::Arc, time::Duration};
 
     use crate::assetto_corsa::{
         util::{check_version, WithPacketId},
         Status,
     };
+    use crate::unix_util::{self, SharedMemoryPath};
 
     use super::{AcApiVersion, PageFileGraphicsTop, PageFileStaticTop, SimState};
 
+    /// Locations of the shared memory pages, which bridges from Proton may expose elsewhere.
+    #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+    pub struct SharedMemoryPages {
+        pub physics: SharedMemoryPath,
+        pub graphics: SharedMemoryPath,
+        pub static_data: SharedMemoryPath,
+    }
+
+    impl Default for SharedMemoryPages {
+        fn default() -> Self {
+            Self {
+                physics: SharedMemoryPath::Name("/acpmf_physics".to_string()),
+                graphics: SharedMemoryPath::Name("/acpmf_graphics".to_string()),
+                static_data: SharedMemoryPath::Name("/acpmf_static".to_string()),
+            }
+        }
+    }
+
     struct SharedMemory<T> {
-        _fd: OwnedFd,
-        memory: *mut c_void,
+        memory: unix_util::SharedMemory,
         phantom_data: PhantomData<T>,
     }
 
-    // Send + Sync here is fine because we own the file descriptor and pointer to the mmapped region
-    // and we're only reading from it.
-    unsafe impl<T: Send + std::fmt::Debug> Send for SharedMemory<T> {}
-    unsafe impl<T: Sync + std::fmt::Debug> Sync for SharedMemory<T> {}
-
     impl<T> SharedMemory<T> {
-        pub fn connect(foo: &str) -> Result<Self> {
-            let path = CString::new(foo).expect("We should be able to build this static C string");
-
-            let fd = unsafe { shm_open(path.as_ptr(), libc::SHM_RDONLY, 0) };
-
-            if fd == -1 {
-                Err(std::io::Error::last_os_error())
-                    .context(format!("Opening the {path:?} file failed"))
-            } else {
-                let len = std::mem::size_of::<T>();
-                let fd = unsafe { OwnedFd::from_raw_fd(fd) };
-
-                let memory = unsafe {
-                    libc::mmap(
-                        std::ptr::null_mut(),
-                        len,
-                        libc::PROT_READ,
-                        libc::MAP_SHARED,
-                        fd.as_raw_fd(),
-                        0,
-                    )
-                };
-
-                if memory == libc::MAP_FAILED {
-                    Err(std::io::Error::last_os_error())
-                        .context("Unable to mmap the opened SHM file {path}")
-                } else {
-                    Ok(Self {
-                        _fd: fd,
-                        memory,
-                        phantom_data: Default::default(),
-                    })
-                }
+        pub fn connect(path: &SharedMemoryPath) -> Result<Self> {
+            let memory = unix_util::SharedMemory::open(path)?;
+            if memory.len() < std::mem::size_of::<T>() {
+                bail!("{path:?} is smaller than the page it should contain");
             }
+            Ok(Self {
+                memory,
+                phantom_data: Default::default(),
+            })
         }
 
         pub fn get(&self) -> &T {
-            unsafe { &(*(self.memory as *const T)) }
+            unsafe { self.memory.get_as::<T>() }
         }
     }
 
@@ -163,8 +144,12 @@ mod unix {
         }
 
         pub async fn connect(retry_delay: Duration) -> Self {
+            Self::connect_with_pages(&SharedMemoryPages::default(), retry_delay).await
+        }
+
+        pub async fn connect_with_pages(pages: &SharedMemoryPages, retry_delay: Duration) -> Self {
             loop {
-                if let Ok(v) = Self::try_connect().await {
+                if let Ok(v) = Self::try_connect_with_pages(pages).await {
                     return v;
                 }
                 tokio::time::sleep(retry_delay).await;
@@ -219,23 +204,27 @@ mod unix {
         }
 
         pub async fn try_connect() -> Result<Self> {
+            Self::try_connect_with_pages(&SharedMemoryPages::default()).await
+        }
+
+        pub async fn try_connect_with_pages(pages: &SharedMemoryPages) -> Result<Self> {
             let graphics_data_memory_top: SharedMemory<PageFileGraphicsTop> =
-                SharedMemory::connect("/acpmf_graphics")?;
+                SharedMemory::connect(&pages.graphics)?;
 
             while !Self::is_connected(&graphics_data_memory_top) {
                 tokio::time::sleep(Duration::from_millis(100)).await;
             }
 
             let page_file_top: SharedMemory<PageFileStaticTop> =
-                SharedMemory::connect("/acpmf_static")?;
+                SharedMemory::connect(&pages.static_data)?;
             Self::check_version(&page_file_top)?;
 
             let static_data_memory: SharedMemory<Version::PageStatic> =
-                SharedMemory::connect("/acpmf_static")?;
+                SharedMemory::connect(&pages.static_data)?;
             let graphics_data_memory: SharedMemory<Version::PageGraphics> =
-                SharedMemory::connect("/acpmf_graphics")?;
+                SharedMemory::connect(&pages.graphics)?;
             let physics_data_memory: SharedMemory<Version::PagePhysics> =
-                SharedMemory::connect("/acpmf_physics")?;
+                SharedMemory::connect(&pages.physics)?;
 
             let static_data: Version::DataStatic = static_data_memory.get().clone().into();
             let graphics_data: Version::DataGraphics = graphics_data_memory.get().clone().into();
@@ -0,0 +1,344 @@
+//! Client for the UDP broadcasting API of Assetto Corsa Competizione.
+//!
+//! The API has to be enabled in `Documents/Assetto Corsa Competizione/Config/broadcasting.json`
+//! by setting `updListenerPort`, usually to 9000. Unlike the shared memory
+//! [`Client`](super::Client), it covers every car of the session and can control the camera
+//! and HUD of a spectating game.
+
+use crate::{Moment, Simetry};
+use anyhow::{bail, Context, Result};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use uom::si::f64::Velocity;
+use uom::si::velocity::kilometer_per_hour;
+
+pub use protocol::{
+    BroadcastingEvent, BroadcastingEventType, CarInfo, CarLocation, DriverInfo, EntryList, LapInfo,
+    Message, RealtimeCarUpdate, RealtimeUpdate, RegistrationResult, Request, SessionPhase,
+    TrackData,
+};
+
+mod protocol;
+
+const REGISTRATION_TIMEOUT: Duration = Duration::from_secs(2);
+/// Minimal delay between entry list requests triggered by unknown cars.
+const ENTRY_LIST_REQUEST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Settings sent when registering, which must match `broadcasting.json`.
+#[derive(Clone, Debug)]
+pub struct Registration {
+    pub display_name: String,
+    pub connection_password: String,
+    pub command_password: String,
+    /// Interval of the realtime updates.
+    pub update_interval: Duration,
+}
+
+impl Default for Registration {
+    fn default() -> Self {
+        Self {
+            display_name: "simetry".to_string(),
+            connection_password: String::new(),
+            command_password: String::new(),
+            update_interval: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Latest update of a car, with the state of the session.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimState {
+    pub realtime_update: RealtimeUpdate,
+    pub car_update: RealtimeCarUpdate,
+    /// Entry of the car, once received.
+    pub car_info: Option<CarInfo>,
+    pub track_data: Option<TrackData>,
+}
+
+#[derive(Debug)]
+pub struct Client {
+    socket: UdpSocket,
+    registration: RegistrationResult,
+    entry_list: HashMap<u16, CarInfo>,
+    track_data: Option<TrackData>,
+    realtime_update: Option<RealtimeUpdate>,
+    car_updates: HashMap<u16, RealtimeCarUpdate>,
+    last_entry_list_request: Option<Instant>,
+}
+
+impl Client {
+    pub const DEFAULT_URI: &'static str = "127.0.0.1:9000";
+
+    pub async fn connect(uri: &str, registration: &Registration, retry_delay: Duration) -> Self {
+        loop {
+            match Self::try_connect(uri, registration).await {
+                Ok(client) => return client,
+                Err(err) => log::debug!("Failed to register to ACC broadcasting: {err}"),
+            }
+            tokio::time::sleep(retry_delay).await;
+        }
+    }
+
+    /// Register and request the entry list and track data.
+    pub async fn try_connect(uri: &str, registration: &Registration) -> Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0").await?;
+        socket.connect(uri).await?;
+        let request = Request::Register {
+            display_name: registration.display_name.clone(),
+            connection_password: registration.connection_password.clone(),
+            update_interval: registration.update_interval,
+            command_password: registration.command_password.clone(),
+        };
+        socket.send(&request.encode()).await?;
+        let registration = tokio::time::timeout(REGISTRATION_TIMEOUT, async {
+            let mut buffer = vec![0u8; u16::MAX as usize];
+            loop {
+                let len = socket.recv(&mut buffer).await?;
+                if let Ok(Message::RegistrationResult(result)) = Message::decode(&buffer[..len]) {
+                    return Ok::<_, anyhow::Error>(result);
+                }
+            }
+        })
+        .await
+        .context("No registration result")??;
+        if !registration.success {
+            bail!("Registration refused: {}", registration.error_message);
+        }
+        let mut client = Self {
+            socket,
+            registration,
+            entry_list: HashMap::new(),
+            track_data: None,
+            realtime_update: None,
+            car_updates: HashMap::new(),
+            last_entry_list_request: None,
+        };
+        client.request_entry_list().await?;
+        client.request_track_data().await?;
+        Ok(client)
+    }
+
+    pub fn connection_id(&self) -> i32 {
+        self.registration.connection_id
+    }
+
+    /// Whether commands are refused, because the command password did not match.
+    pub fn is_read_only(&self) -> bool {
+        self.registration.is_read_only
+    }
+
+    pub fn entry_list(&self) -> &HashMap<u16, CarInfo> {
+        &self.entry_list
+    }
+
+    pub fn track_data(&self) -> Option<&TrackData> {
+        self.track_data.as_ref()
+    }
+
+    pub fn realtime_update(&self) -> Option<&RealtimeUpdate> {
+        self.realtime_update.as_ref()
+    }
+
+    /// Latest update of each car.
+    pub fn car_updates(&self) -> &HashMap<u16, RealtimeCarUpdate> {
+        &self.car_updates
+    }
+
+    /// Waits for the next message, keeping track of the entry list, track data and updates.
+    ///
+    /// The entry list is requested again when a car that is not part of it shows up.
+    pub async fn next_message(&mut self) -> Result<Message> {
+        let mut buffer = vec![0u8; u16::MAX as usize];
+        loop {
+            let len = self.socket.recv(&mut buffer).await?;
+            let message = match Message::decode(&buffer[..len]) {
+                Ok(message) => message,
+                Err(err) => {
+                    log::debug!("Ignoring broadcasting message: {err}");
+                    continue;
+                }
+            };
+            match &message {
+                Message::RegistrationResult(result) => self.registration = result.clone(),
+                Message::RealtimeUpdate(update) => self.realtime_update = Some(update.clone()),
+                Message::RealtimeCarUpdate(update) => {
+                    if !self.entry_list.contains_key(&update.car_index) {
+                        self.request_missing_entry_list().await?;
+                    }
+                    self.car_updates.insert(update.car_index, update.clone());
+                }
+                Message::EntryList(entry_list) => {
+                    self.entry_list
+                        .retain(|index, _| entry_list.car_indexes.contains(index));
+                    self.car_updates
+                        .retain(|index, _| entry_list.car_indexes.contains(index));
+                }
+                Message::TrackData(track_data) => self.track_data = Some(track_data.clone()),
+                Message::EntryListCar(car_info) => {
+                    self.entry_list.insert(car_info.car_index, car_info.clone());
+                }
+                Message::BroadcastingEvent(_) => {}
+            }
+            return Ok(message);
+        }
+    }
+
+    /// Waits for the next update of the focused car.
+    pub async fn next_sim_state(&mut self) -> Result<SimState> {
+        loop {
+            let Message::RealtimeCarUpdate(car_update) = self.next_message().await? else {
+                continue;
+            };
+            let Some(realtime_update) = &self.realtime_update else {
+                continue;
+            };
+            if realtime_update.focused_car_index != car_update.car_index as i32 {
+                continue;
+            }
+            return Ok(SimState {
+                realtime_update: realtime_update.clone(),
+                car_info: self.entry_list.get(&car_update.car_index).cloned(),
+                car_update,
+                track_data: self.track_data.clone(),
+            });
+        }
+    }
+
+    /// Waits for the next [`BroadcastingEvent`], such as accidents and completed laps.
+    pub async fn next_broadcasting_event(&mut self) -> Result<BroadcastingEvent> {
+        loop {
+            if let Message::BroadcastingEvent(event) = self.next_message().await? {
+                return Ok(event);
+            }
+        }
+    }
+
+    /// Ask for the entry list, received as [`Message::EntryList`] followed by a
+    /// [`Message::EntryListCar`] for each car.
+    pub async fn request_entry_list(&mut self) -> Result<()> {
+        self.last_entry_list_request = Some(Instant::now());
+        self.send(&Request::EntryList {
+            connection_id: self.connection_id(),
+        })
+        .await
+    }
+
+    pub async fn request_track_data(&self) -> Result<()> {
+        self.send(&Request::TrackData {
+            connection_id: self.connection_id(),
+        })
+        .await
+    }
+
+    /// Focus the camera on a car, and optionally switch to a camera of [`TrackData::camera_sets`].
+    pub async fn set_focus(
+        &self,
+        car_index: Option<u16>,
+        camera: Option<(&str, &str)>,
+    ) -> Result<()> {
+        self.send(&Request::ChangeFocus {
+            connection_id: self.connection_id(),
+            car_index,
+            camera: camera.map(|(set, camera)| (set.to_string(), camera.to_string())),
+        })
+        .await
+    }
+
+    /// Switch to one of [`TrackData::hud_pages`].
+    pub async fn set_hud_page(&self, hud_page: &str) -> Result<()> {
+        self.send(&Request::ChangeHudPage {
+            connection_id: self.connection_id(),
+            hud_page: hud_page.to_string(),
+        })
+        .await
+    }
+
+    /// Replay from a session time in milliseconds, focused on a car.
+    pub async fn request_instant_replay(
+        &self,
+        start_session_time: f32,
+        duration: Duration,
+        car_index: Option<u16>,
+        camera: Option<(&str, &str)>,
+    ) -> Result<()> {
+        let (camera_set, camera) = camera.unwrap_or_default();
+        self.send(&Request::InstantReplay {
+            connection_id: self.connection_id(),
+            start_session_time,
+            duration,
+            initial_focused_car_index: car_index.map_or(-1, i32::from),
+            initial_camera_set: camera_set.to_string(),
+            initial_camera: camera.to_string(),
+        })
+        .await
+    }
+
+    /// Stop the updates, also done when the client is dropped.
+    pub async fn unregister(&self) -> Result<()> {
+        self.send(&self.unregister_request()).await
+    }
+
+    async fn send(&self, request: &Request) -> Result<()> {
+        self.socket.send(&request.encode()).await?;
+        Ok(())
+    }
+
+    async fn request_missing_entry_list(&mut self) -> Result<()> {
+        let recent = self
+            .last_entry_list_request
+            .is_some_and(|last| last.elapsed() < ENTRY_LIST_REQUEST_INTERVAL);
+        if recent {
+            return Ok(());
+        }
+        self.request_entry_list().await
+    }
+
+    fn unregister_request(&self) -> Request {
+        Request::Unregister {
+            connection_id: self.connection_id(),
+        }
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        let _ = self.socket.try_send(&self.unregister_request().encode());
+    }
+}
+
+#[async_trait::async_trait]
+impl Simetry for Client {
+    fn name(&self) -> &str {
+        "AssettoCorsaCompetizione"
+    }
+
+    async fn next_moment(&mut self) -> Option<Box<dyn Moment + Send + Sync + 'static>> {
+        Some(Box::new(self.next_sim_state().await.ok()?))
+    }
+}
+
+impl Moment for SimState {
+    fn vehicle_gear(&self) -> Option<i8> {
+        Some(self.car_update.gear)
+    }
+
+    fn vehicle_velocity(&self) -> Option<Velocity> {
+        Some(Velocity::new::<kilometer_per_hour>(
+            self.car_update.kmh as f64,
+        ))
+    }
+
+    fn is_vehicle_in_pit_lane(&self) -> Option<bool> {
+        Some(matches!(
+            self.car_update.car_location,
+            CarLocation::Pitlane | CarLocation::PitEntry | CarLocation::PitExit
+        ))
+    }
+
+    fn vehicle_model_id(&self) -> Option<Cow<str>> {
+        let car_info = self.car_info.as_ref()?;
+        Some(car_info.car_model_type.to_string().into())
+    }
+}
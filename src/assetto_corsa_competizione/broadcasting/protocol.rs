@@ -0,0 +1,546 @@
+//! Encoding and decoding of broadcasting messages.
+
+use crate::assetto_corsa_competizione::SessionType;
+use crate::byte_reader::ByteReader;
+use anyhow::{bail, Result};
+use byteorder::{LittleEndian, WriteBytesExt};
+use std::time::Duration;
+
+pub const PROTOCOL_VERSION: u8 = 4;
+
+const REGISTER_COMMAND_APPLICATION: u8 = 1;
+const UNREGISTER_COMMAND_APPLICATION: u8 = 9;
+const REQUEST_ENTRY_LIST: u8 = 10;
+const REQUEST_TRACK_DATA: u8 = 11;
+const CHANGE_HUD_PAGE: u8 = 49;
+const CHANGE_FOCUS: u8 = 50;
+const INSTANT_REPLAY_REQUEST: u8 = 51;
+
+const REGISTRATION_RESULT: u8 = 1;
+const REALTIME_UPDATE: u8 = 2;
+const REALTIME_CAR_UPDATE: u8 = 3;
+const ENTRY_LIST: u8 = 4;
+const TRACK_DATA: u8 = 5;
+const ENTRY_LIST_CAR: u8 = 6;
+const BROADCASTING_EVENT: u8 = 7;
+
+/// Message sent to ACC.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Request {
+    Register {
+        display_name: String,
+        connection_password: String,
+        update_interval: Duration,
+        command_password: String,
+    },
+    Unregister {
+        connection_id: i32,
+    },
+    EntryList {
+        connection_id: i32,
+    },
+    TrackData {
+        connection_id: i32,
+    },
+    ChangeHudPage {
+        connection_id: i32,
+        hud_page: String,
+    },
+    /// Focus a car or camera, or both.
+    ChangeFocus {
+        connection_id: i32,
+        car_index: Option<u16>,
+        /// Camera set and camera.
+        camera: Option<(String, String)>,
+    },
+    InstantReplay {
+        connection_id: i32,
+        /// Session time in milliseconds where the replay starts.
+        start_session_time: f32,
+        duration: Duration,
+        initial_focused_car_index: i32,
+        initial_camera_set: String,
+        initial_camera: String,
+    },
+}
+
+impl Request {
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::new();
+        let w = &mut data;
+        match self {
+            Request::Register {
+                display_name,
+                connection_password,
+                update_interval,
+                command_password,
+            } => {
+                w.push(REGISTER_COMMAND_APPLICATION);
+                w.push(PROTOCOL_VERSION);
+                write_string(w, display_name);
+                write_string(w, connection_password);
+                write_i32(w, update_interval.as_millis() as i32);
+                write_string(w, command_password);
+            }
+            Request::Unregister { connection_id } => {
+                w.push(UNREGISTER_COMMAND_APPLICATION);
+                write_i32(w, *connection_id);
+            }
+            Request::EntryList { connection_id } => {
+                w.push(REQUEST_ENTRY_LIST);
+                write_i32(w, *connection_id);
+            }
+            Request::TrackData { connection_id } => {
+                w.push(REQUEST_TRACK_DATA);
+                write_i32(w, *connection_id);
+            }
+            Request::ChangeHudPage {
+                connection_id,
+                hud_page,
+            } => {
+                w.push(CHANGE_HUD_PAGE);
+                write_i32(w, *connection_id);
+                write_string(w, hud_page);
+            }
+            Request::ChangeFocus {
+                connection_id,
+                car_index,
+                camera,
+            } => {
+                w.push(CHANGE_FOCUS);
+                write_i32(w, *connection_id);
+                w.push(car_index.is_some() as u8);
+                if let Some(car_index) = car_index {
+                    w.write_u16::<LittleEndian>(*car_index).unwrap();
+                }
+                w.push(camera.is_some() as u8);
+                if let Some((camera_set, camera)) = camera {
+                    write_string(w, camera_set);
+                    write_string(w, camera);
+                }
+            }
+            Request::InstantReplay {
+                connection_id,
+                start_session_time,
+                duration,
+                initial_focused_car_index,
+                initial_camera_set,
+                initial_camera,
+            } => {
+                w.push(INSTANT_REPLAY_REQUEST);
+                write_i32(w, *connection_id);
+                w.write_f32::<LittleEndian>(*start_session_time).unwrap();
+                w.write_f32::<LittleEndian>(duration.as_millis() as f32)
+                    .unwrap();
+                write_i32(w, *initial_focused_car_index);
+                write_string(w, initial_camera_set);
+                write_string(w, initial_camera);
+            }
+        }
+        data
+    }
+}
+
+/// Message received from ACC.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Message {
+    RegistrationResult(RegistrationResult),
+    RealtimeUpdate(RealtimeUpdate),
+    RealtimeCarUpdate(RealtimeCarUpdate),
+    EntryList(EntryList),
+    TrackData(TrackData),
+    EntryListCar(CarInfo),
+    BroadcastingEvent(BroadcastingEvent),
+}
+
+impl Message {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut r = ByteReader::new(data, "Broadcasting message");
+        Ok(match r.u8()? {
+            REGISTRATION_RESULT => Message::RegistrationResult(RegistrationResult {
+                connection_id: r.i32()?,
+                success: r.u8()? != 0,
+                is_read_only: r.u8()? == 0,
+                error_message: read_string(&mut r)?,
+            }),
+            REALTIME_UPDATE => Message::RealtimeUpdate(RealtimeUpdate::read(&mut r)?),
+            REALTIME_CAR_UPDATE => Message::RealtimeCarUpdate(RealtimeCarUpdate::read(&mut r)?),
+            ENTRY_LIST => {
+                let connection_id = r.i32()?;
+                let count = r.u16()?;
+                let car_indexes = (0..count).map(|_| r.u16()).collect::<Result<_>>()?;
+                Message::EntryList(EntryList {
+                    connection_id,
+                    car_indexes,
+                })
+            }
+            TRACK_DATA => Message::TrackData(TrackData::read(&mut r)?),
+            ENTRY_LIST_CAR => Message::EntryListCar(CarInfo::read(&mut r)?),
+            BROADCASTING_EVENT => Message::BroadcastingEvent(BroadcastingEvent {
+                event_type: BroadcastingEventType::from_raw(r.u8()?),
+                message: read_string(&mut r)?,
+                time_ms: r.i32()?,
+                car_index: r.i32()?,
+            }),
+            other => bail!("Unknown broadcasting message type {other}"),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegistrationResult {
+    pub connection_id: i32,
+    pub success: bool,
+    /// Commands are only accepted with the right command password.
+    pub is_read_only: bool,
+    pub error_message: String,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum SessionPhase {
+    None,
+    Starting,
+    PreFormation,
+    FormationLap,
+    PreSession,
+    Session,
+    SessionOver,
+    PostSession,
+    ResultUi,
+}
+
+impl SessionPhase {
+    fn from_raw(raw: u8) -> Self {
+        match raw {
+            1 => SessionPhase::Starting,
+            2 => SessionPhase::PreFormation,
+            3 => SessionPhase::FormationLap,
+            4 => SessionPhase::PreSession,
+            5 => SessionPhase::Session,
+            6 => SessionPhase::SessionOver,
+            7 => SessionPhase::PostSession,
+            8 => SessionPhase::ResultUi,
+            _ => SessionPhase::None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LapInfo {
+    /// Milliseconds, if the lap has a time.
+    pub lap_time_ms: Option<i32>,
+    pub car_index: u16,
+    pub driver_index: u16,
+    pub splits: Vec<Option<i32>>,
+    pub is_invalid: bool,
+    pub is_valid_for_best: bool,
+    pub is_outlap: bool,
+    pub is_inlap: bool,
+}
+
+impl LapInfo {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        let time = |v: i32| (v != i32::MAX).then_some(v);
+        let lap_time_ms = time(r.i32()?);
+        let car_index = r.u16()?;
+        let driver_index = r.u16()?;
+        let split_count = r.u8()?;
+        let splits = (0..split_count)
+            .map(|_| Ok(time(r.i32()?)))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            lap_time_ms,
+            car_index,
+            driver_index,
+            splits,
+            is_invalid: r.u8()? != 0,
+            is_valid_for_best: r.u8()? != 0,
+            is_outlap: r.u8()? != 0,
+            is_inlap: r.u8()? != 0,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RealtimeUpdate {
+    pub event_index: u16,
+    pub session_index: u16,
+    /// Raw session type, see [`RealtimeUpdate::session_type`].
+    pub session_type_raw: u8,
+    pub phase: SessionPhase,
+    /// Milliseconds.
+    pub session_time: f32,
+    pub session_end_time: f32,
+    pub focused_car_index: i32,
+    pub active_camera_set: String,
+    pub active_camera: String,
+    pub current_hud_page: String,
+    /// Session time and remaining time in milliseconds, while a replay is playing.
+    pub replay: Option<(f32, f32)>,
+    pub time_of_day: f32,
+    pub ambient_temp: u8,
+    pub track_temp: u8,
+    /// Fractions from 0 to 1.
+    pub clouds: f32,
+    pub rain_level: f32,
+    pub wetness: f32,
+    pub best_session_lap: LapInfo,
+}
+
+impl RealtimeUpdate {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        Ok(Self {
+            event_index: r.u16()?,
+            session_index: r.u16()?,
+            session_type_raw: r.u8()?,
+            phase: SessionPhase::from_raw(r.u8()?),
+            session_time: r.f32()?,
+            session_end_time: r.f32()?,
+            focused_car_index: r.i32()?,
+            active_camera_set: read_string(r)?,
+            active_camera: read_string(r)?,
+            current_hud_page: read_string(r)?,
+            replay: match r.u8()? {
+                0 => None,
+                _ => Some((r.f32()?, r.f32()?)),
+            },
+            time_of_day: r.f32()?,
+            ambient_temp: r.u8()?,
+            track_temp: r.u8()?,
+            clouds: r.u8()? as f32 / 10.0,
+            rain_level: r.u8()? as f32 / 10.0,
+            wetness: r.u8()? as f32 / 10.0,
+            best_session_lap: LapInfo::read(r)?,
+        })
+    }
+
+    pub fn session_type(&self) -> SessionType {
+        match self.session_type_raw {
+            0 => SessionType::Practice,
+            4 => SessionType::Qualify,
+            10 => SessionType::Race,
+            11 => SessionType::Hotlap,
+            12 => SessionType::HotStint,
+            13 => SessionType::HotlapSuperPole,
+            _ => SessionType::Unknown,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CarLocation {
+    None,
+    Track,
+    Pitlane,
+    PitEntry,
+    PitExit,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RealtimeCarUpdate {
+    pub car_index: u16,
+    pub driver_index: u16,
+    pub driver_count: u8,
+    /// -1 is reverse, 0 is neutral.
+    pub gear: i8,
+    pub world_pos_x: f32,
+    pub world_pos_y: f32,
+    pub yaw: f32,
+    pub car_location: CarLocation,
+    pub kmh: u16,
+    pub position: u16,
+    pub cup_position: u16,
+    pub track_position: u16,
+    pub spline_position: f32,
+    pub laps: u16,
+    /// Milliseconds.
+    pub delta: i32,
+    pub best_session_lap: LapInfo,
+    pub last_lap: LapInfo,
+    pub current_lap: LapInfo,
+}
+
+impl RealtimeCarUpdate {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        Ok(Self {
+            car_index: r.u16()?,
+            driver_index: r.u16()?,
+            driver_count: r.u8()?,
+            gear: (r.u8()? as i16 - 2) as i8,
+            world_pos_x: r.f32()?,
+            world_pos_y: r.f32()?,
+            yaw: r.f32()?,
+            car_location: match r.u8()? {
+                1 => CarLocation::Track,
+                2 => CarLocation::Pitlane,
+                3 => CarLocation::PitEntry,
+                4 => CarLocation::PitExit,
+                _ => CarLocation::None,
+            },
+            kmh: r.u16()?,
+            position: r.u16()?,
+            cup_position: r.u16()?,
+            track_position: r.u16()?,
+            spline_position: r.f32()?,
+            laps: r.u16()?,
+            delta: r.i32()?,
+            best_session_lap: LapInfo::read(r)?,
+            last_lap: LapInfo::read(r)?,
+            current_lap: LapInfo::read(r)?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EntryList {
+    pub connection_id: i32,
+    pub car_indexes: Vec<u16>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TrackData {
+    pub connection_id: i32,
+    pub track_name: String,
+    pub track_id: i32,
+    pub track_meters: i32,
+    /// Camera sets with the names of their cameras.
+    pub camera_sets: Vec<(String, Vec<String>)>,
+    pub hud_pages: Vec<String>,
+}
+
+impl TrackData {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        let connection_id = r.i32()?;
+        let track_name = read_string(r)?;
+        let track_id = r.i32()?;
+        let track_meters = r.i32()?;
+        let camera_sets = (0..r.u8()?)
+            .map(|_| {
+                let name = read_string(r)?;
+                let cameras = (0..r.u8()?)
+                    .map(|_| read_string(r))
+                    .collect::<Result<_>>()?;
+                Ok((name, cameras))
+            })
+            .collect::<Result<_>>()?;
+        let hud_pages = (0..r.u8()?)
+            .map(|_| read_string(r))
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            connection_id,
+            track_name,
+            track_id,
+            track_meters,
+            camera_sets,
+            hud_pages,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DriverInfo {
+    pub first_name: String,
+    pub last_name: String,
+    pub short_name: String,
+    pub category: u8,
+    pub nationality: u16,
+}
+
+/// Entry of a car, called `ENTRY_LIST_CAR` in the protocol.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CarInfo {
+    pub car_index: u16,
+    pub car_model_type: u8,
+    pub team_name: String,
+    pub race_number: i32,
+    pub cup_category: u8,
+    pub current_driver_index: u8,
+    pub nationality: u16,
+    pub drivers: Vec<DriverInfo>,
+}
+
+impl CarInfo {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        let car_index = r.u16()?;
+        let car_model_type = r.u8()?;
+        let team_name = read_string(r)?;
+        let race_number = r.i32()?;
+        let cup_category = r.u8()?;
+        let current_driver_index = r.u8()?;
+        let nationality = r.u16()?;
+        let drivers = (0..r.u8()?)
+            .map(|_| {
+                Ok(DriverInfo {
+                    first_name: read_string(r)?,
+                    last_name: read_string(r)?,
+                    short_name: read_string(r)?,
+                    category: r.u8()?,
+                    nationality: r.u16()?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            car_index,
+            car_model_type,
+            team_name,
+            race_number,
+            cup_category,
+            current_driver_index,
+            nationality,
+            drivers,
+        })
+    }
+
+    pub fn current_driver(&self) -> Option<&DriverInfo> {
+        self.drivers.get(self.current_driver_index as usize)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BroadcastingEventType {
+    None,
+    GreenFlag,
+    SessionOver,
+    PenaltyCommMsg,
+    Accident,
+    LapCompleted,
+    BestSessionLap,
+    BestPersonalLap,
+}
+
+impl BroadcastingEventType {
+    fn from_raw(raw: u8) -> Self {
+        match raw {
+            1 => BroadcastingEventType::GreenFlag,
+            2 => BroadcastingEventType::SessionOver,
+            3 => BroadcastingEventType::PenaltyCommMsg,
+            4 => BroadcastingEventType::Accident,
+            5 => BroadcastingEventType::LapCompleted,
+            6 => BroadcastingEventType::BestSessionLap,
+            7 => BroadcastingEventType::BestPersonalLap,
+            _ => BroadcastingEventType::None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BroadcastingEvent {
+    pub event_type: BroadcastingEventType,
+    pub message: String,
+    pub time_ms: i32,
+    pub car_index: i32,
+}
+
+fn write_i32(w: &mut Vec<u8>, value: i32) {
+    w.write_i32::<LittleEndian>(value).unwrap();
+}
+
+fn write_string(w: &mut Vec<u8>, value: &str) {
+    w.write_u16::<LittleEndian>(value.len() as u16).unwrap();
+    w.extend_from_slice(value.as_bytes());
+}
+
+fn read_string(r: &mut ByteReader) -> Result<String> {
+    let len = r.u16()? as usize;
+    Ok(String::from_utf8_lossy(r.take(len)?).into_owned())
+}
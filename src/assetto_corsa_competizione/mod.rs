@@ -1,4 +1,6 @@
 use crate::assetto_corsa::util;
+#[cfg(target_family = "unix")]
+pub use crate::assetto_corsa::util::SharedMemoryPages;
 pub use crate::assetto_corsa_competizione::data::{
     Aids, CarDamage, FlagType, GlobalFlags, Graphics, LapTiming, MfdPitstop, Penalty, Physics,
     RainIntensity, SessionType, StaticData, Status, Time, TrackGripStatus, Vector3, WheelInfo,
@@ -13,6 +15,7 @@ use uom::si::angular_velocity::revolution_per_minute;
 use uom::si::f64::{AngularVelocity, Velocity};
 use uom::si::velocity::kilometer_per_hour;
 
+pub mod broadcasting;
 mod conversions;
 mod data;
 mod shared_memory_data;
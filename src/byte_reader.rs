@@ -0,0 +1,79 @@
+use anyhow::{Context, Result};
+
+/// Cursor over a little-endian packet, failing with "`name` is truncated" past its end.
+pub struct ByteReader<'a> {
+    data: &'a [u8],
+    name: &'static str,
+}
+
+impl<'a> ByteReader<'a> {
+    pub fn new(data: &'a [u8], name: &'static str) -> Self {
+        Self { data, name }
+    }
+
+    /// Number of bytes which were not read yet.
+    pub fn remaining(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(..len)
+            .with_context(|| format!("{} is truncated", self.name))?;
+        self.data = &self.data[len..];
+        Ok(bytes)
+    }
+
+    pub fn bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        Ok(self.take(N)?.try_into()?)
+    }
+
+    pub fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    pub fn i8(&mut self) -> Result<i8> {
+        Ok(self.u8()? as i8)
+    }
+
+    pub fn bool(&mut self) -> Result<bool> {
+        Ok(self.u8()? != 0)
+    }
+
+    pub fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes()?))
+    }
+
+    pub fn i16(&mut self) -> Result<i16> {
+        Ok(i16::from_le_bytes(self.bytes()?))
+    }
+
+    pub fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.bytes()?))
+    }
+
+    pub fn i32(&mut self) -> Result<i32> {
+        Ok(i32::from_le_bytes(self.bytes()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes()?))
+    }
+
+    pub fn f32(&mut self) -> Result<f32> {
+        Ok(f32::from_le_bytes(self.bytes()?))
+    }
+
+    pub fn f64(&mut self) -> Result<f64> {
+        Ok(f64::from_le_bytes(self.bytes()?))
+    }
+
+    pub fn f32s<const N: usize>(&mut self) -> Result<[f32; N]> {
+        let mut values = [0.0; N];
+        for value in &mut values {
+            *value = self.f32()?;
+        }
+        Ok(values)
+    }
+}
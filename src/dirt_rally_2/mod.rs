@@ -1,6 +1,12 @@
-use crate::{Moment, Simetry};
-use anyhow::Result;
-use std::mem::transmute;
+//! Client for the "extradata" UDP telemetry of Codemasters games.
+//!
+//! Besides DiRT Rally 2.0, the same format is sent by DiRT Rally, DiRT 4, the GRID series and
+//! the F1 games up to F1 2017 in legacy mode. The `extradata` setting of the game decides how
+//! many fields are sent, which is detected from the packet length as [`ExtraData`].
+
+use crate::{Moment, Pedals, Simetry};
+use anyhow::{bail, Result};
+use byteorder::{ByteOrder, LittleEndian};
 use std::time::Duration;
 use tokio::net::UdpSocket;
 use uom::si::angular_velocity::revolution_per_minute;
@@ -32,18 +38,61 @@ impl Client {
         Ok(slf)
     }
 
+    /// Waits for the next packet, ignoring packets of unknown length.
     pub async fn next_sim_state(&self) -> Result<SimState> {
         let mut buffer = [0u8; PACKET_BUFFER_SIZE];
-        let (_bytes, _origin) = self.socket.recv_from(&mut buffer).await?;
-        Ok(unsafe { transmute(buffer) })
+        loop {
+            let len = self.socket.recv(&mut buffer).await?;
+            match SimState::decode(&buffer[..len]) {
+                Ok(sim_state) => return Ok(sim_state),
+                Err(err) => log::debug!("{err}"),
+            }
+        }
     }
 }
 
-const PACKET_BUFFER_SIZE: usize = 264;
+const PACKET_BUFFER_SIZE: usize = ExtraData::Level3.packet_len() + 1;
+
+/// Value of the `extradata` setting, each level adds fields to the previous one.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum ExtraData {
+    /// Motion, inputs and engine speed.
+    Level0,
+    /// Race position, assists, fuel, pits and sector times.
+    Level1,
+    /// Brake temperatures and tyre pressures.
+    Level2,
+    /// Lap count, track length, last lap time, engine limits and gear count.
+    Level3,
+}
+
+impl ExtraData {
+    pub const fn packet_len(self) -> usize {
+        let fields = match self {
+            ExtraData::Level0 => 38,
+            ExtraData::Level1 => 51,
+            ExtraData::Level2 => 59,
+            ExtraData::Level3 => 66,
+        };
+        fields * 4
+    }
 
-#[repr(C, packed(4))]
+    pub fn from_packet_len(len: usize) -> Option<Self> {
+        [
+            ExtraData::Level0,
+            ExtraData::Level1,
+            ExtraData::Level2,
+            ExtraData::Level3,
+        ]
+        .into_iter()
+        .find(|extra_data| extra_data.packet_len() == len)
+    }
+}
+
+/// Decoded packet, where fields beyond [`SimState::extra_data`] are zero.
 #[derive(Clone, Debug, PartialEq)]
 pub struct SimState {
+    pub extra_data: ExtraData,
     pub time: f32,
     pub time_of_current_lap: f32,
     pub distance_driven_on_current_lap: f32,
@@ -82,17 +131,124 @@ pub struct SimState {
     pub g_force_longitudinal: f32,
     pub current_lap: f32,
     pub speed_of_engine_rpm_div_10: f32,
-    unused1: [f32; 13],
+    // Sent with ExtraData::Level1 and above.
+    pub sli_pro_native_support: f32,
+    pub car_position: f32,
+    pub kers_level: f32,
+    pub kers_max_level: f32,
+    pub drs: f32,
+    pub traction_control: f32,
+    pub anti_lock_brakes: f32,
+    pub fuel_in_tank: f32,
+    pub fuel_capacity: f32,
+    pub in_pits: f32,
+    pub sector: f32,
+    pub time_of_sector_1: f32,
+    pub time_of_sector_2: f32,
+    // Sent with ExtraData::Level2 and above.
     pub temperature_brake_rear_left: f32,
     pub temperature_brake_rear_right: f32,
     pub temperature_brake_front_left: f32,
     pub temperature_brake_front_right: f32,
-    unused2: [f32; 5],
+    pub pressure_tyre_rear_left: f32,
+    pub pressure_tyre_rear_right: f32,
+    pub pressure_tyre_front_left: f32,
+    pub pressure_tyre_front_right: f32,
+    // Sent with ExtraData::Level3 and above.
+    pub team_info: f32,
     pub number_of_laps_in_total: f32,
     pub length_of_track_in_total: f32,
-    unused3: [f32; 1],
+    pub time_of_last_lap: f32,
     pub maximum_rpm_div_10: f32,
-    unused4: [u8; 8],
+    pub idle_rpm_div_10: f32,
+    pub maximum_gears: f32,
+}
+
+impl SimState {
+    /// Decode a packet of any extradata level.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let Some(extra_data) = ExtraData::from_packet_len(data.len()) else {
+            bail!("Unknown Codemasters packet of {} bytes", data.len());
+        };
+        let f = |idx: usize| {
+            data.get(idx * 4..idx * 4 + 4)
+                .map_or(0.0, LittleEndian::read_f32)
+        };
+        Ok(Self {
+            extra_data,
+            time: f(0),
+            time_of_current_lap: f(1),
+            distance_driven_on_current_lap: f(2),
+            distance_driven_overall: f(3),
+            position_x: f(4),
+            position_y: f(5),
+            position_z: f(6),
+            velocity_ms: f(7),
+            velocity_x: f(8),
+            velocity_y: f(9),
+            velocity_z: f(10),
+            roll_vector_x: f(11),
+            roll_vector_y: f(12),
+            roll_vector_z: f(13),
+            pitch_vector_x: f(14),
+            pitch_vector_y: f(15),
+            pitch_vector_z: f(16),
+            position_of_suspension_rear_left: f(17),
+            position_of_suspension_rear_right: f(18),
+            position_of_suspension_front_left: f(19),
+            position_of_suspension_front_right: f(20),
+            velocity_of_suspension_rear_left: f(21),
+            velocity_of_suspension_rear_right: f(22),
+            velocity_of_suspension_front_left: f(23),
+            velocity_of_suspension_front_right: f(24),
+            velocity_of_wheel_rear_left: f(25),
+            velocity_of_wheel_rear_right: f(26),
+            velocity_of_wheel_front_left: f(27),
+            velocity_of_wheel_front_right: f(28),
+            position_throttle: f(29),
+            position_steer: f(30),
+            position_brake: f(31),
+            position_clutch: f(32),
+            gear: f(33),
+            g_force_lateral: f(34),
+            g_force_longitudinal: f(35),
+            current_lap: f(36),
+            speed_of_engine_rpm_div_10: f(37),
+            sli_pro_native_support: f(38),
+            car_position: f(39),
+            kers_level: f(40),
+            kers_max_level: f(41),
+            drs: f(42),
+            traction_control: f(43),
+            anti_lock_brakes: f(44),
+            fuel_in_tank: f(45),
+            fuel_capacity: f(46),
+            in_pits: f(47),
+            sector: f(48),
+            time_of_sector_1: f(49),
+            time_of_sector_2: f(50),
+            temperature_brake_rear_left: f(51),
+            temperature_brake_rear_right: f(52),
+            temperature_brake_front_left: f(53),
+            temperature_brake_front_right: f(54),
+            pressure_tyre_rear_left: f(55),
+            pressure_tyre_rear_right: f(56),
+            pressure_tyre_front_left: f(57),
+            pressure_tyre_front_right: f(58),
+            team_info: f(59),
+            number_of_laps_in_total: f(60),
+            length_of_track_in_total: f(61),
+            time_of_last_lap: f(62),
+            maximum_rpm_div_10: f(63),
+            idle_rpm_div_10: f(64),
+            maximum_gears: f(65),
+        })
+    }
+
+    /// Check if the fields of `extra_data` were sent.
+    pub fn has(&self, extra_data: ExtraData) -> bool {
+        self.extra_data >= extra_data
+    }
 }
 
 #[async_trait::async_trait]
@@ -126,8 +282,33 @@ impl Moment for SimState {
     }
 
     fn vehicle_max_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        if !self.has(ExtraData::Level3) {
+            return None;
+        }
         Some(AngularVelocity::new::<revolution_per_minute>(
             self.maximum_rpm_div_10 as f64 * 10.0,
         ))
     }
+
+    fn is_vehicle_in_pit_lane(&self) -> Option<bool> {
+        if !self.has(ExtraData::Level1) {
+            return None;
+        }
+        Some(self.in_pits != 0.0)
+    }
+
+    fn is_drs_engaged(&self) -> Option<bool> {
+        if !self.has(ExtraData::Level1) {
+            return None;
+        }
+        Some(self.drs != 0.0)
+    }
+
+    fn pedals(&self) -> Option<Pedals> {
+        Some(Pedals {
+            throttle: self.position_throttle as f64,
+            brake: self.position_brake as f64,
+            clutch: self.position_clutch as f64,
+        })
+    }
 }
@@ -0,0 +1,156 @@
+//! Client for the UDP telemetry of EA SPORTS WRC.
+//!
+//! The game sends packets of the structure configured in the `udp` folder of its telemetry
+//! settings, built from the channels listed in `readme/channels.json`. The same files are loaded
+//! as a [`Structure`] to decode the packets into named channels.
+
+use crate::{Moment, Pedals, Simetry};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use uom::si::angular_velocity::revolution_per_minute;
+use uom::si::f64::{AngularVelocity, Velocity};
+use uom::si::velocity::meter_per_second;
+
+pub use structure::{Channel, ChannelType, Packet, PacketDefinition, Structure, Value};
+
+mod structure;
+
+/// Packet sent while driving, other packets only update the channels.
+const UPDATE_PACKET: &str = "session_update";
+
+/// Latest value of every channel received in the session.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SimState {
+    pub channels: HashMap<String, Value>,
+}
+
+impl SimState {
+    pub fn get(&self, channel: &str) -> Option<&Value> {
+        self.channels.get(channel)
+    }
+
+    pub fn f64(&self, channel: &str) -> Option<f64> {
+        self.get(channel)?.as_f64()
+    }
+
+    /// Progress through the stage, from 0 to 1.
+    pub fn stage_progress(&self) -> Option<f64> {
+        self.f64("stage_progress")
+    }
+}
+
+#[derive(Debug)]
+pub struct Client {
+    socket: UdpSocket,
+    structure: Structure,
+    state: SimState,
+}
+
+impl Client {
+    /// Port of the game's default configuration, which DiRT Rally 2.0 and the F1 games also
+    /// send to, so only one of their clients can listen at a time. Change the port in the
+    /// `telemetry/config.json` of the game to run them side by side.
+    pub const DEFAULT_URI: &'static str = "127.0.0.1:20777";
+
+    pub async fn connect(uri: &str, structure: Structure, retry_delay: Duration) -> Self {
+        loop {
+            if let Ok(client) = Self::try_connect(uri, structure.clone()).await {
+                return client;
+            }
+            tokio::time::sleep(retry_delay).await;
+        }
+    }
+
+    /// Listen on `uri` until a packet of `structure` is received.
+    pub async fn try_connect(uri: &str, structure: Structure) -> Result<Self> {
+        Self::try_connect_with_socket(UdpSocket::bind(uri).await?, structure).await
+    }
+
+    /// Listen on an already bound `socket` until a packet of `structure` is received.
+    pub async fn try_connect_with_socket(socket: UdpSocket, structure: Structure) -> Result<Self> {
+        let mut client = Self {
+            socket,
+            structure,
+            state: SimState::default(),
+        };
+        client.next_packet().await?;
+        Ok(client)
+    }
+
+    /// Waits for the next packet of the structure, ignoring other packets.
+    pub async fn next_packet(&mut self) -> Result<Packet> {
+        let mut buffer = [0u8; 2048];
+        loop {
+            let len = self.socket.recv(&mut buffer).await?;
+            match self.structure.decode(&buffer[..len]) {
+                Ok(packet) => {
+                    self.state.channels.extend(packet.channels.clone());
+                    return Ok(packet);
+                }
+                Err(err) => log::debug!("{err}"),
+            }
+        }
+    }
+
+    /// Waits for the next `session_update` packet.
+    pub async fn next_sim_state(&mut self) -> Result<SimState> {
+        loop {
+            if self.next_packet().await?.id == UPDATE_PACKET {
+                return Ok(self.state.clone());
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Simetry for Client {
+    fn name(&self) -> &str {
+        "EASportsWRC"
+    }
+
+    async fn next_moment(&mut self) -> Option<Box<dyn Moment + Send + Sync + 'static>> {
+        Some(Box::new(self.next_sim_state().await.ok()?))
+    }
+}
+
+impl Moment for SimState {
+    fn vehicle_gear(&self) -> Option<i8> {
+        let gear = self.get("vehicle_gear_index")?.as_i64()?;
+        let index = |channel| self.get(channel).and_then(Value::as_i64);
+        if Some(gear) == index("vehicle_gear_index_reverse") {
+            Some(-1)
+        } else if Some(gear) == index("vehicle_gear_index_neutral") {
+            Some(0)
+        } else {
+            Some(gear as i8)
+        }
+    }
+
+    fn vehicle_velocity(&self) -> Option<Velocity> {
+        Some(Velocity::new::<meter_per_second>(
+            self.f64("vehicle_speed")?,
+        ))
+    }
+
+    fn vehicle_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(
+            self.f64("vehicle_engine_rpm_current")?,
+        ))
+    }
+
+    fn vehicle_max_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(
+            self.f64("vehicle_engine_rpm_max")?,
+        ))
+    }
+
+    fn pedals(&self) -> Option<Pedals> {
+        Some(Pedals {
+            throttle: self.f64("vehicle_throttle")?,
+            brake: self.f64("vehicle_brake")?,
+            clutch: self.f64("vehicle_clutch").unwrap_or_default(),
+        })
+    }
+}
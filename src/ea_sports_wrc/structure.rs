@@ -0,0 +1,275 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Channel which carries the four character code of the packet in its header.
+const FOUR_CC_CHANNEL: &str = "packet_4cc";
+/// Four character codes of the packets which the game sends.
+const FOUR_CCS: [(&str, &str); 5] = [
+    ("session_start", "sess"),
+    ("session_update", "sesu"),
+    ("session_end", "sese"),
+    ("session_pause", "sesp"),
+    ("session_resume", "sesr"),
+];
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelType {
+    Boolean,
+    Uint8,
+    Uint16,
+    Uint32,
+    Uint64,
+    Int8,
+    Int16,
+    Int32,
+    Int64,
+    Float32,
+    Float64,
+    FourCc,
+}
+
+impl ChannelType {
+    pub fn size(self) -> usize {
+        match self {
+            ChannelType::Boolean | ChannelType::Uint8 | ChannelType::Int8 => 1,
+            ChannelType::Uint16 | ChannelType::Int16 => 2,
+            ChannelType::Uint32
+            | ChannelType::Int32
+            | ChannelType::Float32
+            | ChannelType::FourCc => 4,
+            ChannelType::Uint64 | ChannelType::Int64 | ChannelType::Float64 => 8,
+        }
+    }
+
+    /// Decode a value from exactly [`ChannelType::size`] bytes.
+    fn decode(self, data: &[u8]) -> Value {
+        let bytes = |n: usize| -> [u8; 8] {
+            let mut bytes = [0u8; 8];
+            bytes[..n].copy_from_slice(&data[..n]);
+            bytes
+        };
+        match self {
+            ChannelType::Boolean => Value::Boolean(data[0] != 0),
+            ChannelType::Uint8 => Value::Unsigned(data[0] as u64),
+            ChannelType::Uint16 | ChannelType::Uint32 | ChannelType::Uint64 => {
+                Value::Unsigned(u64::from_le_bytes(bytes(self.size())))
+            }
+            ChannelType::Int8 => Value::Signed(data[0] as i8 as i64),
+            ChannelType::Int16 => Value::Signed(i16::from_le_bytes([data[0], data[1]]) as i64),
+            ChannelType::Int32 => {
+                Value::Signed(i32::from_le_bytes(data.try_into().unwrap()) as i64)
+            }
+            ChannelType::Int64 => Value::Signed(i64::from_le_bytes(data.try_into().unwrap())),
+            ChannelType::Float32 => {
+                Value::Float(f32::from_le_bytes(data.try_into().unwrap()) as f64)
+            }
+            ChannelType::Float64 => Value::Float(f64::from_le_bytes(data.try_into().unwrap())),
+            ChannelType::FourCc => Value::FourCc(String::from_utf8_lossy(data).into_owned()),
+        }
+    }
+}
+
+/// Channel as described by `channels.json` of the game.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Channel {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub channel_type: ChannelType,
+    #[serde(default)]
+    pub units: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Boolean(bool),
+    Unsigned(u64),
+    Signed(i64),
+    Float(f64),
+    FourCc(String),
+}
+
+impl Value {
+    /// Numeric value, with booleans as 0 or 1.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Boolean(v) => Some(*v as u8 as f64),
+            Value::Unsigned(v) => Some(*v as f64),
+            Value::Signed(v) => Some(*v as f64),
+            Value::Float(v) => Some(*v),
+            Value::FourCc(_) => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Boolean(v) => Some(*v as i64),
+            Value::Unsigned(v) => i64::try_from(*v).ok(),
+            Value::Signed(v) => Some(*v),
+            Value::Float(_) | Value::FourCc(_) => None,
+        }
+    }
+}
+
+/// Packet of a structure, with the header channels followed by the other channels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PacketDefinition {
+    pub id: String,
+    pub channels: Vec<Channel>,
+}
+
+impl PacketDefinition {
+    pub fn size(&self) -> usize {
+        self.channels
+            .iter()
+            .map(|channel| channel.channel_type.size())
+            .sum()
+    }
+
+    fn four_cc_offset(&self) -> Option<usize> {
+        let idx = self
+            .channels
+            .iter()
+            .position(|channel| channel.id == FOUR_CC_CHANNEL)?;
+        Some(
+            self.channels[..idx]
+                .iter()
+                .map(|c| c.channel_type.size())
+                .sum(),
+        )
+    }
+
+    /// Check if `data` is a packet of this definition.
+    fn matches(&self, data: &[u8]) -> bool {
+        if data.len() != self.size() {
+            return false;
+        }
+        let four_cc = FOUR_CCS
+            .iter()
+            .find(|(id, _)| *id == self.id)
+            .map(|(_, four_cc)| four_cc.as_bytes());
+        match (self.four_cc_offset(), four_cc) {
+            (Some(offset), Some(four_cc)) => &data[offset..offset + 4] == four_cc,
+            _ => true,
+        }
+    }
+}
+
+/// Packet decoded into named channels.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Packet {
+    /// ID of the packet definition, such as `session_update`.
+    pub id: String,
+    pub channels: HashMap<String, Value>,
+}
+
+/// Packet structure, as configured in the `udp` folder of the game's telemetry settings.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Structure {
+    pub id: String,
+    pub packets: Vec<PacketDefinition>,
+}
+
+#[derive(Deserialize)]
+struct ChannelsFile {
+    channels: Vec<Channel>,
+}
+
+#[derive(Deserialize)]
+struct StructureFile {
+    id: String,
+    packets: Vec<PacketFile>,
+}
+
+#[derive(Deserialize)]
+struct PacketFile {
+    id: String,
+    #[serde(default)]
+    header: HeaderFile,
+    #[serde(default)]
+    channels: Vec<String>,
+}
+
+#[derive(Default, Deserialize)]
+struct HeaderFile {
+    #[serde(default)]
+    channels: Vec<String>,
+}
+
+impl Structure {
+    /// Load the channels, usually `readme/channels.json`, and a structure such as `udp/wrc.json`
+    /// from the telemetry folder of the game.
+    pub fn load(channels_path: &Path, structure_path: &Path) -> Result<Self> {
+        let channels = std::fs::read_to_string(channels_path)
+            .with_context(|| format!("Reading {channels_path:?} failed"))?;
+        let structure = std::fs::read_to_string(structure_path)
+            .with_context(|| format!("Reading {structure_path:?} failed"))?;
+        Self::from_json(&channels, &structure)
+    }
+
+    pub fn from_json(channels: &str, structure: &str) -> Result<Self> {
+        let channels: ChannelsFile = serde_json::from_str(channels)?;
+        let channels: HashMap<_, _> = channels
+            .channels
+            .into_iter()
+            .map(|channel| (channel.id.clone(), channel))
+            .collect();
+        let structure: StructureFile = serde_json::from_str(structure)?;
+        let packets = structure
+            .packets
+            .into_iter()
+            .map(|packet| {
+                let channels = packet
+                    .header
+                    .channels
+                    .iter()
+                    .chain(&packet.channels)
+                    .map(|id| {
+                        channels
+                            .get(id)
+                            .cloned()
+                            .with_context(|| format!("Unknown channel {id:?} in {:?}", packet.id))
+                    })
+                    .collect::<Result<_>>()?;
+                Ok(PacketDefinition {
+                    id: packet.id,
+                    channels,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            id: structure.id,
+            packets,
+        })
+    }
+
+    /// Decode a packet of any definition of the structure.
+    pub fn decode(&self, data: &[u8]) -> Result<Packet> {
+        let Some(definition) = self.packets.iter().find(|packet| packet.matches(data)) else {
+            bail!(
+                "Packet of {} bytes matches no packet of structure {:?}",
+                data.len(),
+                self.id
+            );
+        };
+        let mut offset = 0;
+        let channels = definition
+            .channels
+            .iter()
+            .map(|channel| {
+                let size = channel.channel_type.size();
+                let value = channel.channel_type.decode(&data[offset..offset + size]);
+                offset += size;
+                (channel.id.clone(), value)
+            })
+            .collect();
+        Ok(Packet {
+            id: definition.id.clone(),
+            channels,
+        })
+    }
+}
@@ -0,0 +1,219 @@
+//! Client for the UDP telemetry of the F1 games by Codemasters and EA.
+//!
+//! The telemetry has to be enabled in the game settings, with the UDP format set to one of
+//! the years of [`PacketFormat`].
+
+use crate::{Moment, Pedals, RacingFlags, Simetry};
+use anyhow::Result;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use uom::si::angular_velocity::revolution_per_minute;
+use uom::si::f64::{AngularVelocity, Velocity};
+use uom::si::velocity::kilometer_per_hour;
+
+pub use packets::{
+    CarDamage, CarMotion, CarSetup, CarStatus, CarTelemetry, CarTelemetryPacket, Event, FiaFlag,
+    FinalClassification, Header, LapData, LapPositions, LiveryColour, MarshalZone, MotionEx,
+    Packet, PacketData, PacketFormat, Participant, Session, TyreSet, TyreSets, TyreStint,
+    WeatherForecastSample, MAX_CARS,
+};
+
+mod packets;
+
+/// Value of [`LapData::result_status`] once the car finished.
+const RESULT_STATUS_FINISHED: u8 = 3;
+
+/// Telemetry of the player car, with the latest of the packets sent less often.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimState {
+    pub header: Header,
+    pub telemetry: CarTelemetry,
+    pub status: Option<CarStatus>,
+    pub lap_data: Option<LapData>,
+    pub motion: Option<CarMotion>,
+    pub session: Option<Session>,
+}
+
+#[derive(Debug)]
+pub struct Client {
+    socket: UdpSocket,
+    session: Option<Session>,
+    motion: Option<Vec<CarMotion>>,
+    lap_data: Option<Vec<LapData>>,
+    status: Option<Vec<CarStatus>>,
+    participants: Option<Vec<Participant>>,
+}
+
+impl Client {
+    /// Listens on all interfaces, as the game often runs on a console. Port 20777 is also used
+    /// by [`crate::dirt_rally_2::Client::DEFAULT_URI`] on the loopback address, so only one
+    /// of them can be connected at a time.
+    pub const DEFAULT_URI: &'static str = "0.0.0.0:20777";
+
+    pub async fn connect(uri: &str, retry_delay: Duration) -> Self {
+        loop {
+            if let Ok(client) = Self::try_connect(uri).await {
+                return client;
+            }
+            tokio::time::sleep(retry_delay).await;
+        }
+    }
+
+    /// Listen on `uri` until a packet is received.
+    pub async fn try_connect(uri: &str) -> Result<Self> {
+        Self::try_connect_with_socket(UdpSocket::bind(uri).await?).await
+    }
+
+    /// Listen on an already bound `socket` until a packet is received.
+    pub async fn try_connect_with_socket(socket: UdpSocket) -> Result<Self> {
+        let mut client = Self {
+            socket,
+            session: None,
+            motion: None,
+            lap_data: None,
+            status: None,
+            participants: None,
+        };
+        client.next_packet().await?;
+        Ok(client)
+    }
+
+    pub fn session(&self) -> Option<&Session> {
+        self.session.as_ref()
+    }
+
+    /// Participants of the session, indexed like the cars of the other packets.
+    pub fn participants(&self) -> Option<&[Participant]> {
+        self.participants.as_deref()
+    }
+
+    /// Latest lap data of each car.
+    pub fn lap_data(&self) -> Option<&[LapData]> {
+        self.lap_data.as_deref()
+    }
+
+    /// Waits for the next packet, ignoring packets that can not be decoded.
+    pub async fn next_packet(&mut self) -> Result<Packet> {
+        let mut buffer = vec![0u8; 2048];
+        loop {
+            let len = self.socket.recv(&mut buffer).await?;
+            let packet = match Packet::decode(&buffer[..len]) {
+                Ok(packet) => packet,
+                Err(err) => {
+                    log::debug!("Ignoring F1 packet: {err}");
+                    continue;
+                }
+            };
+            match &packet.data {
+                PacketData::Session(session) => self.session = Some(*session.clone()),
+                PacketData::Motion(motion) => self.motion = Some(motion.clone()),
+                PacketData::LapData(lap_data) => self.lap_data = Some(lap_data.clone()),
+                PacketData::CarStatus(status) => self.status = Some(status.clone()),
+                PacketData::Participants(participants) => {
+                    self.participants = Some(participants.clone())
+                }
+                _ => {}
+            }
+            return Ok(packet);
+        }
+    }
+
+    /// Waits for the next car telemetry packet and combines it with the latest other packets.
+    pub async fn next_sim_state(&mut self) -> Result<SimState> {
+        loop {
+            let packet = self.next_packet().await?;
+            let PacketData::CarTelemetry(telemetry) = packet.data else {
+                continue;
+            };
+            let player = packet.header.player_car_index as usize;
+            let Some(car_telemetry) = telemetry.cars.get(player) else {
+                continue;
+            };
+            return Ok(SimState {
+                telemetry: car_telemetry.clone(),
+                status: player_entry(&self.status, player),
+                lap_data: player_entry(&self.lap_data, player),
+                motion: player_entry(&self.motion, player),
+                session: self.session.clone(),
+                header: packet.header,
+            });
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Simetry for Client {
+    fn name(&self) -> &str {
+        "F1"
+    }
+
+    async fn next_moment(&mut self) -> Option<Box<dyn Moment + Send + Sync + 'static>> {
+        Some(Box::new(self.next_sim_state().await.ok()?))
+    }
+}
+
+impl Moment for SimState {
+    fn vehicle_gear(&self) -> Option<i8> {
+        Some(self.telemetry.gear)
+    }
+
+    fn vehicle_velocity(&self) -> Option<Velocity> {
+        Some(Velocity::new::<kilometer_per_hour>(
+            self.telemetry.speed as f64,
+        ))
+    }
+
+    fn vehicle_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(
+            self.telemetry.engine_rpm as f64,
+        ))
+    }
+
+    fn vehicle_max_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(
+            self.status.as_ref()?.max_rpm as f64,
+        ))
+    }
+
+    fn is_pit_limiter_engaged(&self) -> Option<bool> {
+        Some(self.status.as_ref()?.pit_limiter_status)
+    }
+
+    fn is_vehicle_in_pit_lane(&self) -> Option<bool> {
+        Some(self.lap_data.as_ref()?.pit_status != 0)
+    }
+
+    fn rev_lights(&self) -> Option<f64> {
+        Some(self.telemetry.rev_lights_percent as f64 / 100.0)
+    }
+
+    fn is_drs_engaged(&self) -> Option<bool> {
+        Some(self.telemetry.drs)
+    }
+
+    fn flags(&self) -> Option<RacingFlags> {
+        let status = self.status.as_ref()?;
+        Some(RacingFlags {
+            green: status.vehicle_fia_flags == FiaFlag::Green,
+            yellow: status.vehicle_fia_flags == FiaFlag::Yellow,
+            blue: status.vehicle_fia_flags == FiaFlag::Blue,
+            checkered: self
+                .lap_data
+                .as_ref()
+                .is_some_and(|lap_data| lap_data.result_status == RESULT_STATUS_FINISHED),
+            ..Default::default()
+        })
+    }
+
+    fn pedals(&self) -> Option<Pedals> {
+        Some(Pedals {
+            throttle: self.telemetry.throttle as f64,
+            brake: self.telemetry.brake as f64,
+            clutch: self.telemetry.clutch as f64 / 100.0,
+        })
+    }
+}
+
+fn player_entry<T: Clone>(cars: &Option<Vec<T>>, player: usize) -> Option<T> {
+    cars.as_ref()?.get(player).cloned()
+}
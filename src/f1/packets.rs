@@ -0,0 +1,1133 @@
+//! Decoding of the UDP packets, laid out as in the specifications published with each game.
+//!
+//! Arrays of the four wheels are ordered rear left, rear right, front left, front right.
+
+use crate::byte_reader::ByteReader;
+use anyhow::{bail, Result};
+
+/// Number of cars in the arrays of each packet.
+pub const MAX_CARS: usize = 22;
+const MAX_MARSHAL_ZONES: usize = 21;
+const MAX_TYRE_SETS: usize = 20;
+const MAX_TYRE_STINTS: usize = 8;
+const MAX_LIVERY_COLOURS: usize = 4;
+const MAX_LAP_POSITIONS: usize = 50;
+
+const MOTION: u8 = 0;
+const SESSION: u8 = 1;
+const LAP_DATA: u8 = 2;
+const EVENT: u8 = 3;
+const PARTICIPANTS: u8 = 4;
+const CAR_SETUPS: u8 = 5;
+const CAR_TELEMETRY: u8 = 6;
+const CAR_STATUS: u8 = 7;
+const FINAL_CLASSIFICATION: u8 = 8;
+const CAR_DAMAGE: u8 = 10;
+const TYRE_SETS: u8 = 12;
+const MOTION_EX: u8 = 13;
+const LAP_POSITIONS: u8 = 15;
+
+/// Year of the packet format, set in the game's telemetry settings.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub enum PacketFormat {
+    Year2023,
+    Year2024,
+    Year2025,
+}
+
+impl PacketFormat {
+    fn from_raw(raw: u16) -> Result<Self> {
+        Ok(match raw {
+            2023 => PacketFormat::Year2023,
+            2024 => PacketFormat::Year2024,
+            2025 => PacketFormat::Year2025,
+            _ => bail!("Unsupported packet format {raw}"),
+        })
+    }
+
+    fn weather_forecast_samples(self) -> usize {
+        match self {
+            PacketFormat::Year2023 => 56,
+            PacketFormat::Year2024 | PacketFormat::Year2025 => 64,
+        }
+    }
+
+    fn name_len(self) -> usize {
+        match self {
+            PacketFormat::Year2023 | PacketFormat::Year2024 => 48,
+            PacketFormat::Year2025 => 32,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Header {
+    pub packet_format: PacketFormat,
+    pub game_year: u8,
+    pub game_major_version: u8,
+    pub game_minor_version: u8,
+    pub packet_version: u8,
+    pub packet_id: u8,
+    pub session_uid: u64,
+    pub session_time: f32,
+    pub frame_identifier: u32,
+    pub overall_frame_identifier: u32,
+    pub player_car_index: u8,
+    /// 255 without a second player.
+    pub secondary_player_car_index: u8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Packet {
+    pub header: Header,
+    pub data: PacketData,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PacketData {
+    Motion(Vec<CarMotion>),
+    Session(Box<Session>),
+    LapData(Vec<LapData>),
+    Event(Event),
+    Participants(Vec<Participant>),
+    CarSetups(Vec<CarSetup>),
+    CarTelemetry(CarTelemetryPacket),
+    CarStatus(Vec<CarStatus>),
+    FinalClassification(Vec<FinalClassification>),
+    CarDamage(Vec<CarDamage>),
+    TyreSets(TyreSets),
+    MotionEx(Box<MotionEx>),
+    /// Since 2025.
+    LapPositions(LapPositions),
+    /// Packet which is not decoded, by ID.
+    Other(u8),
+}
+
+impl Packet {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let mut r = ByteReader::new(data, "F1 packet");
+        let header = Header {
+            packet_format: PacketFormat::from_raw(r.u16()?)?,
+            game_year: r.u8()?,
+            game_major_version: r.u8()?,
+            game_minor_version: r.u8()?,
+            packet_version: r.u8()?,
+            packet_id: r.u8()?,
+            session_uid: r.u64()?,
+            session_time: r.f32()?,
+            frame_identifier: r.u32()?,
+            overall_frame_identifier: r.u32()?,
+            player_car_index: r.u8()?,
+            secondary_player_car_index: r.u8()?,
+        };
+        let format = header.packet_format;
+        let r = &mut r;
+        let data = match header.packet_id {
+            MOTION => PacketData::Motion(cars(r, CarMotion::read)?),
+            SESSION => PacketData::Session(Box::new(Session::read(r, format)?)),
+            LAP_DATA => PacketData::LapData(cars(r, |r| LapData::read(r, format))?),
+            EVENT => PacketData::Event(Event::read(r)?),
+            PARTICIPANTS => {
+                let active_cars = r.u8()? as usize;
+                let mut participants = cars(r, |r| Participant::read(r, format))?;
+                participants.truncate(active_cars);
+                PacketData::Participants(participants)
+            }
+            CAR_SETUPS => PacketData::CarSetups(cars(r, |r| CarSetup::read(r, format))?),
+            CAR_TELEMETRY => PacketData::CarTelemetry(CarTelemetryPacket {
+                cars: cars(r, CarTelemetry::read)?,
+                mfd_panel_index: r.u8()?,
+                mfd_panel_index_secondary_player: r.u8()?,
+                suggested_gear: r.i8()?,
+            }),
+            CAR_STATUS => PacketData::CarStatus(cars(r, CarStatus::read)?),
+            FINAL_CLASSIFICATION => {
+                let num_cars = r.u8()? as usize;
+                let mut classification = cars(r, |r| FinalClassification::read(r, format))?;
+                classification.truncate(num_cars);
+                PacketData::FinalClassification(classification)
+            }
+            CAR_DAMAGE => PacketData::CarDamage(cars(r, |r| CarDamage::read(r, format))?),
+            TYRE_SETS => PacketData::TyreSets(TyreSets::read(r)?),
+            MOTION_EX => PacketData::MotionEx(Box::new(MotionEx::read(r, format)?)),
+            LAP_POSITIONS => PacketData::LapPositions(LapPositions::read(r)?),
+            other => PacketData::Other(other),
+        };
+        Ok(Self { header, data })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CarMotion {
+    pub world_position: [f32; 3],
+    pub world_velocity: [f32; 3],
+    /// Normalized.
+    pub world_forward_dir: [f32; 3],
+    pub world_right_dir: [f32; 3],
+    pub g_force_lateral: f32,
+    pub g_force_longitudinal: f32,
+    pub g_force_vertical: f32,
+    /// Radians.
+    pub yaw: f32,
+    pub pitch: f32,
+    pub roll: f32,
+}
+
+impl CarMotion {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        let direction = |r: &mut ByteReader| -> Result<[f32; 3]> {
+            let mut dir = [0.0; 3];
+            for value in &mut dir {
+                *value = r.i16()? as f32 / 32767.0;
+            }
+            Ok(dir)
+        };
+        Ok(Self {
+            world_position: r.f32s()?,
+            world_velocity: r.f32s()?,
+            world_forward_dir: direction(r)?,
+            world_right_dir: direction(r)?,
+            g_force_lateral: r.f32()?,
+            g_force_longitudinal: r.f32()?,
+            g_force_vertical: r.f32()?,
+            yaw: r.f32()?,
+            pitch: r.f32()?,
+            roll: r.f32()?,
+        })
+    }
+}
+
+/// Flag of a marshal zone or of a car.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FiaFlag {
+    Unknown,
+    None,
+    Green,
+    Blue,
+    Yellow,
+}
+
+impl FiaFlag {
+    fn from_raw(raw: i8) -> Self {
+        match raw {
+            0 => FiaFlag::None,
+            1 => FiaFlag::Green,
+            2 => FiaFlag::Blue,
+            3 => FiaFlag::Yellow,
+            _ => FiaFlag::Unknown,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarshalZone {
+    /// Fraction of the lap where the zone starts.
+    pub zone_start: f32,
+    pub zone_flag: FiaFlag,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct WeatherForecastSample {
+    pub session_type: u8,
+    /// Minutes.
+    pub time_offset: u8,
+    pub weather: u8,
+    pub track_temperature: i8,
+    pub track_temperature_change: i8,
+    pub air_temperature: i8,
+    pub air_temperature_change: i8,
+    pub rain_percentage: u8,
+}
+
+/// Start of the session packet, leaving out the settings of assists and rules.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Session {
+    /// 0 is clear, up to 5 for storm.
+    pub weather: u8,
+    /// Celsius.
+    pub track_temperature: i8,
+    pub air_temperature: i8,
+    pub total_laps: u8,
+    /// Meters.
+    pub track_length: u16,
+    pub session_type: u8,
+    /// -1 is unknown.
+    pub track_id: i8,
+    pub formula: u8,
+    /// Seconds.
+    pub session_time_left: u16,
+    pub session_duration: u16,
+    /// Kilometers per hour.
+    pub pit_speed_limit: u8,
+    pub game_paused: bool,
+    pub is_spectating: bool,
+    pub spectator_car_index: u8,
+    pub sli_pro_native_support: bool,
+    pub marshal_zones: Vec<MarshalZone>,
+    /// 0 is none, 1 full safety car, 2 virtual safety car, 3 formation lap.
+    pub safety_car_status: u8,
+    pub network_game: bool,
+    pub weather_forecast_samples: Vec<WeatherForecastSample>,
+    /// 0 is perfect, 1 approximate.
+    pub forecast_accuracy: u8,
+    pub ai_difficulty: u8,
+    pub season_link_identifier: u32,
+    pub weekend_link_identifier: u32,
+    pub session_link_identifier: u32,
+    pub pit_stop_window_ideal_lap: u8,
+    pub pit_stop_window_latest_lap: u8,
+    pub pit_stop_rejoin_position: u8,
+}
+
+impl Session {
+    fn read(r: &mut ByteReader, format: PacketFormat) -> Result<Self> {
+        let weather = r.u8()?;
+        let track_temperature = r.i8()?;
+        let air_temperature = r.i8()?;
+        let total_laps = r.u8()?;
+        let track_length = r.u16()?;
+        let session_type = r.u8()?;
+        let track_id = r.i8()?;
+        let formula = r.u8()?;
+        let session_time_left = r.u16()?;
+        let session_duration = r.u16()?;
+        let pit_speed_limit = r.u8()?;
+        let game_paused = r.bool()?;
+        let is_spectating = r.bool()?;
+        let spectator_car_index = r.u8()?;
+        let sli_pro_native_support = r.bool()?;
+        let num_marshal_zones = r.u8()? as usize;
+        let mut marshal_zones = (0..MAX_MARSHAL_ZONES)
+            .map(|_| {
+                Ok(MarshalZone {
+                    zone_start: r.f32()?,
+                    zone_flag: FiaFlag::from_raw(r.i8()?),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        marshal_zones.truncate(num_marshal_zones);
+        let safety_car_status = r.u8()?;
+        let network_game = r.bool()?;
+        let num_samples = r.u8()? as usize;
+        let mut weather_forecast_samples = (0..format.weather_forecast_samples())
+            .map(|_| {
+                Ok(WeatherForecastSample {
+                    session_type: r.u8()?,
+                    time_offset: r.u8()?,
+                    weather: r.u8()?,
+                    track_temperature: r.i8()?,
+                    track_temperature_change: r.i8()?,
+                    air_temperature: r.i8()?,
+                    air_temperature_change: r.i8()?,
+                    rain_percentage: r.u8()?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        weather_forecast_samples.truncate(num_samples);
+        Ok(Self {
+            weather,
+            track_temperature,
+            air_temperature,
+            total_laps,
+            track_length,
+            session_type,
+            track_id,
+            formula,
+            session_time_left,
+            session_duration,
+            pit_speed_limit,
+            game_paused,
+            is_spectating,
+            spectator_car_index,
+            sli_pro_native_support,
+            marshal_zones,
+            safety_car_status,
+            network_game,
+            weather_forecast_samples,
+            forecast_accuracy: r.u8()?,
+            ai_difficulty: r.u8()?,
+            season_link_identifier: r.u32()?,
+            weekend_link_identifier: r.u32()?,
+            session_link_identifier: r.u32()?,
+            pit_stop_window_ideal_lap: r.u8()?,
+            pit_stop_window_latest_lap: r.u8()?,
+            pit_stop_rejoin_position: r.u8()?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct LapData {
+    /// Milliseconds.
+    pub last_lap_time: u32,
+    pub current_lap_time: u32,
+    pub sector1_time: u32,
+    pub sector2_time: u32,
+    pub delta_to_car_in_front: u32,
+    pub delta_to_race_leader: u32,
+    /// Meters, negative before crossing the line for the first time.
+    pub lap_distance: f32,
+    pub total_distance: f32,
+    /// Seconds.
+    pub safety_car_delta: f32,
+    pub car_position: u8,
+    pub current_lap_num: u8,
+    /// 0 is none, 1 pitting, 2 in the pit area.
+    pub pit_status: u8,
+    pub num_pit_stops: u8,
+    /// 0 is the first sector.
+    pub sector: u8,
+    pub current_lap_invalid: bool,
+    /// Seconds of accumulated time penalties.
+    pub penalties: u8,
+    pub total_warnings: u8,
+    pub corner_cutting_warnings: u8,
+    pub num_unserved_drive_through_pens: u8,
+    pub num_unserved_stop_go_pens: u8,
+    pub grid_position: u8,
+    /// 0 is in the garage, 1 flying lap, 2 in lap, 3 out lap, 4 on track.
+    pub driver_status: u8,
+    /// 0 is invalid, 1 inactive, 2 active, 3 finished, 4 did not finish, 5 disqualified,
+    /// 6 not classified, 7 retired.
+    pub result_status: u8,
+    pub pit_lane_timer_active: bool,
+    /// Milliseconds.
+    pub pit_lane_time_in_lane: u16,
+    pub pit_stop_timer: u16,
+    pub pit_stop_should_serve_pen: bool,
+    /// Kilometers per hour, since 2024.
+    pub speed_trap_fastest_speed: Option<f32>,
+    pub speed_trap_fastest_lap: Option<u8>,
+}
+
+impl LapData {
+    fn read(r: &mut ByteReader, format: PacketFormat) -> Result<Self> {
+        let minutes_and_ms = |r: &mut ByteReader| -> Result<u32> {
+            let ms = r.u16()? as u32;
+            Ok(r.u8()? as u32 * 60_000 + ms)
+        };
+        let last_lap_time = r.u32()?;
+        let current_lap_time = r.u32()?;
+        let sector1_time = minutes_and_ms(r)?;
+        let sector2_time = minutes_and_ms(r)?;
+        let (delta_to_car_in_front, delta_to_race_leader) = match format {
+            PacketFormat::Year2023 => (r.u16()? as u32, r.u16()? as u32),
+            PacketFormat::Year2024 | PacketFormat::Year2025 => {
+                (minutes_and_ms(r)?, minutes_and_ms(r)?)
+            }
+        };
+        let mut lap_data = Self {
+            last_lap_time,
+            current_lap_time,
+            sector1_time,
+            sector2_time,
+            delta_to_car_in_front,
+            delta_to_race_leader,
+            lap_distance: r.f32()?,
+            total_distance: r.f32()?,
+            safety_car_delta: r.f32()?,
+            car_position: r.u8()?,
+            current_lap_num: r.u8()?,
+            pit_status: r.u8()?,
+            num_pit_stops: r.u8()?,
+            sector: r.u8()?,
+            current_lap_invalid: r.bool()?,
+            penalties: r.u8()?,
+            total_warnings: r.u8()?,
+            corner_cutting_warnings: r.u8()?,
+            num_unserved_drive_through_pens: r.u8()?,
+            num_unserved_stop_go_pens: r.u8()?,
+            grid_position: r.u8()?,
+            driver_status: r.u8()?,
+            result_status: r.u8()?,
+            pit_lane_timer_active: r.bool()?,
+            pit_lane_time_in_lane: r.u16()?,
+            pit_stop_timer: r.u16()?,
+            pit_stop_should_serve_pen: r.bool()?,
+            speed_trap_fastest_speed: None,
+            speed_trap_fastest_lap: None,
+        };
+        if format >= PacketFormat::Year2024 {
+            lap_data.speed_trap_fastest_speed = Some(r.f32()?);
+            lap_data.speed_trap_fastest_lap = Some(r.u8()?);
+        }
+        Ok(lap_data)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    SessionStarted,
+    SessionEnded,
+    FastestLap {
+        vehicle_index: u8,
+        /// Seconds.
+        lap_time: f32,
+    },
+    Retirement {
+        vehicle_index: u8,
+    },
+    DrsEnabled,
+    DrsDisabled,
+    TeamMateInPits {
+        vehicle_index: u8,
+    },
+    ChequeredFlag,
+    RaceWinner {
+        vehicle_index: u8,
+    },
+    Penalty {
+        penalty_type: u8,
+        infringement_type: u8,
+        vehicle_index: u8,
+        other_vehicle_index: u8,
+        /// Seconds.
+        time: u8,
+        lap_num: u8,
+        places_gained: u8,
+    },
+    SpeedTrap {
+        vehicle_index: u8,
+        /// Kilometers per hour.
+        speed: f32,
+        is_overall_fastest_in_session: bool,
+        is_driver_fastest_in_session: bool,
+        fastest_vehicle_index_in_session: u8,
+        fastest_speed_in_session: f32,
+    },
+    StartLights {
+        num_lights: u8,
+    },
+    LightsOut,
+    DriveThroughServed {
+        vehicle_index: u8,
+    },
+    StopGoServed {
+        vehicle_index: u8,
+    },
+    Flashback {
+        frame_identifier: u32,
+        session_time: f32,
+    },
+    /// Bit flags of the buttons that are pressed.
+    Buttons(u32),
+    RedFlag,
+    Overtake {
+        overtaking_vehicle_index: u8,
+        being_overtaken_vehicle_index: u8,
+    },
+    /// Since 2024.
+    SafetyCar {
+        safety_car_type: u8,
+        event_type: u8,
+    },
+    /// Since 2024.
+    Collision {
+        vehicle_1_index: u8,
+        vehicle_2_index: u8,
+    },
+    /// Event which is not decoded, by code.
+    Other([u8; 4]),
+}
+
+impl Event {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        let code: [u8; 4] = r.take(4)?.try_into()?;
+        Ok(match &code {
+            b"SSTA" => Event::SessionStarted,
+            b"SEND" => Event::SessionEnded,
+            b"FTLP" => Event::FastestLap {
+                vehicle_index: r.u8()?,
+                lap_time: r.f32()?,
+            },
+            b"RTMT" => Event::Retirement {
+                vehicle_index: r.u8()?,
+            },
+            b"DRSE" => Event::DrsEnabled,
+            b"DRSD" => Event::DrsDisabled,
+            b"TMPT" => Event::TeamMateInPits {
+                vehicle_index: r.u8()?,
+            },
+            b"CHQF" => Event::ChequeredFlag,
+            b"RCWN" => Event::RaceWinner {
+                vehicle_index: r.u8()?,
+            },
+            b"PENA" => Event::Penalty {
+                penalty_type: r.u8()?,
+                infringement_type: r.u8()?,
+                vehicle_index: r.u8()?,
+                other_vehicle_index: r.u8()?,
+                time: r.u8()?,
+                lap_num: r.u8()?,
+                places_gained: r.u8()?,
+            },
+            b"SPTP" => Event::SpeedTrap {
+                vehicle_index: r.u8()?,
+                speed: r.f32()?,
+                is_overall_fastest_in_session: r.bool()?,
+                is_driver_fastest_in_session: r.bool()?,
+                fastest_vehicle_index_in_session: r.u8()?,
+                fastest_speed_in_session: r.f32()?,
+            },
+            b"STLG" => Event::StartLights {
+                num_lights: r.u8()?,
+            },
+            b"LGOT" => Event::LightsOut,
+            b"DTSV" => Event::DriveThroughServed {
+                vehicle_index: r.u8()?,
+            },
+            b"SGSV" => Event::StopGoServed {
+                vehicle_index: r.u8()?,
+            },
+            b"FLBK" => Event::Flashback {
+                frame_identifier: r.u32()?,
+                session_time: r.f32()?,
+            },
+            b"BUTN" => Event::Buttons(r.u32()?),
+            b"RDFL" => Event::RedFlag,
+            b"OVTK" => Event::Overtake {
+                overtaking_vehicle_index: r.u8()?,
+                being_overtaken_vehicle_index: r.u8()?,
+            },
+            b"SCAR" => Event::SafetyCar {
+                safety_car_type: r.u8()?,
+                event_type: r.u8()?,
+            },
+            b"COLL" => Event::Collision {
+                vehicle_1_index: r.u8()?,
+                vehicle_2_index: r.u8()?,
+            },
+            _ => Event::Other(code),
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Participant {
+    pub ai_controlled: bool,
+    pub driver_id: u8,
+    pub network_id: u8,
+    pub team_id: u8,
+    pub my_team: bool,
+    pub race_number: u8,
+    pub nationality: u8,
+    pub name: String,
+    /// Whether the telemetry of the player is public.
+    pub your_telemetry: bool,
+    pub show_online_names: bool,
+    /// F1 World tech level, since 2024.
+    pub tech_level: Option<u16>,
+    pub platform: u8,
+    /// Colours of the livery, since 2025.
+    pub livery_colours: Option<Vec<LiveryColour>>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LiveryColour {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Participant {
+    fn read(r: &mut ByteReader, format: PacketFormat) -> Result<Self> {
+        Ok(Self {
+            ai_controlled: r.bool()?,
+            driver_id: r.u8()?,
+            network_id: r.u8()?,
+            team_id: r.u8()?,
+            my_team: r.bool()?,
+            race_number: r.u8()?,
+            nationality: r.u8()?,
+            name: {
+                let name = r.take(format.name_len())?;
+                let len = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+                String::from_utf8_lossy(&name[..len]).into_owned()
+            },
+            your_telemetry: r.bool()?,
+            show_online_names: r.bool()?,
+            tech_level: (format >= PacketFormat::Year2024)
+                .then(|| r.u16())
+                .transpose()?,
+            platform: r.u8()?,
+            livery_colours: (format >= PacketFormat::Year2025)
+                .then(|| {
+                    let num_colours = r.u8()? as usize;
+                    let mut colours = (0..MAX_LIVERY_COLOURS)
+                        .map(|_| {
+                            Ok(LiveryColour {
+                                red: r.u8()?,
+                                green: r.u8()?,
+                                blue: r.u8()?,
+                            })
+                        })
+                        .collect::<Result<Vec<_>>>()?;
+                    colours.truncate(num_colours);
+                    Ok::<_, anyhow::Error>(colours)
+                })
+                .transpose()?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CarSetup {
+    pub front_wing: u8,
+    pub rear_wing: u8,
+    /// Percent.
+    pub on_throttle: u8,
+    pub off_throttle: u8,
+    pub front_camber: f32,
+    pub rear_camber: f32,
+    pub front_toe: f32,
+    pub rear_toe: f32,
+    pub front_suspension: u8,
+    pub rear_suspension: u8,
+    pub front_anti_roll_bar: u8,
+    pub rear_anti_roll_bar: u8,
+    pub front_suspension_height: u8,
+    pub rear_suspension_height: u8,
+    /// Percent.
+    pub brake_pressure: u8,
+    pub brake_bias: u8,
+    /// Percent, since 2024.
+    pub engine_braking: Option<u8>,
+    /// PSI.
+    pub tyre_pressures: [f32; 4],
+    pub ballast: u8,
+    /// Kilograms.
+    pub fuel_load: f32,
+}
+
+impl CarSetup {
+    fn read(r: &mut ByteReader, format: PacketFormat) -> Result<Self> {
+        Ok(Self {
+            front_wing: r.u8()?,
+            rear_wing: r.u8()?,
+            on_throttle: r.u8()?,
+            off_throttle: r.u8()?,
+            front_camber: r.f32()?,
+            rear_camber: r.f32()?,
+            front_toe: r.f32()?,
+            rear_toe: r.f32()?,
+            front_suspension: r.u8()?,
+            rear_suspension: r.u8()?,
+            front_anti_roll_bar: r.u8()?,
+            rear_anti_roll_bar: r.u8()?,
+            front_suspension_height: r.u8()?,
+            rear_suspension_height: r.u8()?,
+            brake_pressure: r.u8()?,
+            brake_bias: r.u8()?,
+            engine_braking: (format >= PacketFormat::Year2024)
+                .then(|| r.u8())
+                .transpose()?,
+            tyre_pressures: r.f32s()?,
+            ballast: r.u8()?,
+            fuel_load: r.f32()?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CarTelemetry {
+    /// Kilometers per hour.
+    pub speed: u16,
+    /// From 0 to 1.
+    pub throttle: f32,
+    /// From -1 for full left to 1 for full right.
+    pub steer: f32,
+    pub brake: f32,
+    /// Percent.
+    pub clutch: u8,
+    /// -1 is reverse, 0 is neutral.
+    pub gear: i8,
+    pub engine_rpm: u16,
+    pub drs: bool,
+    pub rev_lights_percent: u8,
+    /// Bit for each LED, from left to right.
+    pub rev_lights_bit_value: u16,
+    /// Celsius.
+    pub brakes_temperature: [u16; 4],
+    pub tyres_surface_temperature: [u8; 4],
+    pub tyres_inner_temperature: [u8; 4],
+    pub engine_temperature: u16,
+    /// PSI.
+    pub tyres_pressure: [f32; 4],
+    pub surface_type: [u8; 4],
+}
+
+impl CarTelemetry {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        Ok(Self {
+            speed: r.u16()?,
+            throttle: r.f32()?,
+            steer: r.f32()?,
+            brake: r.f32()?,
+            clutch: r.u8()?,
+            gear: r.i8()?,
+            engine_rpm: r.u16()?,
+            drs: r.bool()?,
+            rev_lights_percent: r.u8()?,
+            rev_lights_bit_value: r.u16()?,
+            brakes_temperature: [r.u16()?, r.u16()?, r.u16()?, r.u16()?],
+            tyres_surface_temperature: r.bytes()?,
+            tyres_inner_temperature: r.bytes()?,
+            engine_temperature: r.u16()?,
+            tyres_pressure: r.f32s()?,
+            surface_type: r.bytes()?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CarTelemetryPacket {
+    pub cars: Vec<CarTelemetry>,
+    /// 255 when the multi function display is closed.
+    pub mfd_panel_index: u8,
+    pub mfd_panel_index_secondary_player: u8,
+    /// 0 without a suggestion.
+    pub suggested_gear: i8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct CarStatus {
+    pub traction_control: u8,
+    pub anti_lock_brakes: bool,
+    pub fuel_mix: u8,
+    /// Percent.
+    pub front_brake_bias: u8,
+    pub pit_limiter_status: bool,
+    /// Kilograms.
+    pub fuel_in_tank: f32,
+    pub fuel_capacity: f32,
+    pub fuel_remaining_laps: f32,
+    pub max_rpm: u16,
+    pub idle_rpm: u16,
+    pub max_gears: u8,
+    pub drs_allowed: bool,
+    /// Meters until DRS can be used, 0 when not available.
+    pub drs_activation_distance: u16,
+    pub actual_tyre_compound: u8,
+    pub visual_tyre_compound: u8,
+    pub tyres_age_laps: u8,
+    pub vehicle_fia_flags: FiaFlag,
+    /// Watts.
+    pub engine_power_ice: f32,
+    pub engine_power_mguk: f32,
+    /// Joules.
+    pub ers_store_energy: f32,
+    pub ers_deploy_mode: u8,
+    pub ers_harvested_this_lap_mguk: f32,
+    pub ers_harvested_this_lap_mguh: f32,
+    pub ers_deployed_this_lap: f32,
+    pub network_paused: bool,
+}
+
+impl CarStatus {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        Ok(Self {
+            traction_control: r.u8()?,
+            anti_lock_brakes: r.bool()?,
+            fuel_mix: r.u8()?,
+            front_brake_bias: r.u8()?,
+            pit_limiter_status: r.bool()?,
+            fuel_in_tank: r.f32()?,
+            fuel_capacity: r.f32()?,
+            fuel_remaining_laps: r.f32()?,
+            max_rpm: r.u16()?,
+            idle_rpm: r.u16()?,
+            max_gears: r.u8()?,
+            drs_allowed: r.bool()?,
+            drs_activation_distance: r.u16()?,
+            actual_tyre_compound: r.u8()?,
+            visual_tyre_compound: r.u8()?,
+            tyres_age_laps: r.u8()?,
+            vehicle_fia_flags: FiaFlag::from_raw(r.i8()?),
+            engine_power_ice: r.f32()?,
+            engine_power_mguk: r.f32()?,
+            ers_store_energy: r.f32()?,
+            ers_deploy_mode: r.u8()?,
+            ers_harvested_this_lap_mguk: r.f32()?,
+            ers_harvested_this_lap_mguh: r.f32()?,
+            ers_deployed_this_lap: r.f32()?,
+            network_paused: r.bool()?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TyreStint {
+    pub actual_compound: u8,
+    pub visual_compound: u8,
+    pub end_lap: u8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FinalClassification {
+    pub position: u8,
+    pub num_laps: u8,
+    pub grid_position: u8,
+    pub points: u8,
+    pub num_pit_stops: u8,
+    /// Same values as [`LapData::result_status`].
+    pub result_status: u8,
+    /// Since 2025.
+    pub result_reason: Option<u8>,
+    /// Milliseconds.
+    pub best_lap_time: u32,
+    /// Seconds, without penalties.
+    pub total_race_time: f64,
+    pub penalties_time: u8,
+    pub num_penalties: u8,
+    pub tyre_stints: Vec<TyreStint>,
+}
+
+impl FinalClassification {
+    fn read(r: &mut ByteReader, format: PacketFormat) -> Result<Self> {
+        let position = r.u8()?;
+        let num_laps = r.u8()?;
+        let grid_position = r.u8()?;
+        let points = r.u8()?;
+        let num_pit_stops = r.u8()?;
+        let result_status = r.u8()?;
+        let result_reason = (format >= PacketFormat::Year2025)
+            .then(|| r.u8())
+            .transpose()?;
+        let best_lap_time = r.u32()?;
+        let total_race_time = r.f64()?;
+        let penalties_time = r.u8()?;
+        let num_penalties = r.u8()?;
+        let num_tyre_stints = r.u8()? as usize;
+        let actual: [u8; MAX_TYRE_STINTS] = r.bytes()?;
+        let visual: [u8; MAX_TYRE_STINTS] = r.bytes()?;
+        let end_laps: [u8; MAX_TYRE_STINTS] = r.bytes()?;
+        let tyre_stints = (0..num_tyre_stints.min(MAX_TYRE_STINTS))
+            .map(|idx| TyreStint {
+                actual_compound: actual[idx],
+                visual_compound: visual[idx],
+                end_lap: end_laps[idx],
+            })
+            .collect();
+        Ok(Self {
+            position,
+            num_laps,
+            grid_position,
+            points,
+            num_pit_stops,
+            result_status,
+            result_reason,
+            best_lap_time,
+            total_race_time,
+            penalties_time,
+            num_penalties,
+            tyre_stints,
+        })
+    }
+}
+
+/// Damage and wear in percent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CarDamage {
+    pub tyres_wear: [f32; 4],
+    pub tyres_damage: [u8; 4],
+    pub brakes_damage: [u8; 4],
+    /// Since 2025.
+    pub tyre_blisters: Option<[u8; 4]>,
+    pub front_left_wing_damage: u8,
+    pub front_right_wing_damage: u8,
+    pub rear_wing_damage: u8,
+    pub floor_damage: u8,
+    pub diffuser_damage: u8,
+    pub sidepod_damage: u8,
+    pub drs_fault: bool,
+    pub ers_fault: bool,
+    pub gear_box_damage: u8,
+    pub engine_damage: u8,
+    pub engine_mguh_wear: u8,
+    pub engine_es_wear: u8,
+    pub engine_ce_wear: u8,
+    pub engine_ice_wear: u8,
+    pub engine_mguk_wear: u8,
+    pub engine_tc_wear: u8,
+    pub engine_blown: bool,
+    pub engine_seized: bool,
+}
+
+impl CarDamage {
+    fn read(r: &mut ByteReader, format: PacketFormat) -> Result<Self> {
+        Ok(Self {
+            tyres_wear: r.f32s()?,
+            tyres_damage: r.bytes()?,
+            brakes_damage: r.bytes()?,
+            tyre_blisters: (format >= PacketFormat::Year2025)
+                .then(|| r.bytes())
+                .transpose()?,
+            front_left_wing_damage: r.u8()?,
+            front_right_wing_damage: r.u8()?,
+            rear_wing_damage: r.u8()?,
+            floor_damage: r.u8()?,
+            diffuser_damage: r.u8()?,
+            sidepod_damage: r.u8()?,
+            drs_fault: r.bool()?,
+            ers_fault: r.bool()?,
+            gear_box_damage: r.u8()?,
+            engine_damage: r.u8()?,
+            engine_mguh_wear: r.u8()?,
+            engine_es_wear: r.u8()?,
+            engine_ce_wear: r.u8()?,
+            engine_ice_wear: r.u8()?,
+            engine_mguk_wear: r.u8()?,
+            engine_tc_wear: r.u8()?,
+            engine_blown: r.bool()?,
+            engine_seized: r.bool()?,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TyreSet {
+    pub actual_compound: u8,
+    pub visual_compound: u8,
+    /// Percent.
+    pub wear: u8,
+    pub available: bool,
+    pub recommended_session: u8,
+    /// Laps.
+    pub life_span: u8,
+    pub usable_life: u8,
+    /// Milliseconds compared to the fitted set.
+    pub lap_delta_time: i16,
+    pub fitted: bool,
+}
+
+/// Tyre sets of one car, sent for each car in turn.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TyreSets {
+    pub car_index: u8,
+    /// 13 dry sets followed by 7 wet sets.
+    pub tyre_sets: Vec<TyreSet>,
+    pub fitted_index: u8,
+}
+
+impl TyreSets {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        let car_index = r.u8()?;
+        let tyre_sets = (0..MAX_TYRE_SETS)
+            .map(|_| {
+                Ok(TyreSet {
+                    actual_compound: r.u8()?,
+                    visual_compound: r.u8()?,
+                    wear: r.u8()?,
+                    available: r.bool()?,
+                    recommended_session: r.u8()?,
+                    life_span: r.u8()?,
+                    usable_life: r.u8()?,
+                    lap_delta_time: r.i16()?,
+                    fitted: r.bool()?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self {
+            car_index,
+            tyre_sets,
+            fitted_index: r.u8()?,
+        })
+    }
+}
+
+/// Extended motion of the player car.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MotionEx {
+    pub suspension_position: [f32; 4],
+    pub suspension_velocity: [f32; 4],
+    pub suspension_acceleration: [f32; 4],
+    pub wheel_speed: [f32; 4],
+    pub wheel_slip_ratio: [f32; 4],
+    pub wheel_slip_angle: [f32; 4],
+    pub wheel_lat_force: [f32; 4],
+    pub wheel_long_force: [f32; 4],
+    /// Meters.
+    pub height_of_cog_above_ground: f32,
+    /// Meters per second, in local space.
+    pub local_velocity: [f32; 3],
+    /// Radians per second.
+    pub angular_velocity: [f32; 3],
+    pub angular_acceleration: [f32; 3],
+    /// Radians.
+    pub front_wheels_angle: f32,
+    pub wheel_vert_force: [f32; 4],
+    /// Meters, since 2024.
+    pub front_aero_height: Option<f32>,
+    pub rear_aero_height: Option<f32>,
+    /// Radians, since 2024.
+    pub front_roll_angle: Option<f32>,
+    pub rear_roll_angle: Option<f32>,
+    pub chassis_yaw: Option<f32>,
+    /// Radians, since 2025.
+    pub chassis_pitch: Option<f32>,
+    pub wheel_camber: Option<[f32; 4]>,
+    pub wheel_camber_gain: Option<[f32; 4]>,
+}
+
+impl MotionEx {
+    fn read(r: &mut ByteReader, format: PacketFormat) -> Result<Self> {
+        let since_2024 = format >= PacketFormat::Year2024;
+        let since_2025 = format >= PacketFormat::Year2025;
+        Ok(Self {
+            suspension_position: r.f32s()?,
+            suspension_velocity: r.f32s()?,
+            suspension_acceleration: r.f32s()?,
+            wheel_speed: r.f32s()?,
+            wheel_slip_ratio: r.f32s()?,
+            wheel_slip_angle: r.f32s()?,
+            wheel_lat_force: r.f32s()?,
+            wheel_long_force: r.f32s()?,
+            height_of_cog_above_ground: r.f32()?,
+            local_velocity: r.f32s()?,
+            angular_velocity: r.f32s()?,
+            angular_acceleration: r.f32s()?,
+            front_wheels_angle: r.f32()?,
+            wheel_vert_force: r.f32s()?,
+            front_aero_height: since_2024.then(|| r.f32()).transpose()?,
+            rear_aero_height: since_2024.then(|| r.f32()).transpose()?,
+            front_roll_angle: since_2024.then(|| r.f32()).transpose()?,
+            rear_roll_angle: since_2024.then(|| r.f32()).transpose()?,
+            chassis_yaw: since_2024.then(|| r.f32()).transpose()?,
+            chassis_pitch: since_2025.then(|| r.f32()).transpose()?,
+            wheel_camber: since_2025.then(|| r.f32s()).transpose()?,
+            wheel_camber_gain: since_2025.then(|| r.f32s()).transpose()?,
+        })
+    }
+}
+
+/// Positions of all cars at the end of each lap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LapPositions {
+    /// Lap of the first entry, starting from 0, since each packet holds up to 50 laps.
+    pub lap_start: u8,
+    /// Position of each car by vehicle index for each lap, 0 without a position.
+    pub positions: Vec<[u8; MAX_CARS]>,
+}
+
+impl LapPositions {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        let num_laps = r.u8()? as usize;
+        let lap_start = r.u8()?;
+        let mut positions = (0..MAX_LAP_POSITIONS)
+            .map(|_| r.bytes())
+            .collect::<Result<Vec<_>>>()?;
+        positions.truncate(num_laps);
+        Ok(Self {
+            lap_start,
+            positions,
+        })
+    }
+}
+
+/// Reads the entry of each car.
+fn cars<T, F: FnMut(&mut ByteReader) -> Result<T>>(
+    r: &mut ByteReader,
+    mut read: F,
+) -> Result<Vec<T>> {
+    (0..MAX_CARS).map(|_| read(r)).collect()
+}
@@ -0,0 +1,327 @@
+//! Client for the "Data Out" UDP telemetry of Forza Motorsport and Forza Horizon.
+//!
+//! Data Out has to be enabled in the HUD settings of the game, with the IP address of this
+//! machine and the port the client listens on.
+
+use crate::{Moment, Pedals, Simetry};
+use anyhow::{bail, Result};
+use byteorder::{ByteOrder, LittleEndian};
+use std::borrow::Cow;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use uom::si::angular_velocity::revolution_per_minute;
+use uom::si::f64::{AngularVelocity, ThermodynamicTemperature, Velocity};
+use uom::si::thermodynamic_temperature::degree_fahrenheit;
+use uom::si::velocity::meter_per_second;
+
+const SLED_SIZE: usize = 232;
+const DASH_SIZE: usize = 311;
+const HORIZON_SIZE: usize = 324;
+const MOTORSPORT_2023_SIZE: usize = 331;
+/// Forza Horizon has 12 undocumented bytes between the sled and dash fields.
+const HORIZON_DASH_OFFSET: usize = SLED_SIZE + 12;
+
+/// Packet format, selected in the game settings and detected by length.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PacketVariant {
+    Sled,
+    Dash,
+    /// Dash format of Forza Horizon 4 and 5.
+    Horizon,
+    /// Dash format of Forza Motorsport (2023), with tire wear and the track.
+    Motorsport2023,
+}
+
+impl PacketVariant {
+    pub fn from_len(len: usize) -> Option<Self> {
+        match len {
+            SLED_SIZE => Some(PacketVariant::Sled),
+            DASH_SIZE => Some(PacketVariant::Dash),
+            HORIZON_SIZE => Some(PacketVariant::Horizon),
+            MOTORSPORT_2023_SIZE => Some(PacketVariant::Motorsport2023),
+            _ => None,
+        }
+    }
+}
+
+/// Motion of the car, sent in every variant.
+///
+/// Vectors are in car space, with X to the right, Y up and Z forward. Wheel arrays are front
+/// left, front right, rear left, rear right.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Sled {
+    /// False in menus, where the other values are zero.
+    pub is_race_on: bool,
+    /// Milliseconds, wrapping around.
+    pub timestamp_ms: u32,
+    pub engine_max_rpm: f32,
+    pub engine_idle_rpm: f32,
+    pub current_engine_rpm: f32,
+    /// Meters per second squared.
+    pub acceleration: [f32; 3],
+    /// Meters per second.
+    pub velocity: [f32; 3],
+    /// Radians per second.
+    pub angular_velocity: [f32; 3],
+    /// Radians.
+    pub yaw: f32,
+    pub pitch: f32,
+    pub roll: f32,
+    /// 0 is fully extended, 1 is fully compressed.
+    pub normalized_suspension_travel: [f32; 4],
+    pub tire_slip_ratio: [f32; 4],
+    /// Radians per second.
+    pub wheel_rotation_speed: [f32; 4],
+    pub wheel_on_rumble_strip: [bool; 4],
+    /// From 0 to 1 for the deepest puddle.
+    pub wheel_in_puddle_depth: [f32; 4],
+    pub surface_rumble: [f32; 4],
+    pub tire_slip_angle: [f32; 4],
+    pub tire_combined_slip: [f32; 4],
+    /// Meters.
+    pub suspension_travel_meters: [f32; 4],
+    pub car_ordinal: i32,
+    /// 0 for class D up to 7 for class X.
+    pub car_class: i32,
+    pub car_performance_index: i32,
+    /// 0 is front wheel drive, 1 rear wheel drive, 2 all wheel drive.
+    pub drivetrain_type: i32,
+    pub num_cylinders: i32,
+}
+
+/// Dashboard values of all variants but sled.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dash {
+    /// Meters, in world space.
+    pub position: [f32; 3],
+    /// Meters per second.
+    pub speed: f32,
+    /// Watts.
+    pub power: f32,
+    /// Newton meters.
+    pub torque: f32,
+    /// Fahrenheit, front left, front right, rear left, rear right.
+    pub tire_temp: [f32; 4],
+    pub boost: f32,
+    /// From 0 to 1.
+    pub fuel: f32,
+    /// Meters.
+    pub distance_traveled: f32,
+    /// Seconds.
+    pub best_lap: f32,
+    pub last_lap: f32,
+    pub current_lap: f32,
+    pub current_race_time: f32,
+    pub lap_number: u16,
+    pub race_position: u8,
+    /// From 0 to 255.
+    pub accel: u8,
+    pub brake: u8,
+    pub clutch: u8,
+    pub hand_brake: u8,
+    /// 0 is reverse.
+    pub gear: u8,
+    /// From -127 for full left to 127 for full right.
+    pub steer: i8,
+    pub normalized_driving_line: i8,
+    pub normalized_ai_brake_difference: i8,
+    /// From 0 for new to 1 for worn out, since Forza Motorsport (2023).
+    pub tire_wear: Option<[f32; 4]>,
+    /// Since Forza Motorsport (2023).
+    pub track_ordinal: Option<i32>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimState {
+    pub variant: PacketVariant,
+    pub sled: Sled,
+    pub dash: Option<Dash>,
+}
+
+impl SimState {
+    /// Decode a packet of any variant.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let Some(variant) = PacketVariant::from_len(data.len()) else {
+            bail!("Unknown Forza packet of {} bytes", data.len());
+        };
+        let dash = match variant {
+            PacketVariant::Sled => None,
+            PacketVariant::Dash | PacketVariant::Motorsport2023 => {
+                Some(parse_dash(&data[SLED_SIZE..]))
+            }
+            PacketVariant::Horizon => Some(parse_dash(&data[HORIZON_DASH_OFFSET..])),
+        };
+        Ok(Self {
+            variant,
+            sled: parse_sled(data),
+            dash,
+        })
+    }
+
+    pub fn tire_temperatures(&self) -> Option<[ThermodynamicTemperature; 4]> {
+        let dash = self.dash.as_ref()?;
+        Some(
+            dash.tire_temp
+                .map(|t| ThermodynamicTemperature::new::<degree_fahrenheit>(t as f64)),
+        )
+    }
+}
+
+#[derive(Debug)]
+pub struct Client {
+    socket: UdpSocket,
+}
+
+impl Client {
+    pub const DEFAULT_URI: &'static str = "0.0.0.0:5300";
+
+    pub async fn connect(uri: &str, retry_delay: Duration) -> Self {
+        loop {
+            if let Ok(client) = Self::try_connect(uri).await {
+                return client;
+            }
+            tokio::time::sleep(retry_delay).await;
+        }
+    }
+
+    /// Listen on `uri` until a packet is received.
+    pub async fn try_connect(uri: &str) -> Result<Self> {
+        Self::try_connect_with_socket(UdpSocket::bind(uri).await?).await
+    }
+
+    /// Listen on an already bound `socket` until a packet is received.
+    pub async fn try_connect_with_socket(socket: UdpSocket) -> Result<Self> {
+        let slf = Self { socket };
+        slf.next_sim_state().await?;
+        Ok(slf)
+    }
+
+    /// Waits for the next packet, ignoring packets of unknown length.
+    pub async fn next_sim_state(&self) -> Result<SimState> {
+        let mut buffer = [0u8; MOTORSPORT_2023_SIZE + 1];
+        loop {
+            let len = self.socket.recv(&mut buffer).await?;
+            match SimState::decode(&buffer[..len]) {
+                Ok(sim_state) => return Ok(sim_state),
+                Err(err) => log::debug!("{err}"),
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Simetry for Client {
+    fn name(&self) -> &str {
+        "Forza"
+    }
+
+    async fn next_moment(&mut self) -> Option<Box<dyn Moment + Send + Sync + 'static>> {
+        Some(Box::new(self.next_sim_state().await.ok()?))
+    }
+}
+
+impl Moment for SimState {
+    fn vehicle_gear(&self) -> Option<i8> {
+        match self.dash.as_ref()?.gear {
+            0 => Some(-1),
+            gear => Some(gear as i8),
+        }
+    }
+
+    fn vehicle_velocity(&self) -> Option<Velocity> {
+        Some(Velocity::new::<meter_per_second>(
+            self.dash.as_ref()?.speed as f64,
+        ))
+    }
+
+    fn vehicle_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(
+            self.sled.current_engine_rpm as f64,
+        ))
+    }
+
+    fn vehicle_max_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(
+            self.sled.engine_max_rpm as f64,
+        ))
+    }
+
+    fn vehicle_model_id(&self) -> Option<Cow<str>> {
+        Some(self.sled.car_ordinal.to_string().into())
+    }
+
+    fn pedals(&self) -> Option<Pedals> {
+        let dash = self.dash.as_ref()?;
+        Some(Pedals {
+            throttle: dash.accel as f64 / 255.0,
+            brake: dash.brake as f64 / 255.0,
+            clutch: dash.clutch as f64 / 255.0,
+        })
+    }
+}
+
+fn parse_sled(data: &[u8]) -> Sled {
+    let f = |offset: usize| LittleEndian::read_f32(&data[offset..]);
+    let i = |offset: usize| LittleEndian::read_i32(&data[offset..]);
+    let vector = |offset: usize| [f(offset), f(offset + 4), f(offset + 8)];
+    let wheels = |offset: usize| [f(offset), f(offset + 4), f(offset + 8), f(offset + 12)];
+    Sled {
+        is_race_on: i(0) != 0,
+        timestamp_ms: LittleEndian::read_u32(&data[4..]),
+        engine_max_rpm: f(8),
+        engine_idle_rpm: f(12),
+        current_engine_rpm: f(16),
+        acceleration: vector(20),
+        velocity: vector(32),
+        angular_velocity: vector(44),
+        yaw: f(56),
+        pitch: f(60),
+        roll: f(64),
+        normalized_suspension_travel: wheels(68),
+        tire_slip_ratio: wheels(84),
+        wheel_rotation_speed: wheels(100),
+        wheel_on_rumble_strip: [i(116) != 0, i(120) != 0, i(124) != 0, i(128) != 0],
+        wheel_in_puddle_depth: wheels(132),
+        surface_rumble: wheels(148),
+        tire_slip_angle: wheels(164),
+        tire_combined_slip: wheels(180),
+        suspension_travel_meters: wheels(196),
+        car_ordinal: i(212),
+        car_class: i(216),
+        car_performance_index: i(220),
+        drivetrain_type: i(224),
+        num_cylinders: i(228),
+    }
+}
+
+/// Parses the dash fields, starting at `data`.
+fn parse_dash(data: &[u8]) -> Dash {
+    let f = |offset: usize| LittleEndian::read_f32(&data[offset..]);
+    let is_motorsport_2023 = data.len() >= MOTORSPORT_2023_SIZE - SLED_SIZE;
+    Dash {
+        position: [f(0), f(4), f(8)],
+        speed: f(12),
+        power: f(16),
+        torque: f(20),
+        tire_temp: [f(24), f(28), f(32), f(36)],
+        boost: f(40),
+        fuel: f(44),
+        distance_traveled: f(48),
+        best_lap: f(52),
+        last_lap: f(56),
+        current_lap: f(60),
+        current_race_time: f(64),
+        lap_number: LittleEndian::read_u16(&data[68..]),
+        race_position: data[70],
+        accel: data[71],
+        brake: data[72],
+        clutch: data[73],
+        hand_brake: data[74],
+        gear: data[75],
+        steer: data[76] as i8,
+        normalized_driving_line: data[77] as i8,
+        normalized_ai_brake_difference: data[78] as i8,
+        tire_wear: is_motorsport_2023.then(|| [f(79), f(83), f(87), f(91)]),
+        track_ordinal: is_motorsport_2023.then(|| LittleEndian::read_i32(&data[95..])),
+    }
+}
@@ -0,0 +1,320 @@
+//! Client for the UDP telemetry of Gran Turismo 7.
+//!
+//! The console starts sending encrypted packets to port 33740 of whoever sends a heartbeat to
+//! its port 33739, and stops when the heartbeats stop.
+
+use crate::{Moment, Pedals, Simetry};
+use anyhow::{bail, Context, Result};
+use byteorder::{ByteOrder, LittleEndian};
+use salsa20::cipher::{KeyIvInit, StreamCipher};
+use salsa20::Salsa20;
+use std::borrow::Cow;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use uom::si::angular_velocity::revolution_per_minute;
+use uom::si::f64::{AngularVelocity, Velocity};
+use uom::si::velocity::meter_per_second;
+
+const PACKET_SIZE: usize = 296;
+/// "G7S0" in little endian.
+const MAGIC: u32 = 0x47375330;
+const KEY: &[u8; 32] = b"Simulator Interface Packet GT7 v";
+const IV_OFFSET: usize = 0x40;
+const IV_MASK: u32 = 0xDEADBEAF;
+const HEARTBEAT: &[u8] = b"A";
+/// The console stops after a while without heartbeats, so one is sent after this many packets.
+const PACKETS_PER_HEARTBEAT: u32 = 100;
+const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(1);
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Bits of [`SimState::flags`].
+pub mod flags {
+    pub const CAR_ON_TRACK: u16 = 1 << 0;
+    pub const PAUSED: u16 = 1 << 1;
+    pub const LOADING_OR_PROCESSING: u16 = 1 << 2;
+    pub const IN_GEAR: u16 = 1 << 3;
+    pub const HAS_TURBO: u16 = 1 << 4;
+    pub const REV_LIMITER_BLINK_ALERT_ACTIVE: u16 = 1 << 5;
+    pub const HAND_BRAKE_ACTIVE: u16 = 1 << 6;
+    pub const LIGHTS_ACTIVE: u16 = 1 << 7;
+    pub const HIGH_BEAM_ACTIVE: u16 = 1 << 8;
+    pub const LOW_BEAM_ACTIVE: u16 = 1 << 9;
+    pub const ASM_ACTIVE: u16 = 1 << 10;
+    pub const TCS_ACTIVE: u16 = 1 << 11;
+}
+
+/// Decrypt a packet received from the console and check its magic number.
+pub fn decrypt(packet: &[u8]) -> Result<Vec<u8>> {
+    if packet.len() < PACKET_SIZE {
+        bail!("GT7 packet has {} bytes", packet.len());
+    }
+    let iv = LittleEndian::read_u32(&packet[IV_OFFSET..]);
+    let mut data = packet.to_vec();
+    cipher(iv).apply_keystream(&mut data);
+    let magic = LittleEndian::read_u32(&data);
+    if magic != MAGIC {
+        bail!("GT7 packet has wrong magic {magic:#x}");
+    }
+    Ok(data)
+}
+
+/// Encrypt a packet like the console, to stand in for it.
+pub fn encrypt(packet: &[u8], iv: u32) -> Vec<u8> {
+    let mut data = packet.to_vec();
+    cipher(iv).apply_keystream(&mut data);
+    LittleEndian::write_u32(&mut data[IV_OFFSET..], iv);
+    data
+}
+
+fn cipher(iv: u32) -> Salsa20 {
+    let mut nonce = [0u8; 8];
+    LittleEndian::write_u32(&mut nonce[0..], iv ^ IV_MASK);
+    LittleEndian::write_u32(&mut nonce[4..], iv);
+    Salsa20::new(KEY.into(), &nonce.into())
+}
+
+/// Decrypted packet, with wheel arrays ordered front left, front right, rear left, rear right.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimState {
+    /// Meters.
+    pub position: [f32; 3],
+    /// Meters per second.
+    pub velocity: [f32; 3],
+    pub rotation: [f32; 3],
+    pub relative_orientation_to_north: f32,
+    pub angular_velocity: [f32; 3],
+    pub body_height: f32,
+    pub engine_rpm: f32,
+    /// Liters.
+    pub gas_level: f32,
+    pub gas_capacity: f32,
+    /// Meters per second.
+    pub speed: f32,
+    /// Bar, offset by 1.
+    pub turbo_boost: f32,
+    pub oil_pressure: f32,
+    /// Celsius.
+    pub water_temperature: f32,
+    pub oil_temperature: f32,
+    pub tire_temperature: [f32; 4],
+    pub packet_id: i32,
+    pub lap_count: i16,
+    pub laps_in_race: i16,
+    /// Milliseconds, -1 without a time.
+    pub best_lap_time: i32,
+    pub last_lap_time: i32,
+    pub time_of_day: i32,
+    /// -1 outside of races.
+    pub race_position: i16,
+    pub total_positions: i16,
+    pub rpm_alert_min: u16,
+    pub rpm_alert_max: u16,
+    /// Kilometers per hour.
+    pub calculated_max_speed: i16,
+    /// See [`flags`].
+    pub flags: u16,
+    /// 0 is reverse.
+    pub current_gear: u8,
+    /// 15 without a suggestion.
+    pub suggested_gear: u8,
+    /// From 0 to 255.
+    pub throttle: u8,
+    pub brake: u8,
+    pub road_plane: [f32; 3],
+    pub road_plane_distance: f32,
+    /// Radians per second.
+    pub wheel_rps: [f32; 4],
+    /// Meters.
+    pub tire_radius: [f32; 4],
+    pub suspension_height: [f32; 4],
+    /// From 0 to 1.
+    pub clutch_pedal: f32,
+    pub clutch_engagement: f32,
+    pub rpm_from_clutch_to_gearbox: f32,
+    pub transmission_top_speed: f32,
+    pub gear_ratios: [f32; 8],
+    pub car_code: i32,
+}
+
+impl SimState {
+    /// Decode a decrypted packet.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < PACKET_SIZE {
+            bail!("GT7 packet has {} bytes", data.len());
+        }
+        let f = |offset: usize| LittleEndian::read_f32(&data[offset..]);
+        let i = |offset: usize| LittleEndian::read_i32(&data[offset..]);
+        let s = |offset: usize| LittleEndian::read_i16(&data[offset..]);
+        let u = |offset: usize| LittleEndian::read_u16(&data[offset..]);
+        let vector = |offset: usize| [f(offset), f(offset + 4), f(offset + 8)];
+        let wheels = |offset: usize| [f(offset), f(offset + 4), f(offset + 8), f(offset + 12)];
+        Ok(Self {
+            position: vector(0x04),
+            velocity: vector(0x10),
+            rotation: vector(0x1C),
+            relative_orientation_to_north: f(0x28),
+            angular_velocity: vector(0x2C),
+            body_height: f(0x38),
+            engine_rpm: f(0x3C),
+            gas_level: f(0x44),
+            gas_capacity: f(0x48),
+            speed: f(0x4C),
+            turbo_boost: f(0x50),
+            oil_pressure: f(0x54),
+            water_temperature: f(0x58),
+            oil_temperature: f(0x5C),
+            tire_temperature: wheels(0x60),
+            packet_id: i(0x70),
+            lap_count: s(0x74),
+            laps_in_race: s(0x76),
+            best_lap_time: i(0x78),
+            last_lap_time: i(0x7C),
+            time_of_day: i(0x80),
+            race_position: s(0x84),
+            total_positions: s(0x86),
+            rpm_alert_min: u(0x88),
+            rpm_alert_max: u(0x8A),
+            calculated_max_speed: s(0x8C),
+            flags: u(0x8E),
+            current_gear: data[0x90] & 0x0F,
+            suggested_gear: data[0x90] >> 4,
+            throttle: data[0x91],
+            brake: data[0x92],
+            road_plane: vector(0x94),
+            road_plane_distance: f(0xA0),
+            wheel_rps: wheels(0xA4),
+            tire_radius: wheels(0xB4),
+            suspension_height: wheels(0xC4),
+            clutch_pedal: f(0xF4),
+            clutch_engagement: f(0xF8),
+            rpm_from_clutch_to_gearbox: f(0xFC),
+            transmission_top_speed: f(0x100),
+            gear_ratios: std::array::from_fn(|idx| f(0x104 + idx * 4)),
+            car_code: i(0x124),
+        })
+    }
+
+    pub fn has_flag(&self, flag: u16) -> bool {
+        self.flags & flag != 0
+    }
+}
+
+#[derive(Debug)]
+pub struct Client {
+    socket: UdpSocket,
+    packets_since_heartbeat: u32,
+}
+
+impl Client {
+    /// Port the console listens on for heartbeats.
+    pub const CONSOLE_PORT: u16 = 33739;
+    pub const DEFAULT_LOCAL_URI: &'static str = "0.0.0.0:33740";
+
+    /// Connect to the console at `console_uri`, such as `192.168.1.20:33739`.
+    pub async fn connect(console_uri: &str, retry_delay: Duration) -> Self {
+        loop {
+            if let Ok(client) = Self::try_connect(console_uri).await {
+                return client;
+            }
+            tokio::time::sleep(retry_delay).await;
+        }
+    }
+
+    pub async fn try_connect(console_uri: &str) -> Result<Self> {
+        Self::try_connect_from(Self::DEFAULT_LOCAL_URI, console_uri).await
+    }
+
+    /// Connect while listening on `local_uri`, which the console only sends to on port 33740.
+    pub async fn try_connect_from(local_uri: &str, console_uri: &str) -> Result<Self> {
+        let socket = UdpSocket::bind(local_uri).await?;
+        socket.connect(console_uri).await?;
+        let mut client = Self {
+            socket,
+            packets_since_heartbeat: 0,
+        };
+        tokio::time::timeout(CONNECT_TIMEOUT, client.next_sim_state())
+            .await
+            .context("No packets from the console")??;
+        Ok(client)
+    }
+
+    /// Waits for the next valid packet, sending heartbeats as needed.
+    pub async fn next_sim_state(&mut self) -> Result<SimState> {
+        let mut buffer = [0u8; 2 * PACKET_SIZE];
+        loop {
+            if self.packets_since_heartbeat == 0 {
+                self.socket.send(HEARTBEAT).await?;
+            }
+            let Ok(received) =
+                tokio::time::timeout(HEARTBEAT_TIMEOUT, self.socket.recv(&mut buffer)).await
+            else {
+                self.packets_since_heartbeat = 0;
+                continue;
+            };
+            let len = received?;
+            self.packets_since_heartbeat =
+                (self.packets_since_heartbeat + 1) % PACKETS_PER_HEARTBEAT;
+            match decrypt(&buffer[..len]).and_then(|data| SimState::decode(&data)) {
+                Ok(sim_state) => return Ok(sim_state),
+                Err(err) => log::debug!("Ignoring packet: {err}"),
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Simetry for Client {
+    fn name(&self) -> &str {
+        "GranTurismo7"
+    }
+
+    async fn next_moment(&mut self) -> Option<Box<dyn Moment + Send + Sync + 'static>> {
+        Some(Box::new(self.next_sim_state().await.ok()?))
+    }
+}
+
+impl Moment for SimState {
+    fn vehicle_gear(&self) -> Option<i8> {
+        if !self.has_flag(flags::IN_GEAR) {
+            return Some(0);
+        }
+        match self.current_gear {
+            0 => Some(-1),
+            gear => Some(gear as i8),
+        }
+    }
+
+    fn vehicle_velocity(&self) -> Option<Velocity> {
+        Some(Velocity::new::<meter_per_second>(self.speed as f64))
+    }
+
+    fn vehicle_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(
+            self.engine_rpm as f64,
+        ))
+    }
+
+    fn vehicle_max_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(
+            self.rpm_alert_max as f64,
+        ))
+    }
+
+    fn shift_point(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(
+            self.rpm_alert_min as f64,
+        ))
+    }
+
+    fn vehicle_model_id(&self) -> Option<Cow<str>> {
+        Some(self.car_code.to_string().into())
+    }
+
+    fn pedals(&self) -> Option<Pedals> {
+        Some(Pedals {
+            throttle: self.throttle as f64 / 255.0,
+            brake: self.brake as f64 / 255.0,
+            clutch: self.clutch_pedal as f64,
+        })
+    }
+}
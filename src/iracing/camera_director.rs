@@ -0,0 +1,266 @@
+//! Automatic TV director, choosing which car to show during a broadcast.
+
+use crate::iracing::commands::{self, camera, CommandSink};
+use crate::iracing::session_info::car_number;
+use crate::iracing::{SimState, SimStateSource};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use yaml_rust::Yaml;
+
+/// Value of `CarIdxTrackSurface` for cars that are off track.
+const TRACK_SURFACE_OFF_TRACK: i32 = 0;
+
+/// Tuning of [`CameraDirector`] decisions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DirectorConfig {
+    /// Shortest time a shot is kept, measured in session time.
+    pub min_shot_duration: Duration,
+    /// Whether a new incident may cut a shot shorter than `min_shot_duration`.
+    pub incidents_interrupt_shots: bool,
+    /// How long a car stays interesting after an incident.
+    pub incident_hold: Duration,
+    /// Largest gap, as a fraction of a lap, between two cars to be considered a battle.
+    pub battle_gap: f64,
+    /// Score of a car, divided by its position, so that leaders are favoured.
+    pub leader_weight: f64,
+    /// Score of the closest possible battle, falling off linearly with the gap.
+    pub battle_weight: f64,
+    /// Score of a car with a recent incident.
+    pub incident_weight: f64,
+    /// Names of camera groups from the `CameraInfo` section, used in rotation for each shot type.
+    pub leader_camera_groups: Vec<String>,
+    pub battle_camera_groups: Vec<String>,
+    pub incident_camera_groups: Vec<String>,
+}
+
+impl Default for DirectorConfig {
+    fn default() -> Self {
+        Self {
+            min_shot_duration: Duration::from_secs(8),
+            incidents_interrupt_shots: true,
+            incident_hold: Duration::from_secs(10),
+            battle_gap: 0.015,
+            leader_weight: 1.0,
+            battle_weight: 2.0,
+            incident_weight: 3.0,
+            leader_camera_groups: vec!["TV1".into(), "Chopper".into(), "Blimp".into()],
+            battle_camera_groups: vec!["TV1".into(), "TV2".into(), "TV3".into()],
+            incident_camera_groups: vec!["TV1".into(), "Chase".into()],
+        }
+    }
+}
+
+/// Why a car was chosen for a shot.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ShotReason {
+    Leader,
+    /// Battle for position with the car ahead.
+    Battle {
+        car_ahead_idx: i32,
+    },
+    Incident,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Shot {
+    pub car_idx: i32,
+    pub camera_group: i32,
+    pub reason: ShotReason,
+    /// Session time when the shot started.
+    pub session_time: f64,
+}
+
+/// Picks the car to show from the running order and incidents, and switches the camera to it.
+///
+/// Decisions only depend on the sim states passed to [`CameraDirector::update`], so the director
+/// can be run against recorded `.ibt` files with a [`RecordingSink`](commands::RecordingSink).
+pub struct CameraDirector {
+    config: DirectorConfig,
+    sink: Arc<dyn CommandSink>,
+    current: Option<Shot>,
+    incident_counts: HashMap<i32, i64>,
+    /// Session time of the last incident of each car.
+    incidents: HashMap<i32, f64>,
+    rotation: usize,
+}
+
+impl CameraDirector {
+    /// Creates director sending commands to the [`commands::default_sink`].
+    pub fn new(config: DirectorConfig) -> Self {
+        Self::with_sink(config, commands::default_sink())
+    }
+
+    pub fn with_sink(config: DirectorConfig, sink: Arc<dyn CommandSink>) -> Self {
+        Self {
+            config,
+            sink,
+            current: None,
+            incident_counts: HashMap::new(),
+            incidents: HashMap::new(),
+            rotation: 0,
+        }
+    }
+
+    pub fn config(&self) -> &DirectorConfig {
+        &self.config
+    }
+
+    pub fn current_shot(&self) -> Option<&Shot> {
+        self.current.as_ref()
+    }
+
+    /// Directs the whole source, until it is done.
+    pub async fn run<S: SimStateSource>(&mut self, source: &mut S) {
+        while let Some(state) = source.next_sim_state().await {
+            self.update(&state);
+        }
+    }
+
+    /// Evaluates the sim state and switches the camera if a new shot was chosen.
+    ///
+    /// Returns the new shot if the camera was switched.
+    pub fn update(&mut self, state: &SimState) -> Option<Shot> {
+        let session_time = state.read_name::<f64>("SessionTime")?;
+        self.track_incidents(state, session_time);
+
+        let (car_idx, reason) = self.best_candidate(state)?;
+        if let Some(current) = &self.current {
+            let shot_length = session_time - current.session_time;
+            let may_cut = shot_length >= self.config.min_shot_duration.as_secs_f64()
+                || shot_length < 0.0
+                || (self.config.incidents_interrupt_shots
+                    && reason == ShotReason::Incident
+                    && current.reason != ShotReason::Incident);
+            if current.car_idx == car_idx || !may_cut {
+                return None;
+            }
+        }
+
+        let camera_group = self.camera_group(state, reason)?;
+        let car_number = car_number(state.session_info(), car_idx).ok()?;
+        commands::with_sink(Arc::clone(&self.sink), || {
+            camera::switch_to_car_number(car_number, camera_group as u16, 0)
+        });
+        self.rotation = self.rotation.wrapping_add(1);
+        let shot = Shot {
+            car_idx,
+            camera_group,
+            reason,
+            session_time,
+        };
+        self.current = Some(shot.clone());
+        Some(shot)
+    }
+
+    fn track_incidents(&mut self, state: &SimState, session_time: f64) {
+        let drivers = racing_drivers(state.session_info());
+        for (car_idx, driver) in &drivers {
+            let off_track = state.read_name_at::<i32>("CarIdxTrackSurface", *car_idx as usize)
+                == Some(TRACK_SURFACE_OFF_TRACK);
+            let count = driver["CurDriverIncidentCount"].as_i64().unwrap_or(0);
+            let previous = self.incident_counts.insert(*car_idx, count);
+            let new_incident = previous.is_some_and(|previous| count > previous);
+            if off_track || new_incident {
+                self.incidents.insert(*car_idx, session_time);
+            }
+        }
+        let hold = self.config.incident_hold.as_secs_f64();
+        self.incidents
+            .retain(|_, time| (0.0..=hold).contains(&(session_time - *time)));
+    }
+
+    fn best_candidate(&self, state: &SimState) -> Option<(i32, ShotReason)> {
+        // Position and total distance in laps of every car that is racing.
+        let mut running_order = racing_drivers(state.session_info())
+            .into_iter()
+            .filter_map(|(car_idx, _)| {
+                let idx = car_idx as usize;
+                let position = state.read_name_at::<i32>("CarIdxPosition", idx)?;
+                let lap = state.read_name_at::<i32>("CarIdxLap", idx)?;
+                let lap_dist_pct = state.read_name_at::<f32>("CarIdxLapDistPct", idx)?;
+                if position <= 0 || lap_dist_pct < 0.0 {
+                    return None;
+                }
+                Some((car_idx, position, lap as f64 + lap_dist_pct as f64))
+            })
+            .collect::<Vec<_>>();
+        running_order.sort_by_key(|(_, position, _)| *position);
+
+        let config = &self.config;
+        let leader_score = |position: i32| config.leader_weight / position as f64;
+        let mut best: Option<(f64, i32, ShotReason)> = None;
+        let mut consider = |score: f64, car_idx: i32, reason: ShotReason| {
+            if best.map_or(true, |(best_score, _, _)| score > best_score) {
+                best = Some((score, car_idx, reason));
+            }
+        };
+
+        for (idx, (car_idx, position, distance)) in running_order.iter().enumerate() {
+            consider(leader_score(*position), *car_idx, ShotReason::Leader);
+            if self.incidents.contains_key(car_idx) {
+                consider(
+                    config.incident_weight + leader_score(*position),
+                    *car_idx,
+                    ShotReason::Incident,
+                );
+            }
+            let Some((car_ahead_idx, position_ahead, distance_ahead)) =
+                idx.checked_sub(1).map(|v| running_order[v])
+            else {
+                continue;
+            };
+            let gap = distance_ahead - distance;
+            if (0.0..config.battle_gap).contains(&gap) {
+                consider(
+                    config.battle_weight * (1.0 - gap / config.battle_gap)
+                        + leader_score(position_ahead),
+                    *car_idx,
+                    ShotReason::Battle { car_ahead_idx },
+                );
+            }
+        }
+
+        best.map(|(_, car_idx, reason)| (car_idx, reason))
+    }
+
+    fn camera_group(&self, state: &SimState, reason: ShotReason) -> Option<i32> {
+        let names = match reason {
+            ShotReason::Leader => &self.config.leader_camera_groups,
+            ShotReason::Battle { .. } => &self.config.battle_camera_groups,
+            ShotReason::Incident => &self.config.incident_camera_groups,
+        };
+        let groups = state.session_info()["CameraInfo"]["Groups"].as_vec();
+        let available = names
+            .iter()
+            .filter_map(|name| {
+                groups?.iter().find(|group| {
+                    group["GroupName"]
+                        .as_str()
+                        .is_some_and(|v| v.eq_ignore_ascii_case(name))
+                })?["GroupNum"]
+                    .as_i64()
+            })
+            .collect::<Vec<_>>();
+        match available.is_empty() {
+            false => Some(available[self.rotation % available.len()] as i32),
+            // Keep the group the camera is in now.
+            true => state.read_name("CamGroupNumber"),
+        }
+    }
+}
+
+/// Drivers which take part in the race, excluding the pace car and spectators.
+fn racing_drivers(session_info: &Yaml) -> Vec<(i32, &Yaml)> {
+    let Some(drivers) = session_info["DriverInfo"]["Drivers"].as_vec() else {
+        return Vec::new();
+    };
+    drivers
+        .iter()
+        .filter(|driver| {
+            driver["CarIsPaceCar"].as_i64().unwrap_or(0) == 0
+                && driver["IsSpectator"].as_i64().unwrap_or(0) == 0
+        })
+        .filter_map(|driver| Some((driver["CarIdx"].as_i64()? as i32, driver)))
+        .collect()
+}
@@ -1,21 +1,33 @@
 use crate::iracing::constants::IRSDK_VER;
 use crate::iracing::header::{VarBuf, VarHeaderRaw};
 use crate::iracing::session_info::parse_session_info;
-use crate::iracing::{Header, SimState, VarHeader, VarHeaders};
-use crate::{windows_util, Moment, Simetry};
-use anyhow::{bail, Result};
+use crate::iracing::{
+    Header, SimState, SimStateSource, Subscriptions, VarChange, VarHeader, VarHeaders,
+};
+#[cfg(target_family = "unix")]
+use crate::unix_util::{self as platform, SharedMemoryPath};
+#[cfg(target_family = "windows")]
+use crate::windows_util as platform;
+use crate::{Moment, Simetry};
+use anyhow::{bail, Context, Result};
 use std::collections::HashMap;
+use std::future::Future;
 use std::slice::from_raw_parts;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
+#[cfg(target_family = "windows")]
 use tokio::task::spawn_blocking;
+#[cfg(target_family = "windows")]
 use windows::core::PCSTR;
+#[cfg(target_family = "windows")]
 use windows::Win32::System::Threading::{
     OpenEventA, WaitForSingleObject, SYNCHRONIZATION_SYNCHRONIZE,
 };
+#[cfg(target_family = "windows")]
 use windows::Win32::System::WindowsProgramming::INFINITE;
 use yaml_rust::Yaml;
 
+#[cfg(target_family = "windows")]
 static DATAVALIDEVENTNAME: &[u8] = b"Local\\IRSDKDataValidEvent\0";
 static MEMMAPFILENAME: &[u8] = b"Local\\IRSDKMemMapFileName\0";
 
@@ -29,6 +41,7 @@ pub struct Client {
     session_info_cache: SessionInfoCache,
     last_tick_count: i32,
     last_valid_time: Option<SystemTime>,
+    subscriptions: Subscriptions,
 
     shared_memory: SharedMemory,
     data_valid_event: DataValidEvent,
@@ -44,8 +57,35 @@ impl Client {
         }
     }
 
+    #[cfg(target_family = "windows")]
+    pub async fn try_connect() -> Result<Self> {
+        Self::try_connect_to(SharedMemory::connect()).await
+    }
+
+    /// Connects to `/IRSDKMemMapFileName`, see [`Client::try_connect_with_path`].
+    #[cfg(target_family = "unix")]
     pub async fn try_connect() -> Result<Self> {
-        let shared_memory = SharedMemory::connect();
+        Self::try_connect_with_path(&SharedMemoryPath::windows_name(MEMMAPFILENAME)).await
+    }
+
+    #[cfg(target_family = "unix")]
+    pub async fn connect_with_path(path: &SharedMemoryPath, retry_delay: Duration) -> Self {
+        loop {
+            if let Ok(v) = Self::try_connect_with_path(path).await {
+                return v;
+            }
+            tokio::time::sleep(retry_delay).await;
+        }
+    }
+
+    /// Connects to the memory mapped file exposed at `path`, without the data valid event the
+    /// memory is polled.
+    #[cfg(target_family = "unix")]
+    pub async fn try_connect_with_path(path: &SharedMemoryPath) -> Result<Self> {
+        Self::try_connect_to(SharedMemory::connect(path)).await
+    }
+
+    async fn try_connect_to(shared_memory: impl Future<Output = SharedMemory>) -> Result<Self> {
         let data_valid_event = DataValidEvent::connect();
 
         let shared_memory = shared_memory.await;
@@ -68,6 +108,7 @@ impl Client {
             session_info_cache: SessionInfoCache::default(),
             last_tick_count: i32::MAX,
             last_valid_time: None,
+            subscriptions: Subscriptions::default(),
             shared_memory,
             data_valid_event,
         })
@@ -89,11 +130,35 @@ impl Client {
         }
     }
 
+    /// Variables watched by [`Client::next_change`].
+    pub fn subscriptions_mut(&mut self) -> &mut Subscriptions {
+        &mut self.subscriptions
+    }
+
+    /// Waits for the next change of a subscribed variable.
+    ///
+    /// Changes are detected between sim states received through this function, so states read
+    /// with [`Client::next_sim_state`] in the meantime are not compared against each other.
+    ///
+    /// Returns `None` when disconnected, or if there are no subscriptions.
+    pub async fn next_change(&mut self) -> Option<VarChange> {
+        loop {
+            if let Some(change) = self.subscriptions.next_change() {
+                return Some(change);
+            }
+            if self.subscriptions.is_empty() {
+                return None;
+            }
+            let sim_state = self.next_sim_state().await?;
+            self.subscriptions.update(&sim_state);
+        }
+    }
+
     fn get_new_sim_state(&mut self) -> Option<SimState> {
         let header = self.shared_memory.header();
 
         if self.vars_at_buf_len != header.buf_len {
-            self.vars = Arc::new(self.shared_memory.get_var_headers());
+            self.vars = Arc::new(self.shared_memory.get_var_headers().ok()?);
         }
 
         if header.status & STATUS_CONNECTED_FLAG == 0 {
@@ -102,7 +167,7 @@ impl Client {
         }
 
         let mut latest_buffer_idx = 0;
-        for idx in 1..(header.num_buf as usize) {
+        for idx in 1..(header.num_buf as usize).min(header.var_buf.len()) {
             if header.var_buf[latest_buffer_idx].tick_count < header.var_buf[idx].tick_count {
                 latest_buffer_idx = idx;
             }
@@ -118,7 +183,7 @@ impl Client {
         // Two attempts to retrieve data
         for _ in 0..2 {
             let tick_count = buffer.tick_count;
-            let data = self.shared_memory.data(header, buffer);
+            let data = self.shared_memory.data(header, buffer).ok()?;
             if tick_count == buffer.tick_count {
                 self.last_tick_count = tick_count;
                 self.last_valid_time = Some(SystemTime::now());
@@ -168,6 +233,13 @@ impl Simetry for Client {
     }
 }
 
+#[async_trait::async_trait]
+impl SimStateSource for Client {
+    async fn next_sim_state(&mut self) -> Option<SimState> {
+        Client::next_sim_state(self).await
+    }
+}
+
 #[derive(Default)]
 struct SessionInfoCache {
     content: Option<(i32, Arc<Yaml>)>,
@@ -181,17 +253,25 @@ impl SessionInfoCache {
                 return Ok(Arc::clone(data));
             }
         }
-        let session_info = Arc::new(parse_session_info(shared_memory.raw_session_info())?);
+        let session_info = Arc::new(parse_session_info(shared_memory.raw_session_info()?)?);
         self.content = Some((new_id, Arc::clone(&session_info)));
         Ok(session_info)
     }
 }
 
-struct SharedMemory(windows_util::SharedMemory);
+struct SharedMemory(platform::SharedMemory);
 
 impl SharedMemory {
+    #[cfg(target_family = "windows")]
     async fn connect() -> Self {
-        Self(windows_util::SharedMemory::connect(MEMMAPFILENAME, Duration::from_millis(250)).await)
+        Self(platform::SharedMemory::connect(MEMMAPFILENAME, Duration::from_millis(250)).await)
+    }
+
+    #[cfg(target_family = "unix")]
+    async fn connect(path: &SharedMemoryPath) -> Self {
+        let min_len = std::mem::size_of::<Header>();
+        let poll_delay = Duration::from_millis(250);
+        Self(platform::SharedMemory::connect_path(path, min_len, poll_delay).await)
     }
 
     fn header(&self) -> &Header {
@@ -202,51 +282,68 @@ impl SharedMemory {
         (self.header().status & STATUS_CONNECTED_FLAG) != 0
     }
 
-    fn raw_var_headers(&self) -> &[VarHeaderRaw] {
+    /// `len` bytes at `offset`, checked against the current size of the memory, since a file
+    /// exposed by a bridge may be shorter than its header claims. See [`SharedMemoryPath::File`]
+    /// for files that shrink while mapped.
+    fn bytes(&self, offset: i32, len: usize) -> Result<&[u8]> {
+        let start = usize::try_from(offset).context("Negative offset in shared memory")?;
+        let end = start
+            .checked_add(len)
+            .context("Shared memory range overflows")?;
+        let available = self.0.available_len();
+        if end > available {
+            bail!("Range {start}..{end} is outside of the {available} bytes of shared memory");
+        }
+        Ok(unsafe { from_raw_parts((self.0.get() as *const u8).add(start), len) })
+    }
+
+    fn raw_var_headers(&self) -> Result<&[VarHeaderRaw]> {
         let header = self.header();
-        unsafe {
-            from_raw_parts(
-                (self.0.get() as *const u8).offset(header.var_header_offset as isize)
-                    as *const VarHeaderRaw,
-                header.num_vars as usize,
-            )
+        let num_vars = usize::try_from(header.num_vars).context("Negative number of variables")?;
+        let bytes = self.bytes(
+            header.var_header_offset,
+            num_vars * std::mem::size_of::<VarHeaderRaw>(),
+        )?;
+        if bytes
+            .as_ptr()
+            .align_offset(std::mem::align_of::<VarHeaderRaw>())
+            != 0
+        {
+            bail!("Variable headers are not aligned");
         }
+        Ok(unsafe { from_raw_parts(bytes.as_ptr() as *const VarHeaderRaw, num_vars) })
     }
 
-    fn get_var_headers(&self) -> VarHeaders {
-        self.raw_var_headers()
+    fn get_var_headers(&self) -> Result<VarHeaders> {
+        Ok(self
+            .raw_var_headers()?
             .iter()
             .filter_map(|var_header_raw| {
                 let var_header = VarHeader::from_raw(var_header_raw).ok()?;
                 Some((var_header.name.clone(), var_header))
             })
-            .collect()
+            .collect())
     }
 
-    fn data(&self, header: &Header, buffer: &VarBuf) -> &[u8] {
-        unsafe {
-            from_raw_parts(
-                (self.0.get() as *const u8).offset(buffer.buf_offset as isize),
-                header.buf_len as usize,
-            )
-        }
+    fn data(&self, header: &Header, buffer: &VarBuf) -> Result<&[u8]> {
+        let len = usize::try_from(header.buf_len).context("Negative buffer length")?;
+        self.bytes(buffer.buf_offset, len)
     }
 
-    fn raw_session_info(&self) -> &[u8] {
+    fn raw_session_info(&self) -> Result<&[u8]> {
         let header = self.header();
-        unsafe {
-            from_raw_parts(
-                (self.0.get() as *const u8).offset(header.session_info_offset as isize),
-                header.session_info_len as usize,
-            )
-        }
+        let len =
+            usize::try_from(header.session_info_len).context("Negative session info length")?;
+        self.bytes(header.session_info_offset, len)
     }
 }
 
+#[cfg(target_family = "windows")]
 struct DataValidEvent {
-    handle: windows_util::SafeHandle,
+    handle: platform::SafeHandle,
 }
 
+#[cfg(target_family = "windows")]
 impl DataValidEvent {
     async fn connect() -> Self {
         let poll_delay = Duration::from_millis(250);
@@ -260,7 +357,7 @@ impl DataValidEvent {
                     )
                 }
                 .ok()
-                .and_then(windows_util::SafeHandle::new);
+                .and_then(platform::SafeHandle::new);
                 if let Some(handle) = handle_opt {
                     return Self { handle };
                 }
@@ -279,3 +376,21 @@ impl DataValidEvent {
         .ok();
     }
 }
+
+/// Stands in for the event on Unix, where bridges only expose the memory.
+#[cfg(target_family = "unix")]
+struct DataValidEvent;
+
+#[cfg(target_family = "unix")]
+impl DataValidEvent {
+    const POLL_DELAY: Duration = Duration::from_millis(2);
+
+    async fn connect() -> Self {
+        Self
+    }
+
+    async fn wait(&self, timeout: Option<Duration>) {
+        let delay = timeout.map_or(Self::POLL_DELAY, |v| v.min(Self::POLL_DELAY));
+        tokio::time::sleep(delay).await;
+    }
+}
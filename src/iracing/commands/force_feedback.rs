@@ -1,6 +1,7 @@
 //! Commands to control force feedback.
 //!
 //! Using this override blocks the menu setting.
+//! [`FfbCalibrator`](crate::iracing::FfbCalibrator) can recommend the max force to set.
 
 use super::BROADCAST_FFBCOMMAND;
 
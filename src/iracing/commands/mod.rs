@@ -1,17 +1,23 @@
-use once_cell::sync::Lazy;
-use std::marker::PhantomData;
-use windows::core::PCSTR;
-use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
-use windows::Win32::UI::WindowsAndMessaging::{RegisterWindowMessageA, SendNotifyMessageA};
+//! Commands sent to iRacing as broadcast messages.
+//!
+//! Functions in the submodules send their messages to the current [`CommandSink`].
+//! By default that is [`BroadcastSink`] on Windows, and it can be replaced with
+//! [`set_default_sink`], or temporarily for the current thread with [`with_sink`]. Failures to
+//! send are only logged, use [`try_with_sink`] to get them.
 
-static BROADCASTMSGNAME: &[u8] = b"IRSDK_BROADCASTMSG\0";
+use std::marker::PhantomData;
 
-static IRACING_BROADCAST_MSG_ID: Lazy<u32> =
-    Lazy::new(|| unsafe { RegisterWindowMessageA(PCSTR::from_raw(BROADCASTMSGNAME.as_ptr())) });
+pub use network::{CommandReceiver, NetworkSink};
+pub use sink::{
+    default_sink, set_default_sink, try_with_sink, with_sink, BroadcastMessage, CommandSink,
+    RecordingSink,
+};
+#[cfg(target_family = "windows")]
+pub use win32::BroadcastSink;
 
 struct RawParams {
     var1: u16,
-    var2: isize,
+    var2: i64,
 }
 
 fn make_long(a: u16, b: u16) -> u32 {
@@ -38,7 +44,7 @@ impl From<Param2u> for RawParams {
     fn from((var1, var2): Param2u) -> Self {
         Self {
             var1,
-            var2: make_long(var2, 0u16) as isize,
+            var2: make_long(var2, 0u16) as i64,
         }
     }
 }
@@ -47,7 +53,7 @@ impl From<Param3> for RawParams {
     fn from((var1, var2, var3): Param3) -> Self {
         Self {
             var1,
-            var2: make_long(var2, var3) as isize,
+            var2: make_long(var2, var3) as i64,
         }
     }
 }
@@ -56,7 +62,7 @@ impl From<Param2i> for RawParams {
     fn from((var1, var2): Param2i) -> Self {
         Self {
             var1,
-            var2: var2 as isize,
+            var2: var2 as i64,
         }
     }
 }
@@ -65,7 +71,7 @@ impl From<Param2f> for RawParams {
     fn from((var1, var2): Param2f) -> Self {
         Self {
             var1,
-            var2: (var2 * 65536.0) as isize,
+            var2: (var2 * 65536.0) as i64,
         }
     }
 }
@@ -85,15 +91,11 @@ impl<T: Into<RawParams>> Command<T> {
 
     fn run(&self, args: T) {
         let params = args.into();
-        let param1 = make_long(self.code, params.var1) as usize;
-        unsafe {
-            SendNotifyMessageA(
-                HWND(0xffff),
-                *IRACING_BROADCAST_MSG_ID,
-                WPARAM(param1),
-                LPARAM(params.var2),
-            )
-        };
+        sink::send(BroadcastMessage {
+            code: self.code,
+            var1: params.var1,
+            var2: params.var2,
+        })
     }
 }
 
@@ -115,8 +117,12 @@ const BROADCAST_VIDEO_CAPTURE: Command<Param1> = Command::new(13);
 pub mod camera;
 pub mod chat;
 pub mod force_feedback;
+mod network;
 pub mod pit;
 pub mod reload_textures;
 pub mod replay;
+mod sink;
 pub mod telemetry;
 pub mod video;
+#[cfg(target_family = "windows")]
+mod win32;
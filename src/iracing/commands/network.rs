@@ -0,0 +1,108 @@
+use super::{BroadcastMessage, CommandSink};
+use anyhow::{bail, Context, Result};
+use byteorder::{ByteOrder, LittleEndian};
+use std::net::{SocketAddr, ToSocketAddrs};
+
+const MAGIC: &[u8; 4] = b"IRBC";
+const PACKET_SIZE: usize = 16;
+
+fn encode(message: BroadcastMessage) -> [u8; PACKET_SIZE] {
+    let mut packet = [0u8; PACKET_SIZE];
+    packet[0..4].copy_from_slice(MAGIC);
+    LittleEndian::write_u16(&mut packet[4..6], message.code);
+    LittleEndian::write_u16(&mut packet[6..8], message.var1);
+    LittleEndian::write_i64(&mut packet[8..16], message.var2);
+    packet
+}
+
+fn decode(packet: &[u8]) -> Result<BroadcastMessage> {
+    if packet.len() != PACKET_SIZE || &packet[0..4] != MAGIC {
+        bail!("Received packet is not an iRacing command");
+    }
+    Ok(BroadcastMessage {
+        code: LittleEndian::read_u16(&packet[4..6]),
+        var1: LittleEndian::read_u16(&packet[6..8]),
+        var2: LittleEndian::read_i64(&packet[8..16]),
+    })
+}
+
+/// Sends commands over UDP to a [`CommandReceiver`], usually running on the machine with iRacing.
+///
+/// Messages are neither authenticated nor acknowledged, so only use this on trusted networks.
+#[derive(Debug)]
+pub struct NetworkSink {
+    socket: std::net::UdpSocket,
+}
+
+impl NetworkSink {
+    pub fn connect<A: ToSocketAddrs>(receiver: A) -> Result<Self> {
+        let receiver = receiver
+            .to_socket_addrs()?
+            .next()
+            .context("Receiver address did not resolve")?;
+        let local = if receiver.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = std::net::UdpSocket::bind(local)?;
+        socket.connect(receiver)?;
+        Ok(Self { socket })
+    }
+}
+
+impl CommandSink for NetworkSink {
+    fn send(&self, message: BroadcastMessage) -> Result<()> {
+        self.socket.send(&encode(message))?;
+        Ok(())
+    }
+}
+
+/// Receives commands sent by a [`NetworkSink`].
+///
+/// Run [`CommandReceiver::forward_to`] with a [`BroadcastSink`](super::BroadcastSink) on the
+/// machine running iRacing to execute them.
+///
+/// Messages are not authenticated, so binding to an address reachable from the network lets
+/// anyone on it send pit, chat and camera commands. [`CommandReceiver::DEFAULT_URI`] only
+/// accepts commands from the same machine.
+#[derive(Debug)]
+pub struct CommandReceiver {
+    socket: tokio::net::UdpSocket,
+}
+
+impl CommandReceiver {
+    pub const DEFAULT_URI: &'static str = "127.0.0.1:24811";
+
+    pub async fn bind(uri: &str) -> Result<Self> {
+        Ok(Self {
+            socket: tokio::net::UdpSocket::bind(uri).await?,
+        })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.socket.local_addr()?)
+    }
+
+    /// Waits for the next valid message, ignoring any other traffic.
+    pub async fn recv(&self) -> Result<(BroadcastMessage, SocketAddr)> {
+        let mut buffer = [0u8; PACKET_SIZE + 1];
+        loop {
+            let (len, origin) = self.socket.recv_from(&mut buffer).await?;
+            match decode(&buffer[..len]) {
+                Ok(message) => return Ok((message, origin)),
+                Err(err) => log::debug!("Ignoring packet from {origin}: {err}"),
+            }
+        }
+    }
+
+    /// Forwards all received messages to `sink` until the socket fails.
+    pub async fn forward_to(&self, sink: &dyn CommandSink) -> Result<()> {
+        loop {
+            let (message, origin) = self.recv().await?;
+            if let Err(err) = sink.send(message) {
+                log::warn!("Failed to forward {message:?} from {origin}: {err:#}");
+            }
+        }
+    }
+}
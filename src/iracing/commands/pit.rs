@@ -1,4 +1,7 @@
 //! Commands to control pitstops.
+//!
+//! See [`PitServiceRequest`](crate::iracing::PitServiceRequest) for validated requests that
+//! only send what differs from the current pit service.
 
 use super::BROADCAST_PIT_COMMAND;
 
@@ -0,0 +1,129 @@
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Raw broadcast message understood by iRacing.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub struct BroadcastMessage {
+    /// Type of the message, such as camera switch or pit command.
+    pub code: u16,
+    /// First argument, sent in the high word of `WPARAM`.
+    pub var1: u16,
+    /// Remaining arguments, sent as `LPARAM`.
+    pub var2: i64,
+}
+
+/// Destination of commands for iRacing.
+pub trait CommandSink: Send + Sync {
+    fn send(&self, message: BroadcastMessage) -> Result<()>;
+}
+
+impl<T: CommandSink + ?Sized> CommandSink for Arc<T> {
+    fn send(&self, message: BroadcastMessage) -> Result<()> {
+        (**self).send(message)
+    }
+}
+
+/// Sink that stores messages in memory instead of sending them, useful for testing.
+#[derive(Debug, Default)]
+pub struct RecordingSink {
+    messages: Mutex<Vec<BroadcastMessage>>,
+}
+
+impl RecordingSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// All messages received so far.
+    pub fn messages(&self) -> Vec<BroadcastMessage> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    /// Removes and returns all messages received so far.
+    pub fn take(&self) -> Vec<BroadcastMessage> {
+        std::mem::take(&mut *self.messages.lock().unwrap())
+    }
+}
+
+impl CommandSink for RecordingSink {
+    fn send(&self, message: BroadcastMessage) -> Result<()> {
+        self.messages.lock().unwrap().push(message);
+        Ok(())
+    }
+}
+
+#[cfg(not(target_family = "windows"))]
+struct UnavailableSink;
+
+#[cfg(not(target_family = "windows"))]
+impl CommandSink for UnavailableSink {
+    fn send(&self, _message: BroadcastMessage) -> Result<()> {
+        anyhow::bail!("No command sink configured, use `set_default_sink` on this platform")
+    }
+}
+
+static DEFAULT_SINK: Lazy<RwLock<Arc<dyn CommandSink>>> = Lazy::new(|| {
+    #[cfg(target_family = "windows")]
+    let sink = Arc::new(super::BroadcastSink);
+    #[cfg(not(target_family = "windows"))]
+    let sink = Arc::new(UnavailableSink);
+    RwLock::new(sink)
+});
+
+thread_local! {
+    static SCOPED_SINK: RefCell<Option<Arc<dyn CommandSink>>> = RefCell::new(None);
+    /// First failure of a command run by [`try_with_sink`].
+    static SEND_ERROR: RefCell<Option<anyhow::Error>> = const { RefCell::new(None) };
+}
+
+/// Sink used by commands when no sink was set with [`with_sink`].
+pub fn default_sink() -> Arc<dyn CommandSink> {
+    Arc::clone(&DEFAULT_SINK.read().unwrap())
+}
+
+/// Replaces the sink used by commands when no sink was set with [`with_sink`].
+pub fn set_default_sink(sink: Arc<dyn CommandSink>) {
+    *DEFAULT_SINK.write().unwrap() = sink;
+}
+
+/// Runs `f` with all commands on the current thread going to `sink`.
+pub fn with_sink<R>(sink: Arc<dyn CommandSink>, f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<Arc<dyn CommandSink>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            SCOPED_SINK.with(|v| *v.borrow_mut() = previous);
+        }
+    }
+
+    let _restore = Restore(SCOPED_SINK.with(|v| v.replace(Some(sink))));
+    f()
+}
+
+/// Like [`with_sink`], failing if any command could not be sent.
+///
+/// Commands themselves only log failures, so this tells whether they reached the sink, such as
+/// a [`NetworkSink`](super::NetworkSink) to another machine. All commands are still run.
+pub fn try_with_sink<R>(sink: Arc<dyn CommandSink>, f: impl FnOnce() -> R) -> Result<R> {
+    let outer = SEND_ERROR.with(|v| v.borrow_mut().take());
+    let result = with_sink(sink, f);
+    match SEND_ERROR.with(|v| v.replace(outer)) {
+        Some(err) => Err(err),
+        None => Ok(result),
+    }
+}
+
+pub(super) fn send(message: BroadcastMessage) {
+    let sink = SCOPED_SINK
+        .with(|v| v.borrow().clone())
+        .unwrap_or_else(default_sink);
+    if let Err(err) = sink.send(message) {
+        log::warn!("Failed to send iRacing command {message:?}: {err:#}");
+        SEND_ERROR.with(|v| {
+            v.borrow_mut().get_or_insert(err);
+        });
+    }
+}
@@ -0,0 +1,31 @@
+use super::{make_long, BroadcastMessage, CommandSink};
+use anyhow::Result;
+use once_cell::sync::Lazy;
+use windows::core::PCSTR;
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::UI::WindowsAndMessaging::{RegisterWindowMessageA, SendNotifyMessageA};
+
+static BROADCASTMSGNAME: &[u8] = b"IRSDK_BROADCASTMSG\0";
+
+static IRACING_BROADCAST_MSG_ID: Lazy<u32> =
+    Lazy::new(|| unsafe { RegisterWindowMessageA(PCSTR::from_raw(BROADCASTMSGNAME.as_ptr())) });
+
+/// Sends commands to iRacing running on this machine, using window messages.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct BroadcastSink;
+
+impl CommandSink for BroadcastSink {
+    fn send(&self, message: BroadcastMessage) -> Result<()> {
+        let param1 = make_long(message.code, message.var1) as usize;
+        unsafe {
+            SendNotifyMessageA(
+                HWND(0xffff),
+                *IRACING_BROADCAST_MSG_ID,
+                WPARAM(param1),
+                LPARAM(message.var2 as isize),
+            )
+        }
+        .ok()?;
+        Ok(())
+    }
+}
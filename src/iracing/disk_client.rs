@@ -1,7 +1,10 @@
 use crate::iracing::constants::IRSDK_VER;
 use crate::iracing::header::VarHeaderRaw;
 use crate::iracing::session_info::parse_session_info;
-use crate::iracing::{DiskSubHeader, Header, SimState, VarHeader, VarHeaders};
+use crate::iracing::{
+    DiskSubHeader, Header, SimState, SimStateSource, Subscriptions, VarChange, VarHeader,
+    VarHeaders,
+};
 use anyhow::{bail, Result};
 use std::fs::File;
 use std::io::{Read, Seek, SeekFrom};
@@ -16,6 +19,7 @@ pub struct DiskClient {
     sub_header: DiskSubHeader,
     variables: Arc<VarHeaders>,
     session_info: Arc<Yaml>,
+    subscriptions: Subscriptions,
 }
 
 impl DiskClient {
@@ -54,6 +58,7 @@ impl DiskClient {
             sub_header,
             variables,
             session_info,
+            subscriptions: Subscriptions::default(),
         })
     }
 
@@ -83,6 +88,34 @@ impl DiskClient {
             Arc::clone(&self.session_info),
         ))
     }
+
+    /// Variables watched by [`DiskClient::next_change`].
+    pub fn subscriptions_mut(&mut self) -> &mut Subscriptions {
+        &mut self.subscriptions
+    }
+
+    /// Reads rows until the next change of a subscribed variable.
+    ///
+    /// Returns `None` at the end of the file, or if there are no subscriptions.
+    pub fn next_change(&mut self) -> Option<VarChange> {
+        loop {
+            if let Some(change) = self.subscriptions.next_change() {
+                return Some(change);
+            }
+            if self.subscriptions.is_empty() {
+                return None;
+            }
+            let sim_state = self.next_sim_state()?;
+            self.subscriptions.update(&sim_state);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl SimStateSource for DiskClient {
+    async fn next_sim_state(&mut self) -> Option<SimState> {
+        DiskClient::next_sim_state(self)
+    }
 }
 
 fn read_struct<T, R: Read>(mut read: R) -> Result<T> {
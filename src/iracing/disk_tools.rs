@@ -0,0 +1,328 @@
+//! Editing of `.ibt` files, built on [`DiskClient`] and [`DiskWriter`].
+//!
+//! The session info of each output is fitted to the rows written to it: sessions without rows
+//! are left out, and results are cut at the last lap completed in the rows of their session.
+
+use crate::iracing::{DiskClient, DiskWriter, SimState, VarHeaders};
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use yaml_rust::Yaml;
+
+/// Part of a file written by [`split_file`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Segment {
+    /// Each value of `Lap`.
+    Lap,
+    /// Driving between pit exits, detected when `OnPitRoad` becomes false.
+    Stint,
+}
+
+/// Copy the rows for which `keep` returns true, leaving out the variables in `drop_variables`.
+pub fn trim_file<F: FnMut(&SimState) -> bool>(
+    input: &Path,
+    output: &Path,
+    drop_variables: &[&str],
+    mut keep: F,
+) -> Result<()> {
+    let mut client = DiskClient::open(input)?;
+    let mut kept = Vec::new();
+    let mut written = Written::default();
+    while let Some(sim_state) = client.next_sim_state() {
+        let keep = keep(&sim_state);
+        if keep {
+            written.add(&sim_state);
+        }
+        kept.push(keep);
+    }
+
+    let mut client = DiskClient::open(input)?;
+    let mut writer = create_like(&client, output, drop_variables, &written)?;
+    for keep in kept {
+        let sim_state = client
+            .next_sim_state()
+            .context("Input changed while trimming")?;
+        if keep {
+            writer.write_sim_state(&sim_state)?;
+        }
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+/// Write each segment of the input to its own file, named by `output` from the segment index.
+///
+/// Returns the files that were written.
+pub fn split_file<F: FnMut(usize) -> PathBuf>(
+    input: &Path,
+    segment: Segment,
+    drop_variables: &[&str],
+    mut output: F,
+) -> Result<Vec<PathBuf>> {
+    let mut client = DiskClient::open(input)?;
+    let mut segments = Vec::<Written>::new();
+    let mut rows = Vec::new();
+    let mut last_lap = None;
+    let mut was_on_pit_road = false;
+    while let Some(sim_state) = client.next_sim_state() {
+        let starts_segment = match segment {
+            Segment::Lap => {
+                let lap = sim_state.read_name::<i32>("Lap").context("Missing Lap")?;
+                last_lap.replace(lap) != Some(lap)
+            }
+            Segment::Stint => {
+                let on_pit_road = sim_state
+                    .read_name::<bool>("OnPitRoad")
+                    .context("Missing OnPitRoad")?;
+                let pit_exit = was_on_pit_road && !on_pit_road;
+                was_on_pit_road = on_pit_road;
+                pit_exit || segments.is_empty()
+            }
+        };
+        if starts_segment {
+            segments.push(Written::default());
+        }
+        segments.last_mut().unwrap().add(&sim_state);
+        rows.push(segments.len() - 1);
+    }
+
+    let mut client = DiskClient::open(input)?;
+    let mut outputs = Vec::new();
+    let mut writer: Option<DiskWriter<BufWriter<File>>> = None;
+    for idx in rows {
+        let sim_state = client
+            .next_sim_state()
+            .context("Input changed while splitting")?;
+        if idx == outputs.len() {
+            if let Some(writer) = writer.take() {
+                writer.finish()?;
+            }
+            let path = output(idx);
+            writer = Some(create_like(&client, &path, drop_variables, &segments[idx])?);
+            outputs.push(path);
+        }
+        writer.as_mut().unwrap().write_sim_state(&sim_state)?;
+    }
+    if let Some(writer) = writer {
+        writer.finish()?;
+    }
+    Ok(outputs)
+}
+
+/// Concatenate files, such as the stints of an endurance race, into one timeline.
+///
+/// When `SessionTime` of a file starts before the end of the previous one, it is shifted to
+/// continue right after it. The output has all variables of the inputs, with rows of inputs
+/// that lack some of them written as zero. Its session info is that of the last input, which
+/// has the most complete results, with the sessions of all inputs.
+pub fn merge_files<P: AsRef<Path>>(
+    inputs: &[P],
+    output: &Path,
+    drop_variables: &[&str],
+) -> Result<()> {
+    let mut clients = inputs
+        .iter()
+        .map(|input| DiskClient::open(input.as_ref()))
+        .collect::<Result<Vec<_>>>()?;
+    let Some(first) = clients.first() else {
+        bail!("No files to merge");
+    };
+    let tick_rate = first.header().tick_rate;
+    let session_start_date = first.sub_header().session_start_date;
+
+    let mut variables = VarHeaders::new();
+    let mut offset = 0;
+    for client in &clients {
+        let mut added = client.variables().values().collect::<Vec<_>>();
+        added.sort_by_key(|v| v.offset);
+        for var in added {
+            if drop_variables.contains(&var.name.as_str()) {
+                continue;
+            }
+            match variables.get(&var.name) {
+                Some(existing) if existing.var_type != var.var_type => {
+                    bail!("Variable {} has different types", var.name)
+                }
+                Some(existing) if existing.count != var.count => {
+                    bail!("Variable {} has different counts", var.name)
+                }
+                Some(_) => {}
+                None => {
+                    let mut var = var.clone();
+                    var.offset = offset;
+                    offset += var.byte_count();
+                    variables.insert(var.name.clone(), var);
+                }
+            }
+        }
+    }
+
+    let tick = 1.0 / tick_rate.max(1) as f64;
+    let mut shifts = Vec::with_capacity(clients.len());
+    let mut written = Written::default();
+    let mut last_session_time: Option<f64> = None;
+    for client in &mut clients {
+        let mut shift = None;
+        while let Some(sim_state) = client.next_sim_state() {
+            written.add(&sim_state);
+            let Some(session_time) = sim_state.read_name::<f64>("SessionTime") else {
+                continue;
+            };
+            let shift = *shift.get_or_insert(match last_session_time {
+                Some(last) if session_time <= last => last + tick - session_time,
+                _ => 0.0,
+            });
+            last_session_time = Some(session_time + shift);
+        }
+        shifts.push(shift.unwrap_or_default());
+    }
+
+    let session_infos = clients.iter().map(|v| v.session_info()).collect::<Vec<_>>();
+    let session_info = fit_session_info(&merge_sessions(&session_infos), &written);
+    let mut writer = DiskWriter::create(output, &variables, &session_info, tick_rate)?;
+    writer.sub_header_mut().session_start_date = session_start_date;
+    let session_time_offset = writer.variables().get("SessionTime").map(|v| v.offset);
+    for (input, shift) in inputs.iter().zip(shifts) {
+        let mut client = DiskClient::open(input.as_ref())?;
+        while let Some(sim_state) = client.next_sim_state() {
+            let mut row = writer.row_from_sim_state(&sim_state)?;
+            if let (Some(offset), Some(session_time)) = (
+                session_time_offset,
+                sim_state.read_name::<f64>("SessionTime"),
+            ) {
+                let session_time = session_time + shift;
+                row[offset..offset + 8].copy_from_slice(&session_time.to_le_bytes());
+            }
+            writer.write_raw(&row)?;
+        }
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+fn create_like(
+    client: &DiskClient,
+    output: &Path,
+    drop_variables: &[&str],
+    written: &Written,
+) -> Result<DiskWriter<BufWriter<File>>> {
+    let mut variables = client.variables().clone();
+    variables.retain(|name, _| !drop_variables.contains(&name.as_str()));
+    let mut writer = DiskWriter::create(
+        output,
+        &variables,
+        &fit_session_info(client.session_info(), written),
+        client.header().tick_rate,
+    )?;
+    writer.sub_header_mut().session_start_date = client.sub_header().session_start_date;
+    Ok(writer)
+}
+
+/// Sessions and laps of the rows written to a file.
+#[derive(Debug, Default)]
+struct Written {
+    /// Laps completed by `SessionNum`, from `LapCompleted` or else `Lap`.
+    laps_completed: BTreeMap<i64, Option<i64>>,
+}
+
+impl Written {
+    fn add(&mut self, sim_state: &SimState) {
+        let session_num = sim_state.read_name::<i32>("SessionNum").unwrap_or_default();
+        let laps_completed = sim_state
+            .read_name::<i32>("LapCompleted")
+            .or_else(|| sim_state.read_name::<i32>("Lap").map(|v| v - 1))
+            .map(|v| v.max(0) as i64);
+        let last = self.laps_completed.entry(session_num as i64).or_default();
+        *last = (*last).max(laps_completed);
+    }
+}
+
+/// Session info with only the written sessions, and their results cut at the written laps.
+fn fit_session_info(session_info: &Yaml, written: &Written) -> Yaml {
+    let mut session_info = session_info.clone();
+    let Some(Yaml::Array(sessions)) =
+        field(&mut session_info, "SessionInfo").and_then(|v| field(v, "Sessions"))
+    else {
+        return session_info;
+    };
+    sessions.retain(|session| {
+        session["SessionNum"]
+            .as_i64()
+            .is_some_and(|v| written.laps_completed.contains_key(&v))
+    });
+    for session in sessions {
+        let Some(Some(laps)) = session["SessionNum"]
+            .as_i64()
+            .and_then(|v| written.laps_completed.get(&v))
+            .copied()
+        else {
+            continue;
+        };
+        if session["ResultsLapsComplete"]
+            .as_i64()
+            .is_some_and(|v| v > laps)
+        {
+            set(session, "ResultsLapsComplete", Yaml::Integer(laps));
+        }
+        let Some(Yaml::Array(positions)) = field(session, "ResultsPositions") else {
+            continue;
+        };
+        for position in positions {
+            if position["LapsComplete"].as_i64().is_some_and(|v| v > laps) {
+                // Times of laps that were cut are unknown, which iRacing reports as -1.
+                set(position, "LapsComplete", Yaml::Integer(laps));
+                set(position, "Lap", Yaml::Integer(laps));
+                set(position, "Time", unknown_time());
+                set(position, "LastTime", unknown_time());
+            }
+            if position["FastestLap"].as_i64().is_some_and(|v| v > laps) {
+                set(position, "FastestLap", Yaml::Integer(-1));
+                set(position, "FastestTime", unknown_time());
+            }
+        }
+    }
+    session_info
+}
+
+/// Session info of the last input with the sessions of all inputs, preferring later ones.
+fn merge_sessions(session_infos: &[&Yaml]) -> Yaml {
+    let mut sessions = BTreeMap::new();
+    for session_info in session_infos {
+        for session in session_info["SessionInfo"]["Sessions"]
+            .as_vec()
+            .into_iter()
+            .flatten()
+        {
+            if let Some(session_num) = session["SessionNum"].as_i64() {
+                sessions.insert(session_num, session.clone());
+            }
+        }
+    }
+    let mut session_info = session_infos.last().map_or(Yaml::Null, |v| (*v).clone());
+    if let Some(Yaml::Array(merged)) =
+        field(&mut session_info, "SessionInfo").and_then(|v| field(v, "Sessions"))
+    {
+        *merged = sessions.into_values().collect();
+    }
+    session_info
+}
+
+fn field<'a>(yaml: &'a mut Yaml, name: &str) -> Option<&'a mut Yaml> {
+    match yaml {
+        Yaml::Hash(hash) => hash.get_mut(&Yaml::String(name.to_string())),
+        _ => None,
+    }
+}
+
+/// Replace an existing field, leaving out fields the session info does not have.
+fn set(yaml: &mut Yaml, name: &str, value: Yaml) {
+    if let Some(field) = field(yaml, name) {
+        *field = value;
+    }
+}
+
+fn unknown_time() -> Yaml {
+    Yaml::Real("-1.0000".to_string())
+}
@@ -0,0 +1,209 @@
+use crate::iracing::constants::IRSDK_VER;
+use crate::iracing::header::VarHeaderRaw;
+use crate::iracing::session_info::emit_session_info;
+use crate::iracing::{DiskSubHeader, Header, SimState, VarData, VarHeader, VarHeaders};
+use anyhow::{bail, Result};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::mem::size_of;
+use std::path::Path;
+use yaml_rust::Yaml;
+
+/// Value of [`Header::status`] for connected, as in the files written by iRacing.
+const STATUS_CONNECTED: i32 = 1;
+
+/// Writes telemetry in the `.ibt` format, which can be read by [`DiskClient`](super::DiskClient).
+///
+/// Rows are written as they come, and the headers are completed by [`DiskWriter::finish`].
+#[derive(Debug)]
+pub struct DiskWriter<W: Write + Seek> {
+    writer: W,
+    header: Header,
+    sub_header: DiskSubHeader,
+    variables: VarHeaders,
+    /// Variables in the order of the file.
+    order: Vec<String>,
+    laps: HashSet<i32>,
+}
+
+impl DiskWriter<BufWriter<File>> {
+    pub fn create<P: AsRef<Path>>(
+        path: P,
+        variables: &VarHeaders,
+        session_info: &Yaml,
+        tick_rate: i32,
+    ) -> Result<Self> {
+        Self::new(
+            BufWriter::new(File::create(path)?),
+            variables,
+            session_info,
+            tick_rate,
+        )
+    }
+}
+
+impl<W: Write + Seek> DiskWriter<W> {
+    /// Starts the file with the variables, which are packed in order of their offsets.
+    pub fn new(
+        mut writer: W,
+        variables: &VarHeaders,
+        session_info: &Yaml,
+        tick_rate: i32,
+    ) -> Result<Self> {
+        let mut sorted = variables.values().collect::<Vec<_>>();
+        sorted.sort_by_key(|v| (v.offset, v.name.clone()));
+        let mut offset = 0;
+        let mut packed = VarHeaders::with_capacity(sorted.len());
+        let mut order = Vec::with_capacity(sorted.len());
+        for var in sorted {
+            packed.insert(
+                var.name.clone(),
+                VarHeader {
+                    offset,
+                    ..var.clone()
+                },
+            );
+            order.push(var.name.clone());
+            offset += var.byte_count();
+        }
+
+        let session_info = emit_session_info(session_info)?;
+        let var_header_offset = size_of::<Header>() + size_of::<DiskSubHeader>();
+        let session_info_offset = var_header_offset + order.len() * size_of::<VarHeaderRaw>();
+        let buf_offset = session_info_offset + session_info.len();
+        let mut header = Header {
+            ver: IRSDK_VER,
+            status: STATUS_CONNECTED,
+            tick_rate,
+            session_info_update: 0,
+            session_info_len: session_info.len() as i32,
+            session_info_offset: session_info_offset as i32,
+            num_vars: order.len() as i32,
+            var_header_offset: var_header_offset as i32,
+            num_buf: 1,
+            buf_len: offset as i32,
+            ..Default::default()
+        };
+        header.var_buf[0].buf_offset = buf_offset as i32;
+
+        let sub_header = DiskSubHeader::default();
+        write_struct(&mut writer, &header)?;
+        write_struct(&mut writer, &sub_header)?;
+        for name in &order {
+            write_struct(&mut writer, &packed[name].to_raw())?;
+        }
+        writer.write_all(&session_info)?;
+
+        Ok(Self {
+            writer,
+            header,
+            sub_header,
+            variables: packed,
+            order,
+            laps: HashSet::new(),
+        })
+    }
+
+    /// Variables as laid out in the written rows.
+    pub fn variables(&self) -> &VarHeaders {
+        &self.variables
+    }
+
+    pub fn header(&self) -> &Header {
+        &self.header
+    }
+
+    /// Sub-header which will be written by [`DiskWriter::finish`].
+    ///
+    /// Times and counts are updated for each row, while the start date is up to the caller.
+    pub fn sub_header_mut(&mut self) -> &mut DiskSubHeader {
+        &mut self.sub_header
+    }
+
+    /// Write row already laid out as [`DiskWriter::variables`].
+    pub fn write_raw(&mut self, row: &[u8]) -> Result<()> {
+        if row.len() != self.header.buf_len as usize {
+            bail!(
+                "Row has {} bytes, expected {}",
+                row.len(),
+                self.header.buf_len
+            );
+        }
+        self.writer.write_all(row)?;
+
+        let session_time = self
+            .variables
+            .get("SessionTime")
+            .and_then(|var| f64::parse_from_raw(0, var, row));
+        if let Some(session_time) = session_time {
+            if self.sub_header.session_record_count == 0 {
+                self.sub_header.session_start_time = session_time;
+            }
+            self.sub_header.session_end_time = session_time;
+        }
+        let lap = self
+            .variables
+            .get("Lap")
+            .and_then(|var| i32::parse_from_raw(0, var, row));
+        if let Some(lap) = lap {
+            self.laps.insert(lap);
+            self.sub_header.session_lap_count = self.laps.len() as i32;
+        }
+        self.sub_header.session_record_count += 1;
+        self.header.var_buf[0].tick_count = self.sub_header.session_record_count;
+        Ok(())
+    }
+
+    /// Write the variables of the sim state which are part of this file.
+    ///
+    /// Variables missing from the sim state are written as zero.
+    pub fn write_sim_state(&mut self, sim_state: &SimState) -> Result<()> {
+        let row = self.row_from_sim_state(sim_state)?;
+        self.write_raw(&row)
+    }
+
+    /// Lay out the variables of the sim state as a row of this file, for editing before
+    /// [`DiskWriter::write_raw`].
+    pub fn row_from_sim_state(&self, sim_state: &SimState) -> Result<Vec<u8>> {
+        let mut row = vec![0u8; self.header.buf_len as usize];
+        for name in &self.order {
+            let var = &self.variables[name];
+            let Some(source) = sim_state.variables().get(name) else {
+                continue;
+            };
+            if source.var_type != var.var_type {
+                bail!("Variable {name} changed type");
+            }
+            if source.count != var.count {
+                bail!(
+                    "Variable {name} changed count from {} to {}",
+                    var.count,
+                    source.count
+                );
+            }
+            let len = var.byte_count();
+            let Some(bytes) = sim_state.raw_data().get(source.offset..source.offset + len) else {
+                continue;
+            };
+            row[var.offset..var.offset + len].copy_from_slice(bytes);
+        }
+        Ok(row)
+    }
+
+    /// Complete the headers and flush, returning the underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        self.writer.seek(SeekFrom::Start(0))?;
+        write_struct(&mut self.writer, &self.header)?;
+        write_struct(&mut self.writer, &self.sub_header)?;
+        self.writer.seek(SeekFrom::End(0))?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+fn write_struct<T, W: Write>(mut write: W, s: &T) -> Result<()> {
+    let buffer = unsafe { std::slice::from_raw_parts(s as *const T as *const u8, size_of::<T>()) };
+    write.write_all(buffer)?;
+    Ok(())
+}
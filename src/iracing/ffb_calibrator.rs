@@ -0,0 +1,189 @@
+//! Recommendation of the force feedback max force from observed steering torque.
+
+use crate::iracing::commands::{self, force_feedback, CommandSink};
+use crate::iracing::{SimState, SimStateSource};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+use uom::si::f64::Torque;
+use uom::si::torque::newton_meter;
+
+/// Tuning of [`FfbCalibrator`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct FfbCalibrationConfig {
+    /// Session time covered by the observed samples.
+    pub window: Duration,
+    /// Fraction of the max force that the peak torque should reach.
+    pub target_peak: f64,
+    /// Percentile of absolute torque used as peak, so that single spikes such as kerb strikes
+    /// do not dominate.
+    pub peak_percentile: f64,
+    /// `SteeringWheelPctTorque` from which the output is considered clipping.
+    pub clipping_threshold: f64,
+    /// Fraction of clipping samples above which the peak may have been cut off by the output.
+    pub max_clipping: f64,
+    /// Relative increase of the max force when more than `max_clipping` of the samples clip.
+    pub clipping_headroom: f64,
+}
+
+impl Default for FfbCalibrationConfig {
+    fn default() -> Self {
+        Self {
+            window: Duration::from_secs(120),
+            target_peak: 0.9,
+            peak_percentile: 0.99,
+            clipping_threshold: 0.995,
+            max_clipping: 0.01,
+            clipping_headroom: 0.1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FfbRecommendation {
+    /// Value for [`force_feedback::set`], with headroom when too many samples clip.
+    pub max_force: Torque,
+    /// Observed peak torque, at the configured percentile.
+    pub peak_torque: Torque,
+    /// Fraction of samples where the output was clipping with the max force in use.
+    pub clipping: f64,
+    pub samples: usize,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Sample {
+    session_time: f64,
+    torque: f64,
+    clipping: bool,
+}
+
+/// Observes `SteeringWheelTorque` and `SteeringWheelPctTorque` while driving and recommends the
+/// max force that uses the full range of the wheel without clipping.
+///
+/// Works with live telemetry as well as `.ibt` files through
+/// [`DiskClient`](crate::iracing::DiskClient).
+#[derive(Clone, Debug)]
+pub struct FfbCalibrator {
+    config: FfbCalibrationConfig,
+    samples: VecDeque<Sample>,
+}
+
+impl FfbCalibrator {
+    pub fn new(config: FfbCalibrationConfig) -> Self {
+        Self {
+            config,
+            samples: VecDeque::new(),
+        }
+    }
+
+    pub fn config(&self) -> &FfbCalibrationConfig {
+        &self.config
+    }
+
+    /// Whether the samples cover the whole window.
+    pub fn is_window_full(&self) -> bool {
+        match (self.samples.front(), self.samples.back()) {
+            (Some(first), Some(last)) => {
+                last.session_time - first.session_time >= self.config.window.as_secs_f64()
+            }
+            _ => false,
+        }
+    }
+
+    /// Record the torque of the sim state, ignoring states where the car is not on track.
+    pub fn update(&mut self, state: &SimState) {
+        let Some(session_time) = state.read_name::<f64>("SessionTime") else {
+            return;
+        };
+        if state.read_name::<bool>("IsOnTrack") == Some(false) {
+            return;
+        }
+        let (Some(torque), Some(pct_torque)) = (
+            state.read_name::<f32>("SteeringWheelTorque"),
+            state.read_name::<f32>("SteeringWheelPctTorque"),
+        ) else {
+            return;
+        };
+        if self
+            .samples
+            .back()
+            .is_some_and(|last| last.session_time > session_time)
+        {
+            // Time went backwards, such as for a new session.
+            self.samples.clear();
+        }
+        self.samples.push_back(Sample {
+            session_time,
+            torque: (torque as f64).abs(),
+            clipping: (pct_torque as f64).abs() >= self.config.clipping_threshold,
+        });
+        let start = session_time - self.config.window.as_secs_f64();
+        while self.samples.front().is_some_and(|v| v.session_time < start) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Read sim states until the window is full, then return the recommendation.
+    ///
+    /// When the source ends before that, the recommendation covers what was observed.
+    pub async fn observe<S: SimStateSource>(
+        &mut self,
+        source: &mut S,
+    ) -> Option<FfbRecommendation> {
+        while !self.is_window_full() {
+            let Some(state) = source.next_sim_state().await else {
+                break;
+            };
+            self.update(&state);
+        }
+        self.recommendation()
+    }
+
+    /// Max force recommended from the samples in the window, if there are any.
+    pub fn recommendation(&self) -> Option<FfbRecommendation> {
+        let mut torques = self.samples.iter().map(|v| v.torque).collect::<Vec<_>>();
+        if torques.is_empty() {
+            return None;
+        }
+        torques.sort_by(f64::total_cmp);
+        let percentile = self.config.peak_percentile.clamp(0.0, 1.0);
+        let peak = torques[((torques.len() - 1) as f64 * percentile).round() as usize];
+        if peak <= 0.0 {
+            return None;
+        }
+        let clipping =
+            self.samples.iter().filter(|v| v.clipping).count() as f64 / torques.len() as f64;
+        let mut max_force = peak / self.config.target_peak;
+        if clipping > self.config.max_clipping {
+            // The peak is then only a lower bound, so leave room above it.
+            max_force *= 1.0 + self.config.clipping_headroom;
+        }
+        Some(FfbRecommendation {
+            max_force: Torque::new::<newton_meter>(max_force),
+            peak_torque: Torque::new::<newton_meter>(peak),
+            clipping,
+            samples: torques.len(),
+        })
+    }
+
+    /// Set the recommended max force through [`force_feedback::set`], sending the command to
+    /// the [`commands::default_sink`].
+    ///
+    /// Returns the recommendation that was applied.
+    pub fn apply(&self) -> Option<FfbRecommendation> {
+        self.apply_with_sink(commands::default_sink())
+    }
+
+    /// Like [`FfbCalibrator::apply`], sending the command to `sink`.
+    pub fn apply_with_sink(&self, sink: Arc<dyn CommandSink>) -> Option<FfbRecommendation> {
+        let recommendation = self.recommendation()?;
+        let max_force = recommendation.max_force.get::<newton_meter>() as f32;
+        commands::with_sink(sink, || force_feedback::set(max_force));
+        Some(recommendation)
+    }
+
+    /// Forget all samples, such as after changing the car.
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+}
@@ -57,3 +57,14 @@ pub mod start_flags {
     pub const SET: u32 = 0x4000_0000;
     pub const GO: u32 = 0x8000_0000;
 }
+
+/// Bits of `PitSvFlags`.
+pub mod pit_service_flags {
+    pub const LF_TIRE_CHANGE: u32 = 0x0001;
+    pub const RF_TIRE_CHANGE: u32 = 0x0002;
+    pub const LR_TIRE_CHANGE: u32 = 0x0004;
+    pub const RR_TIRE_CHANGE: u32 = 0x0008;
+    pub const FUEL_FILL: u32 = 0x0010;
+    pub const WINDSHIELD_TEAROFF: u32 = 0x0020;
+    pub const FAST_REPAIR: u32 = 0x0040;
+}
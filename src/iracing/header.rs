@@ -1,4 +1,4 @@
-use crate::windows_util::cp1252_to_string;
+use crate::cp1252::{cp1252_to_string, string_to_cp1252};
 use anyhow::{bail, Result};
 use std::collections::HashMap;
 
@@ -75,7 +75,7 @@ pub(crate) struct VarHeaderRaw {
     unit: [u8; MAX_STRING],
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct VarHeader {
     /// VarType
     pub var_type: VarType,
@@ -108,9 +108,36 @@ impl VarHeader {
             unit: cp1252_to_string(&raw.unit).unwrap_or_default(),
         })
     }
+
+    pub(crate) fn to_raw(&self) -> VarHeaderRaw {
+        VarHeaderRaw {
+            var_type: self.var_type as i32,
+            offset: self.offset as i32,
+            count: self.count as i32,
+            count_as_time: self.count_as_time as u8,
+            pad: [0; 3],
+            name: fixed_string(&self.name),
+            desc: fixed_string(&self.desc),
+            unit: fixed_string(&self.unit),
+        }
+    }
+
+    /// Length of the variable in bytes.
+    pub fn byte_count(&self) -> usize {
+        self.var_type.byte_count() * self.count
+    }
+}
+
+/// Null terminated string, truncated to fit.
+fn fixed_string<const N: usize>(string: &str) -> [u8; N] {
+    let mut fixed = [0u8; N];
+    let encoded = string_to_cp1252(string);
+    let len = encoded.len().min(N - 1);
+    fixed[..len].copy_from_slice(&encoded[..len]);
+    fixed
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum VarType {
     // 1 byte
     Char = 0,
@@ -3,23 +3,38 @@
 //! Use [`commands`] to send messages to iRacing.
 
 mod bit_field;
+mod camera_director;
 mod car_positions;
 mod client;
 pub mod commands;
 mod constants;
 mod disk_client;
+pub mod disk_tools;
+mod disk_writer;
+mod ffb_calibrator;
 pub mod flags;
 mod header;
+mod pit_service;
+mod replay_controller;
 mod session_info;
 mod sim_state;
+mod subscriptions;
 mod var_data;
 
 pub use bit_field::BitField;
+pub use camera_director::{CameraDirector, DirectorConfig, Shot, ShotReason};
 pub use car_positions::CarPositions;
 pub use client::Client;
 pub use constants::{UNLIMITED_LAPS, UNLIMITED_TIME};
 pub use disk_client::DiskClient;
+pub use disk_writer::DiskWriter;
+pub use ffb_calibrator::{FfbCalibrationConfig, FfbCalibrator, FfbRecommendation};
 pub use flags::{CameraFlag, CameraState};
 pub use header::{DiskSubHeader, Header, VarHeader, VarHeaders, VarType};
-pub use sim_state::SimState;
+pub use pit_service::{
+    PitServiceItem, PitServiceLimits, PitServiceRejected, PitServiceRequest, PitServiceState, Tire,
+};
+pub use replay_controller::{Highlight, Incident, ReplayController, ReplayTimeout};
+pub use sim_state::{SimState, SimStateSource};
+pub use subscriptions::{Subscriptions, VarChange};
 pub use var_data::{Value, VarData};
@@ -0,0 +1,410 @@
+//! Pit service requests which are validated and applied as a diff to what is already requested.
+
+use crate::iracing::commands::{self, pit, CommandSink};
+use crate::iracing::flags::pit_service_flags;
+use crate::iracing::{SimState, SimStateSource};
+use anyhow::{bail, Context, Result};
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use uom::si::f64::{Pressure, Volume};
+use uom::si::pressure::kilopascal;
+use uom::si::volume::liter;
+use yaml_rust::Yaml;
+
+/// Allowed difference between requested and reported fuel, since iRacing only takes whole liters.
+const FUEL_TOLERANCE_LITERS: f64 = 0.5;
+/// Allowed difference between requested and reported pressure, since iRacing only takes whole kPa.
+const PRESSURE_TOLERANCE_KPA: f64 = 0.5;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum Tire {
+    LeftFront,
+    RightFront,
+    LeftRear,
+    RightRear,
+}
+
+impl Tire {
+    pub const ALL: [Tire; 4] = [
+        Tire::LeftFront,
+        Tire::RightFront,
+        Tire::LeftRear,
+        Tire::RightRear,
+    ];
+
+    /// Name of the variable with the requested cold pressure of this tire.
+    pub fn pressure_var_name(self) -> &'static str {
+        match self {
+            Tire::LeftFront => "PitSvLFP",
+            Tire::RightFront => "PitSvRFP",
+            Tire::LeftRear => "PitSvLRP",
+            Tire::RightRear => "PitSvRRP",
+        }
+    }
+
+    fn flag(self) -> u32 {
+        match self {
+            Tire::LeftFront => pit_service_flags::LF_TIRE_CHANGE,
+            Tire::RightFront => pit_service_flags::RF_TIRE_CHANGE,
+            Tire::LeftRear => pit_service_flags::LR_TIRE_CHANGE,
+            Tire::RightRear => pit_service_flags::RR_TIRE_CHANGE,
+        }
+    }
+
+    fn send_change(self, pressure: i32) {
+        match self {
+            Tire::LeftFront => pit::lf(pressure),
+            Tire::RightFront => pit::rf(pressure),
+            Tire::LeftRear => pit::lr(pressure),
+            Tire::RightRear => pit::rr(pressure),
+        }
+    }
+}
+
+/// Single part of a pit service request.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum PitServiceItem {
+    Fuel,
+    Tire(Tire),
+    Windshield,
+    FastRepair,
+}
+
+/// Error returned when the sim did not reflect all parts of a request in time.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PitServiceRejected {
+    pub items: Vec<PitServiceItem>,
+}
+
+impl Display for PitServiceRejected {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Pit service request was not accepted: {:?}", self.items)
+    }
+}
+
+impl std::error::Error for PitServiceRejected {}
+
+/// Limits of the pit service of the current car.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PitServiceLimits {
+    /// Most fuel that the car may carry, after any fuel restriction.
+    pub fuel_capacity: Option<Volume>,
+    /// Smallest and largest cold tire pressure that can be requested.
+    pub tire_pressure: Option<(Pressure, Pressure)>,
+}
+
+impl PitServiceLimits {
+    /// Reads the fuel capacity from `DriverCarFuelMaxLtr` and `DriverCarMaxFuelPct`.
+    ///
+    /// Session info does not list tire pressure limits, so those are only checked once set with
+    /// [`PitServiceLimits::with_tire_pressure`]. The sim itself clamps pressures to its limits,
+    /// which shows up as a difference when confirming the request.
+    pub fn from_session_info(session_info: &Yaml) -> Self {
+        let driver_info = &session_info["DriverInfo"];
+        let max_pct = as_number(&driver_info["DriverCarMaxFuelPct"]).unwrap_or(1.0);
+        Self {
+            fuel_capacity: as_number(&driver_info["DriverCarFuelMaxLtr"])
+                .map(|max_liters| Volume::new::<liter>(max_liters * max_pct)),
+            tire_pressure: None,
+        }
+    }
+
+    /// Check requested tire pressures against the range.
+    pub fn with_tire_pressure(mut self, min: Pressure, max: Pressure) -> Self {
+        self.tire_pressure = Some((min, max));
+        self
+    }
+}
+
+/// Pit service as currently requested in the sim, from the `PitSv*` variables.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PitServiceState {
+    /// Bits of [`pit_service_flags`].
+    pub flags: u32,
+    pub fuel: Volume,
+    /// Cold pressures in the order of [`Tire::ALL`].
+    pub tire_pressures: [Pressure; 4],
+}
+
+impl PitServiceState {
+    pub fn read(state: &SimState) -> Option<Self> {
+        let flags = state.read_name::<u32>("PitSvFlags")?;
+        let fuel = Volume::new::<liter>(state.read_name::<f32>("PitSvFuel")? as f64);
+        let mut tire_pressures = [Pressure::default(); 4];
+        for (pressure, tire) in tire_pressures.iter_mut().zip(Tire::ALL) {
+            let kpa = state.read_name::<f32>(tire.pressure_var_name())?;
+            *pressure = Pressure::new::<kilopascal>(kpa as f64);
+        }
+        Some(Self {
+            flags,
+            fuel,
+            tire_pressures,
+        })
+    }
+
+    fn has(&self, flag: u32) -> bool {
+        self.flags & flag != 0
+    }
+
+    fn tire_pressure(&self, tire: Tire) -> Pressure {
+        self.tire_pressures[tire as usize]
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TireRequest {
+    Keep,
+    Change(Pressure),
+}
+
+/// Desired pit service, where everything not mentioned is left as it is.
+///
+/// Unlike [`commands::pit`](crate::iracing::commands::pit), amounts are validated against
+/// [`PitServiceLimits`] and only commands for parts that differ from the current
+/// [`PitServiceState`] are sent.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PitServiceRequest {
+    fuel: Option<Volume>,
+    tires: [Option<TireRequest>; 4],
+    windshield: Option<bool>,
+    fast_repair: Option<bool>,
+}
+
+impl PitServiceRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the amount of fuel, or no fuel at all if it is zero.
+    pub fn fuel(mut self, amount: Volume) -> Self {
+        self.fuel = Some(amount);
+        self
+    }
+
+    /// Change the tire, setting its cold pressure.
+    pub fn change_tire(mut self, tire: Tire, pressure: Pressure) -> Self {
+        self.tires[tire as usize] = Some(TireRequest::Change(pressure));
+        self
+    }
+
+    pub fn change_all_tires(self, pressure: Pressure) -> Self {
+        Tire::ALL
+            .into_iter()
+            .fold(self, |request, tire| request.change_tire(tire, pressure))
+    }
+
+    /// Do not change the tire.
+    pub fn keep_tire(mut self, tire: Tire) -> Self {
+        self.tires[tire as usize] = Some(TireRequest::Keep);
+        self
+    }
+
+    pub fn keep_all_tires(self) -> Self {
+        Tire::ALL
+            .into_iter()
+            .fold(self, |request, tire| request.keep_tire(tire))
+    }
+
+    /// Use a windshield tear-off, or not.
+    pub fn windshield(mut self, enabled: bool) -> Self {
+        self.windshield = Some(enabled);
+        self
+    }
+
+    pub fn fast_repair(mut self, enabled: bool) -> Self {
+        self.fast_repair = Some(enabled);
+        self
+    }
+
+    /// Check the request against the limits of the car.
+    pub fn validate(&self, limits: &PitServiceLimits) -> Result<()> {
+        if let Some(fuel) = self.fuel {
+            let liters = fuel.get::<liter>();
+            let capacity = limits
+                .fuel_capacity
+                .context("Fuel capacity of the car is unknown")?
+                .get::<liter>();
+            if !(0.0..=capacity).contains(&liters) {
+                bail!("Fuel of {liters:.1} l is outside of 0 to {capacity:.1} l");
+            }
+        }
+        for (tire, request) in Tire::ALL.into_iter().zip(self.tires) {
+            let (Some(TireRequest::Change(pressure)), Some((min, max))) =
+                (request, limits.tire_pressure)
+            else {
+                continue;
+            };
+            if pressure < min || pressure > max {
+                bail!(
+                    "Pressure of {:.1} kPa for {tire:?} is outside of {:.1} to {:.1} kPa",
+                    pressure.get::<kilopascal>(),
+                    min.get::<kilopascal>(),
+                    max.get::<kilopascal>(),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Parts of the request which the state does not reflect.
+    pub fn differences(&self, current: &PitServiceState) -> Vec<PitServiceItem> {
+        let mut items = Vec::new();
+        if let Some(fuel) = self.fuel {
+            let matches = match fuel.get::<liter>().round() {
+                liters if liters <= 0.0 => !current.has(pit_service_flags::FUEL_FILL),
+                liters => {
+                    current.has(pit_service_flags::FUEL_FILL)
+                        && (current.fuel.get::<liter>() - liters).abs() <= FUEL_TOLERANCE_LITERS
+                }
+            };
+            if !matches {
+                items.push(PitServiceItem::Fuel);
+            }
+        }
+        for (tire, request) in Tire::ALL.into_iter().zip(self.tires) {
+            let matches = match request {
+                None => true,
+                Some(TireRequest::Keep) => !current.has(tire.flag()),
+                Some(TireRequest::Change(pressure)) => {
+                    let kpa = pressure.get::<kilopascal>().round();
+                    current.has(tire.flag())
+                        && (current.tire_pressure(tire).get::<kilopascal>() - kpa).abs()
+                            <= PRESSURE_TOLERANCE_KPA
+                }
+            };
+            if !matches {
+                items.push(PitServiceItem::Tire(tire));
+            }
+        }
+        let toggles = [
+            (
+                self.windshield,
+                pit_service_flags::WINDSHIELD_TEAROFF,
+                PitServiceItem::Windshield,
+            ),
+            (
+                self.fast_repair,
+                pit_service_flags::FAST_REPAIR,
+                PitServiceItem::FastRepair,
+            ),
+        ];
+        for (request, flag, item) in toggles {
+            if request.is_some_and(|enabled| enabled != current.has(flag)) {
+                items.push(item);
+            }
+        }
+        items
+    }
+
+    /// Validate the request and send commands for the parts that differ from the sim state.
+    ///
+    /// Commands go to the [`commands::default_sink`]. Returns the parts that were sent.
+    pub fn apply(&self, state: &SimState) -> Result<Vec<PitServiceItem>> {
+        self.apply_with_sink(state, commands::default_sink())
+    }
+
+    /// Like [`PitServiceRequest::apply`], sending the commands to `sink`.
+    pub fn apply_with_sink(
+        &self,
+        state: &SimState,
+        sink: Arc<dyn CommandSink>,
+    ) -> Result<Vec<PitServiceItem>> {
+        self.validate(&PitServiceLimits::from_session_info(state.session_info()))?;
+        let current = PitServiceState::read(state).context("Missing pit service variables")?;
+        let items = self.differences(&current);
+        commands::with_sink(sink, || self.send(&items, &current));
+        Ok(items)
+    }
+
+    fn send(&self, items: &[PitServiceItem], current: &PitServiceState) {
+        for item in items {
+            match item {
+                PitServiceItem::Fuel => match self.fuel.unwrap().get::<liter>().round() as i32 {
+                    liters if liters <= 0 => pit::clear_fuel(),
+                    liters => pit::fuel(liters),
+                },
+                PitServiceItem::Tire(_) => {}
+                PitServiceItem::Windshield if self.windshield == Some(true) => pit::ws(),
+                PitServiceItem::Windshield => pit::clear_ws(),
+                PitServiceItem::FastRepair if self.fast_repair == Some(true) => pit::fr(),
+                PitServiceItem::FastRepair => pit::clear_fr(),
+            }
+        }
+        if items.iter().any(|v| matches!(v, PitServiceItem::Tire(_))) {
+            self.apply_tires(current);
+        }
+    }
+
+    /// Tires can only be unchecked all at once, so any tire to keep clears all and re-checks the rest.
+    fn apply_tires(&self, current: &PitServiceState) {
+        let unchecks = Tire::ALL
+            .into_iter()
+            .zip(self.tires)
+            .any(|(tire, request)| request == Some(TireRequest::Keep) && current.has(tire.flag()));
+        if unchecks {
+            pit::clear_tires();
+        }
+        for (tire, request) in Tire::ALL.into_iter().zip(self.tires) {
+            let pressure = match request {
+                Some(TireRequest::Change(pressure)) => pressure.get::<kilopascal>().round() as i32,
+                Some(TireRequest::Keep) => continue,
+                // Restore tires that were not part of the request.
+                None if unchecks && current.has(tire.flag()) => {
+                    current.tire_pressure(tire).get::<kilopascal>().round() as i32
+                }
+                None => continue,
+            };
+            tire.send_change(pressure);
+        }
+    }
+
+    /// Apply the request and wait until the sim reflects all of it.
+    ///
+    /// Fails with [`PitServiceRejected`] if some parts are still different after `timeout`.
+    /// Commands go to the [`commands::default_sink`].
+    pub async fn apply_and_confirm<S: SimStateSource>(
+        &self,
+        source: &mut S,
+        timeout: Duration,
+    ) -> Result<()> {
+        self.apply_and_confirm_with_sink(source, commands::default_sink(), timeout)
+            .await
+    }
+
+    /// Like [`PitServiceRequest::apply_and_confirm`], sending the commands to `sink`.
+    pub async fn apply_and_confirm_with_sink<S: SimStateSource>(
+        &self,
+        source: &mut S,
+        sink: Arc<dyn CommandSink>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let state = source
+            .next_sim_state()
+            .await
+            .context("Sim state source is done")?;
+        if self.apply_with_sink(&state, sink)?.is_empty() {
+            return Ok(());
+        }
+        let deadline = Instant::now() + timeout;
+        let mut items = Vec::new();
+        while Instant::now() < deadline {
+            let Ok(state) = tokio::time::timeout_at(deadline, source.next_sim_state()).await else {
+                break;
+            };
+            let state = state.context("Sim state source is done")?;
+            let current = PitServiceState::read(&state).context("Missing pit service variables")?;
+            items = self.differences(&current);
+            if items.is_empty() {
+                return Ok(());
+            }
+        }
+        Err(PitServiceRejected { items }.into())
+    }
+}
+
+/// Session info numbers are written without a fraction when it is zero.
+fn as_number(yaml: &Yaml) -> Option<f64> {
+    yaml.as_f64().or_else(|| yaml.as_i64().map(|v| v as f64))
+}
@@ -0,0 +1,344 @@
+//! Replay control which confirms every step by watching the replay telemetry.
+
+use crate::iracing::commands::{self, camera, replay, CommandSink};
+use crate::iracing::session_info::car_number;
+use crate::iracing::{SimState, SimStateSource};
+use anyhow::{bail, Context, Result};
+use std::fmt::{Display, Formatter};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Replay frames per second of session time.
+const REPLAY_FRAME_RATE: f64 = 60.0;
+/// Allowed difference in seconds between requested and reached replay session time.
+const SEEK_TOLERANCE: f64 = 1.0;
+
+/// Error returned when the sim did not reach the expected state in time.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ReplayTimeout {
+    /// Step that was not confirmed.
+    pub step: &'static str,
+}
+
+impl Display for ReplayTimeout {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Replay did not confirm step in time: {}", self.step)
+    }
+}
+
+impl std::error::Error for ReplayTimeout {}
+
+/// Incident found while searching the replay.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Incident {
+    /// Car the camera switched to for the incident.
+    pub car_idx: i32,
+    pub session_num: i32,
+    /// Session time of the incident itself, before applying any pre-roll.
+    pub session_time: f64,
+    pub frame: i32,
+}
+
+/// Part of the replay to be played by [`ReplayController::play_highlights`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Highlight {
+    pub session_num: i32,
+    /// Session time in seconds where the highlight starts.
+    pub start: f64,
+    pub duration: Duration,
+    /// Car to focus the camera on, or keep the current one.
+    pub car_idx: Option<i32>,
+    /// Camera group to use, or keep the current one.
+    pub camera_group: Option<u16>,
+}
+
+/// Stateful replay controller, built on top of [`commands::replay`] and [`commands::camera`].
+///
+/// Each operation sends commands and then waits for the replay telemetry
+/// (`ReplayFrameNum`, `ReplaySessionTime`, `ReplayPlaySpeed`, `CamCarIdx`, `CamGroupNumber`)
+/// to reflect them, failing with [`ReplayTimeout`] if that does not happen in time.
+pub struct ReplayController<S> {
+    source: S,
+    sink: Arc<dyn CommandSink>,
+    step_timeout: Duration,
+    last_state: Option<SimState>,
+    last_incident: Option<Incident>,
+}
+
+impl<S: SimStateSource> ReplayController<S> {
+    pub const DEFAULT_STEP_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Creates controller sending commands to the [`commands::default_sink`].
+    pub fn new(source: S) -> Self {
+        Self::with_sink(source, commands::default_sink())
+    }
+
+    pub fn with_sink(source: S, sink: Arc<dyn CommandSink>) -> Self {
+        Self {
+            source,
+            sink,
+            step_timeout: Self::DEFAULT_STEP_TIMEOUT,
+            last_state: None,
+            last_incident: None,
+        }
+    }
+
+    /// Time to wait for each step to be reflected in telemetry.
+    pub fn step_timeout(mut self, step_timeout: Duration) -> Self {
+        self.step_timeout = step_timeout;
+        self
+    }
+
+    /// Latest sim state received while controlling the replay.
+    pub fn last_state(&self) -> Option<&SimState> {
+        self.last_state.as_ref()
+    }
+
+    pub fn into_source(self) -> S {
+        self.source
+    }
+
+    /// Play replay at normal speed.
+    pub async fn play(&mut self) -> Result<()> {
+        self.set_speed(1, false).await
+    }
+
+    pub async fn pause(&mut self) -> Result<()> {
+        self.set_speed(0, false).await
+    }
+
+    /// Set play speed, see [`replay::play_with_speed`].
+    pub async fn set_speed(&mut self, speed: i8, slow_motion: bool) -> Result<()> {
+        self.run(|| replay::play_with_speed(speed, slow_motion));
+        self.wait_for("set play speed", |state| {
+            state.read_name::<i32>("ReplayPlaySpeed") == Some(speed as i32)
+                && (speed == 0 || state.read_name("ReplayPlaySlowMotion") == Some(slow_motion))
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Point the camera at the car, optionally also switching the camera group.
+    pub async fn focus_car(&mut self, car_idx: i32, camera_group: Option<u16>) -> Result<()> {
+        let state = self.current_state().await?;
+        let car_number = car_number(state.session_info(), car_idx)?;
+        let group = match camera_group {
+            Some(group) => group,
+            None => state
+                .read_name::<i32>("CamGroupNumber")
+                .context("Missing CamGroupNumber")? as u16,
+        };
+        self.run(|| camera::switch_to_car_number(car_number, group, 0));
+        self.wait_for("focus car", |state| {
+            state.read_name::<i32>("CamCarIdx") == Some(car_idx)
+                && state.read_name::<i32>("CamGroupNumber") == Some(group as i32)
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Move replay to the given session time in seconds.
+    pub async fn seek_session_time(&mut self, session_num: i32, session_time: f64) -> Result<()> {
+        let millis = (session_time * 1000.0).round() as i32;
+        self.run(|| replay::search_session_time(session_num as u16, millis));
+        self.wait_for("seek session time", |state| {
+            state.read_name::<i32>("ReplaySessionNum") == Some(session_num)
+                && state
+                    .read_name::<f64>("ReplaySessionTime")
+                    .is_some_and(|v| (v - session_time).abs() <= SEEK_TOLERANCE)
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Focus the car and move replay to the start of the given lap of that car.
+    ///
+    /// Replay is left paused at the start of the lap.
+    pub async fn jump_to_lap(&mut self, car_idx: i32, lap: i32) -> Result<()> {
+        self.focus_car(car_idx, None).await?;
+        self.pause().await?;
+        let mut max_steps = None;
+        loop {
+            let state = self.current_state().await?;
+            let current_lap = state
+                .read_name_at::<i32>("CarIdxLap", car_idx as usize)
+                .context("Missing CarIdxLap")?;
+            if current_lap == lap {
+                return Ok(());
+            }
+            // Allow for a few searches that only reach the start of the current lap.
+            let max_steps = max_steps.get_or_insert((current_lap - lap).abs() * 2 + 2);
+            if *max_steps == 0 {
+                bail!("Could not reach lap {lap} of car {car_idx}, stuck at lap {current_lap}");
+            }
+            *max_steps -= 1;
+            if current_lap < lap {
+                self.search_frame_change("search next lap", replay::search_next_lap)
+                    .await?;
+            } else {
+                self.search_frame_change("search previous lap", replay::search_prev_lap)
+                    .await?;
+            }
+        }
+    }
+
+    /// Move to the next incident and start playing `pre_roll` before it.
+    ///
+    /// Fails with [`ReplayTimeout`] when there are no more incidents.
+    pub async fn next_incident(&mut self, pre_roll: Duration) -> Result<Incident> {
+        self.pause().await?;
+        if let Some(last_frame) = self.last_incident.as_ref().map(|v| v.frame) {
+            // Searching from within the pre-roll would find the same incident again.
+            if self.current_frame().await? < last_frame {
+                self.seek_frame(last_frame).await?;
+            }
+        }
+        self.incident(replay::search_next_incident, pre_roll).await
+    }
+
+    /// Move to the previous incident and start playing `pre_roll` before it.
+    ///
+    /// Fails with [`ReplayTimeout`] when there are no more incidents.
+    pub async fn prev_incident(&mut self, pre_roll: Duration) -> Result<Incident> {
+        self.pause().await?;
+        self.incident(replay::search_prev_incident, pre_roll).await
+    }
+
+    /// Play all highlights in order, returning once the last one has finished.
+    pub async fn play_highlights(&mut self, highlights: &[Highlight]) -> Result<()> {
+        for highlight in highlights {
+            self.seek_session_time(highlight.session_num, highlight.start)
+                .await?;
+            match (highlight.car_idx, highlight.camera_group) {
+                (Some(car_idx), group) => self.focus_car(car_idx, group).await?,
+                (None, Some(group)) => {
+                    let car_idx = self
+                        .current_state()
+                        .await?
+                        .read_name::<i32>("CamCarIdx")
+                        .context("Missing CamCarIdx")?;
+                    self.focus_car(car_idx, Some(group)).await?;
+                }
+                (None, None) => {}
+            }
+            self.play().await?;
+            let end = highlight.start + highlight.duration.as_secs_f64();
+            let timeout = highlight.duration + self.step_timeout;
+            self.wait_for_within("play highlight", timeout, |state| {
+                state
+                    .read_name::<f64>("ReplaySessionTime")
+                    .is_some_and(|v| v >= end)
+            })
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Move replay to the given frame.
+    pub async fn seek_frame(&mut self, frame: i32) -> Result<()> {
+        self.run(|| replay::set_play_position(replay::PlayPosition::Begin, frame));
+        self.wait_for("seek frame", |state| {
+            state.read_name::<i32>("ReplayFrameNum") == Some(frame)
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// Search for an incident while paused, so the frame only changes because of the search.
+    async fn incident(&mut self, search: fn(), pre_roll: Duration) -> Result<Incident> {
+        let state = self.search_frame_change("search incident", search).await?;
+        let incident = Incident {
+            car_idx: state.read_name("CamCarIdx").context("Missing CamCarIdx")?,
+            session_num: state
+                .read_name("ReplaySessionNum")
+                .context("Missing ReplaySessionNum")?,
+            session_time: state
+                .read_name("ReplaySessionTime")
+                .context("Missing ReplaySessionTime")?,
+            frame: state
+                .read_name("ReplayFrameNum")
+                .context("Missing ReplayFrameNum")?,
+        };
+        let frames = (pre_roll.as_secs_f64() * REPLAY_FRAME_RATE).round() as i32;
+        if frames > 0 {
+            self.run(|| replay::set_play_position(replay::PlayPosition::Current, -frames));
+            self.wait_for("rewind pre-roll", |state| {
+                state
+                    .read_name::<i32>("ReplayFrameNum")
+                    .is_some_and(|v| v < incident.frame)
+            })
+            .await?;
+        }
+        self.play().await?;
+        self.last_incident = Some(incident.clone());
+        Ok(incident)
+    }
+
+    /// Run a search command and wait for the replay frame to change.
+    async fn search_frame_change(&mut self, step: &'static str, search: fn()) -> Result<SimState> {
+        let frame = self.current_frame().await?;
+        self.run(search);
+        self.wait_for(step, |state| {
+            state
+                .read_name::<i32>("ReplayFrameNum")
+                .is_some_and(|v| v != frame)
+        })
+        .await
+    }
+
+    async fn current_frame(&mut self) -> Result<i32> {
+        self.current_state()
+            .await?
+            .read_name("ReplayFrameNum")
+            .context("Missing ReplayFrameNum")
+    }
+
+    fn run(&self, command: impl FnOnce()) {
+        commands::with_sink(Arc::clone(&self.sink), command)
+    }
+
+    async fn current_state(&mut self) -> Result<&SimState> {
+        if self.last_state.is_none() {
+            let state = self
+                .source
+                .next_sim_state()
+                .await
+                .context("Sim state source is done")?;
+            self.last_state = Some(state);
+        }
+        Ok(self.last_state.as_ref().unwrap())
+    }
+
+    async fn wait_for<F: FnMut(&SimState) -> bool>(
+        &mut self,
+        step: &'static str,
+        condition: F,
+    ) -> Result<SimState> {
+        self.wait_for_within(step, self.step_timeout, condition)
+            .await
+    }
+
+    async fn wait_for_within<F: FnMut(&SimState) -> bool>(
+        &mut self,
+        step: &'static str,
+        timeout: Duration,
+        mut condition: F,
+    ) -> Result<SimState> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let state = tokio::time::timeout_at(deadline, self.source.next_sim_state())
+                .await
+                .map_err(|_| ReplayTimeout { step })?
+                .context("Sim state source is done")?;
+            self.last_state = Some(state.clone());
+            if condition(&state) {
+                return Ok(state);
+            }
+            if Instant::now() >= deadline {
+                // Sources that are always ready, such as recordings, never hit the timeout above.
+                return Err(ReplayTimeout { step }.into());
+            }
+        }
+    }
+}
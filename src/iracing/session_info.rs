@@ -1,6 +1,6 @@
-use crate::windows_util::cp1252_to_string;
+use crate::cp1252::{cp1252_to_string, string_to_cp1252};
 use anyhow::{bail, Context, Result};
-use yaml_rust::{Yaml, YamlLoader};
+use yaml_rust::{Yaml, YamlEmitter, YamlLoader};
 
 pub fn parse_session_info(raw: &[u8]) -> Result<Yaml> {
     let data_string = cp1252_to_string(raw).context("CP1252 decode of session info failed")?;
@@ -10,3 +10,24 @@ pub fn parse_session_info(raw: &[u8]) -> Result<Yaml> {
     }
     Ok(items.swap_remove(0))
 }
+
+pub fn emit_session_info(session_info: &Yaml) -> Result<Vec<u8>> {
+    let mut data_string = String::new();
+    YamlEmitter::new(&mut data_string).dump(session_info)?;
+    data_string.push_str("\n...\n");
+    Ok(string_to_cp1252(&data_string))
+}
+
+/// Car number used by camera commands for the car with the given index.
+pub fn car_number(session_info: &Yaml, car_idx: i32) -> Result<u16> {
+    let driver = session_info["DriverInfo"]["Drivers"]
+        .as_vec()
+        .context("Missing drivers in session info")?
+        .iter()
+        .find(|driver| driver["CarIdx"].as_i64() == Some(car_idx as i64))
+        .with_context(|| format!("Car {car_idx} not found in session info"))?;
+    let car_number = driver["CarNumberRaw"]
+        .as_i64()
+        .context("Missing CarNumberRaw")?;
+    Ok(car_number as u16)
+}
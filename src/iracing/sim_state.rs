@@ -139,8 +139,16 @@ impl Debug for SimState {
     }
 }
 
+/// Anything producing consecutive sim states, live or recorded.
+#[async_trait::async_trait]
+pub trait SimStateSource: Send {
+    /// Waits for the next sim state, `None` means that the source is done.
+    async fn next_sim_state(&mut self) -> Option<SimState>;
+}
+
 impl SimState {
-    pub(super) fn new(
+    /// Creates sim state from its raw parts, such as for synthetic telemetry.
+    pub fn new(
         header: Arc<Header>,
         variables: Arc<VarHeaders>,
         raw_data: Vec<u8>,
@@ -174,6 +182,11 @@ impl SimState {
         &self.header
     }
 
+    /// Row of all variables, laid out as described by [`SimState::variables`].
+    pub fn raw_data(&self) -> &[u8] {
+        &self.raw_data
+    }
+
     pub fn variables(&self) -> &VarHeaders {
         &self.variables
     }
@@ -0,0 +1,116 @@
+//! Notifications when values of variables change.
+
+use crate::iracing::{SimState, Value};
+use std::collections::VecDeque;
+
+/// Change of a single entry of a subscribed variable.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VarChange {
+    /// Name of the variable.
+    pub name: String,
+    /// Index of the entry that changed, always 0 for variables that are not arrays.
+    pub entry: usize,
+    /// Value at the time of the previous notification, or first observation.
+    pub old: Value,
+    pub new: Value,
+    /// `SessionTime` of the sim state where the change was observed.
+    pub session_time: f64,
+}
+
+/// Set of variables to watch for changes.
+///
+/// Feed it sim states with [`Subscriptions::update`] and receive changes with
+/// [`Subscriptions::next_change`]. The first observation of a variable only records its value.
+#[derive(Clone, Debug, Default)]
+pub struct Subscriptions {
+    subscriptions: Vec<Subscription>,
+    pending: VecDeque<VarChange>,
+}
+
+#[derive(Clone, Debug)]
+struct Subscription {
+    name: String,
+    dead_band: f64,
+    last: Option<Vec<Value>>,
+}
+
+impl Subscriptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Watch variable for any change.
+    pub fn subscribe<S: Into<String>>(&mut self, name: S) {
+        self.subscribe_with_dead_band(name, 0.0)
+    }
+
+    /// Watch variable for changes, ignoring changes of float values up to `dead_band`.
+    ///
+    /// The dead-band is measured from the last reported value, so slow drifts are still reported
+    /// once they accumulate. It has no effect on variables which are not floats.
+    pub fn subscribe_with_dead_band<S: Into<String>>(&mut self, name: S, dead_band: f64) {
+        let name = name.into();
+        let dead_band = dead_band.abs();
+        match self.subscriptions.iter_mut().find(|v| v.name == name) {
+            Some(subscription) => subscription.dead_band = dead_band,
+            None => self.subscriptions.push(Subscription {
+                name,
+                dead_band,
+                last: None,
+            }),
+        }
+    }
+
+    /// Stop watching variable and drop its pending changes.
+    pub fn unsubscribe(&mut self, name: &str) {
+        self.subscriptions.retain(|v| v.name != name);
+        self.pending.retain(|v| v.name != name);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.subscriptions.is_empty()
+    }
+
+    /// Compare subscribed variables against the previous state and queue up changes.
+    pub fn update(&mut self, sim_state: &SimState) {
+        let session_time = sim_state.read_name("SessionTime").unwrap_or_default();
+        for subscription in &mut self.subscriptions {
+            let Some(values) = sim_state.read_name::<Vec<Value>>(&subscription.name) else {
+                continue;
+            };
+            let last = match &mut subscription.last {
+                Some(last) if last.len() == values.len() => last,
+                last => {
+                    // First observation, or the variable layout changed.
+                    *last = Some(values);
+                    continue;
+                }
+            };
+            for (entry, (old, new)) in last.iter_mut().zip(values).enumerate() {
+                if !is_change(old, &new, subscription.dead_band) {
+                    continue;
+                }
+                self.pending.push_back(VarChange {
+                    name: subscription.name.clone(),
+                    entry,
+                    old: std::mem::replace(old, new.clone()),
+                    new,
+                    session_time,
+                });
+            }
+        }
+    }
+
+    /// Take the oldest change that was not yet received.
+    pub fn next_change(&mut self) -> Option<VarChange> {
+        self.pending.pop_front()
+    }
+}
+
+fn is_change(old: &Value, new: &Value, dead_band: f64) -> bool {
+    match (old, new) {
+        (Value::Float(old), Value::Float(new)) => (new - old).abs() as f64 > dead_band,
+        (Value::Double(old), Value::Double(new)) => (new - old).abs() > dead_band,
+        _ => old != new,
+    }
+}
@@ -1,16 +1,21 @@
+use crate::cp1252::cp1252_to_string;
 use crate::iracing::header::{VarHeader, VarType};
 use byteorder::{LittleEndian, ReadBytesExt};
 
 pub trait VarData: Sized {
+    /// Read value starting at array index `entry` of the variable described by `header`.
+    ///
+    /// Returns `None` if the entry is out of range for the variable, or if `data` is too short
+    /// to contain the variable, such as for a truncated row in an `.ibt` file.
     fn parse_from_raw(entry: usize, header: &VarHeader, data: &[u8]) -> Option<Self> {
         if entry >= header.count {
             return None;
         }
         let leftover = header.count - entry;
         let entry_size = header.var_type.byte_count();
-        let start = header.offset + entry * entry_size;
-        let end = start + leftover * entry_size;
-        Self::parse(header.var_type, &data[start..end])
+        let start = header.offset.checked_add(entry.checked_mul(entry_size)?)?;
+        let end = start.checked_add(leftover.checked_mul(entry_size)?)?;
+        Self::parse(header.var_type, data.get(start..end)?)
     }
 
     /// Read value from location in data.
@@ -65,6 +70,32 @@ impl<T: VarData> VarData for Vec<T> {
     }
 }
 
+/// Reads exactly `N` consecutive entries, failing if fewer are available.
+impl<T: VarData, const N: usize> VarData for [T; N] {
+    fn parse(var_type: VarType, data: &[u8]) -> Option<Self> {
+        let entry_size = var_type.byte_count();
+        if data.len() < N * entry_size {
+            return None;
+        }
+        data.chunks_exact(entry_size)
+            .take(N)
+            .map(|chunk| T::parse(var_type, chunk))
+            .collect::<Option<Vec<T>>>()?
+            .try_into()
+            .ok()
+    }
+}
+
+/// Reads a NUL terminated CP1252 string from a `Char` array.
+impl VarData for String {
+    fn parse(var_type: VarType, data: &[u8]) -> Option<Self> {
+        match var_type {
+            VarType::Char => cp1252_to_string(data).ok(),
+            _ => None,
+        }
+    }
+}
+
 impl VarData for u8 {
     fn parse(var_type: VarType, mut data: &[u8]) -> Option<Self> {
         match var_type {
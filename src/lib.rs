@@ -2,23 +2,30 @@ pub use racing_flags::RacingFlags;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::time::Duration;
+#[cfg(target_family = "unix")]
+pub use unix_util::SharedMemoryPath;
 use uom::si::f64::{AngularVelocity, Velocity};
 
 pub mod assetto_corsa;
 pub mod assetto_corsa_competizione;
-#[cfg(target_family = "windows")]
+mod byte_reader;
+mod cp1252;
 pub mod dirt_rally_2;
+pub mod ea_sports_wrc;
+pub mod f1;
+pub mod forza;
 #[cfg(feature = "unstable_generic_http_client")]
 pub mod generic_http;
-#[cfg(target_family = "windows")]
+pub mod gran_turismo_7;
 pub mod iracing;
-#[cfg(target_family = "windows")]
+pub mod outgauge;
+pub mod project_cars_2;
 pub mod raceroom_racing_experience;
 mod racing_flags;
-#[cfg(target_family = "windows")]
 pub mod rfactor_2;
-#[cfg(target_family = "windows")]
 pub mod truck_simulator;
+#[cfg(target_family = "unix")]
+mod unix_util;
 #[cfg(target_family = "windows")]
 mod windows_util;
 
@@ -38,8 +45,20 @@ pub trait Simetry {
 pub struct SimetryConnectionBuilder {
     #[cfg(feature = "unstable_generic_http_client")]
     generic_http_uri: String,
-    #[cfg(target_family = "windows")]
+    #[cfg(target_family = "unix")]
+    assetto_corsa_pages: assetto_corsa::SharedMemoryPages,
+    #[cfg(target_family = "unix")]
+    assetto_corsa_competizione_pages: assetto_corsa_competizione::SharedMemoryPages,
+    #[cfg(target_family = "unix")]
+    iracing_path: Option<SharedMemoryPath>,
+    #[cfg(target_family = "unix")]
+    raceroom_racing_experience_path: Option<SharedMemoryPath>,
+    rfactor_2_config: rfactor_2::Config,
     dirt_rally_2_uri: String,
+    forza_uri: String,
+    outgauge_uri: String,
+    project_cars_2_uri: String,
+    truck_simulator_json_uri: String,
     retry_delay: Duration,
 }
 
@@ -48,8 +67,21 @@ impl Default for SimetryConnectionBuilder {
         Self {
             #[cfg(feature = "unstable_generic_http_client")]
             generic_http_uri: generic_http::DEFAULT_URI.to_string(),
-            #[cfg(target_family = "windows")]
+            #[cfg(target_family = "unix")]
+            assetto_corsa_pages: assetto_corsa::SharedMemoryPages::default(),
+            #[cfg(target_family = "unix")]
+            assetto_corsa_competizione_pages:
+                assetto_corsa_competizione::SharedMemoryPages::default(),
+            #[cfg(target_family = "unix")]
+            iracing_path: None,
+            #[cfg(target_family = "unix")]
+            raceroom_racing_experience_path: None,
+            rfactor_2_config: rfactor_2::Config::default(),
             dirt_rally_2_uri: dirt_rally_2::Client::DEFAULT_URI.to_string(),
+            forza_uri: forza::Client::DEFAULT_URI.to_string(),
+            outgauge_uri: outgauge::Client::DEFAULT_URI.to_string(),
+            project_cars_2_uri: project_cars_2::Client::DEFAULT_URI.to_string(),
+            truck_simulator_json_uri: truck_simulator::json_client::DEFAULT_URI.to_string(),
             retry_delay: Duration::from_secs(5),
         }
     }
@@ -62,12 +94,68 @@ impl SimetryConnectionBuilder {
         self
     }
 
-    #[cfg(target_family = "windows")]
+    /// Shared memory pages of Assetto Corsa, such as files exposed by a bridge from Proton.
+    #[cfg(target_family = "unix")]
+    pub fn assetto_corsa_pages(mut self, pages: assetto_corsa::SharedMemoryPages) -> Self {
+        self.assetto_corsa_pages = pages;
+        self
+    }
+
+    /// Shared memory pages of Assetto Corsa Competizione.
+    #[cfg(target_family = "unix")]
+    pub fn assetto_corsa_competizione_pages(
+        mut self,
+        pages: assetto_corsa_competizione::SharedMemoryPages,
+    ) -> Self {
+        self.assetto_corsa_competizione_pages = pages;
+        self
+    }
+
+    /// Memory mapped file of iRacing, `/IRSDKMemMapFileName` by default.
+    #[cfg(target_family = "unix")]
+    pub fn iracing_path(mut self, path: SharedMemoryPath) -> Self {
+        self.iracing_path = Some(path);
+        self
+    }
+
+    /// Shared memory of RaceRoom, `/$R3E` by default.
+    #[cfg(target_family = "unix")]
+    pub fn raceroom_racing_experience_path(mut self, path: SharedMemoryPath) -> Self {
+        self.raceroom_racing_experience_path = Some(path);
+        self
+    }
+
+    pub fn rfactor_2_config(mut self, config: rfactor_2::Config) -> Self {
+        self.rfactor_2_config = config;
+        self
+    }
+
     pub fn dirt_rally_2_uri(mut self, uri: String) -> Self {
         self.dirt_rally_2_uri = uri;
         self
     }
 
+    pub fn forza_uri(mut self, uri: String) -> Self {
+        self.forza_uri = uri;
+        self
+    }
+
+    pub fn outgauge_uri(mut self, uri: String) -> Self {
+        self.outgauge_uri = uri;
+        self
+    }
+
+    pub fn project_cars_2_uri(mut self, uri: String) -> Self {
+        self.project_cars_2_uri = uri;
+        self
+    }
+
+    /// URI of the ETS2 telemetry server queried by [`truck_simulator::json_client`].
+    pub fn truck_simulator_json_uri(mut self, uri: String) -> Self {
+        self.truck_simulator_json_uri = uri;
+        self
+    }
+
     pub fn retry_delay(mut self, delay: Duration) -> Self {
         self.retry_delay = delay;
         self
@@ -78,13 +166,58 @@ impl SimetryConnectionBuilder {
         use tokio::select;
 
         let retry_delay = self.retry_delay;
-        let assetto_corsa_future = assetto_corsa::Client::connect(retry_delay);
+        let iracing_future = async {
+            match &self.iracing_path {
+                Some(path) => iracing::Client::connect_with_path(path, retry_delay).await,
+                None => iracing::Client::connect(retry_delay).await,
+            }
+        };
+        let assetto_corsa_future =
+            assetto_corsa::Client::connect_with_pages(&self.assetto_corsa_pages, retry_delay);
         let assetto_corsa_competizione_future =
-            assetto_corsa_competizione::Client::connect(retry_delay);
+            assetto_corsa_competizione::Client::connect_with_pages(
+                &self.assetto_corsa_competizione_pages,
+                retry_delay,
+            );
+        let raceroom_racing_experience_future = async {
+            match &self.raceroom_racing_experience_path {
+                Some(path) => {
+                    raceroom_racing_experience::Client::connect_with_path(path, retry_delay).await
+                }
+                None => raceroom_racing_experience::Client::connect(retry_delay).await,
+            }
+        };
+        let rfactor_2_future = rfactor_2::Client::connect_with_config(&self.rfactor_2_config);
+        let dirt_rally_2_future =
+            dirt_rally_2::Client::connect(&self.dirt_rally_2_uri, retry_delay);
+        #[cfg(feature = "unstable_generic_http_client")]
+        let generic_http_future =
+            generic_http::GenericHttpClient::connect(&self.generic_http_uri, retry_delay);
+        #[cfg(not(feature = "unstable_generic_http_client"))]
+        let generic_http_future = never_resolved();
+        let truck_simulator_future = truck_simulator::Client::connect(retry_delay);
+        let truck_simulator_json_future = truck_simulator::json_client::Client::connect(
+            &self.truck_simulator_json_uri,
+            retry_delay,
+        );
+        let forza_future = forza::Client::connect(&self.forza_uri, retry_delay);
+        let outgauge_future = outgauge::Client::connect(&self.outgauge_uri, retry_delay);
+        let project_cars_2_future =
+            project_cars_2::Client::connect(&self.project_cars_2_uri, retry_delay);
 
         select! {
+            x = iracing_future => Box::new(x),
             x = assetto_corsa_future => Box::new(x),
             x = assetto_corsa_competizione_future => Box::new(x),
+            x = raceroom_racing_experience_future => Box::new(x),
+            x = rfactor_2_future => Box::new(x),
+            x = dirt_rally_2_future => Box::new(x),
+            x = generic_http_future => Box::new(x),
+            x = truck_simulator_future => Box::new(x),
+            x = truck_simulator_json_future => Box::new(x),
+            x = forza_future => Box::new(x),
+            x = outgauge_future => Box::new(x),
+            x = project_cars_2_future => Box::new(x),
         }
     }
 
@@ -99,7 +232,7 @@ impl SimetryConnectionBuilder {
             assetto_corsa_competizione::Client::connect(retry_delay);
         let raceroom_racing_experience_future =
             raceroom_racing_experience::Client::connect(retry_delay);
-        let rfactor_2_future = rfactor_2::Client::connect();
+        let rfactor_2_future = rfactor_2::Client::connect_with_config(&self.rfactor_2_config);
         let dirt_rally_2_future =
             dirt_rally_2::Client::connect(&self.dirt_rally_2_uri, retry_delay);
         #[cfg(feature = "unstable_generic_http_client")]
@@ -108,6 +241,16 @@ impl SimetryConnectionBuilder {
         #[cfg(not(feature = "unstable_generic_http_client"))]
         let generic_http_future = never_resolved();
         let truck_simulator_future = truck_simulator::Client::connect(retry_delay);
+        let truck_simulator_json_future = truck_simulator::json_client::Client::connect(
+            &self.truck_simulator_json_uri,
+            retry_delay,
+        );
+        let forza_future = forza::Client::connect(&self.forza_uri, retry_delay);
+        let outgauge_future = outgauge::Client::connect(&self.outgauge_uri, retry_delay);
+        let project_cars_2_future =
+            project_cars_2::Client::connect(&self.project_cars_2_uri, retry_delay);
+        let project_cars_2_shared_memory_future =
+            project_cars_2::shared_memory::Client::connect(retry_delay);
 
         select! {
             x = iracing_future => Box::new(x),
@@ -118,15 +261,17 @@ impl SimetryConnectionBuilder {
             x = dirt_rally_2_future => Box::new(x),
             x = generic_http_future => Box::new(x),
             x = truck_simulator_future => Box::new(x),
+            x = truck_simulator_json_future => Box::new(x),
+            x = forza_future => Box::new(x),
+            x = outgauge_future => Box::new(x),
+            x = project_cars_2_future => Box::new(x),
+            x = project_cars_2_shared_memory_future => Box::new(x),
         }
     }
 }
 
-#[cfg(all(
-    target_family = "windows",
-    not(feature = "unstable_generic_http_client")
-))]
-async fn never_resolved() -> iracing::Client {
+#[cfg(not(feature = "unstable_generic_http_client"))]
+async fn never_resolved() -> dirt_rally_2::Client {
     loop {
         tokio::time::sleep(Duration::from_secs(1_000_000_000)).await;
     }
@@ -178,6 +323,16 @@ pub trait Moment {
         None
     }
 
+    /// Fraction of the shift lights that are lit, from 0 to 1.
+    fn rev_lights(&self) -> Option<f64> {
+        None
+    }
+
+    /// Check if the drag reduction system is open.
+    fn is_drs_engaged(&self) -> Option<bool> {
+        None
+    }
+
     fn flags(&self) -> Option<RacingFlags> {
         None
     }
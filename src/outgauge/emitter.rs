@@ -0,0 +1,152 @@
+use super::{dash_lights, outgauge_flags, OutGauge};
+use crate::{Moment, Simetry};
+use anyhow::Result;
+use std::time::{Duration, Instant};
+use tokio::net::UdpSocket;
+use tokio::time::MissedTickBehavior;
+use uom::si::angular_velocity::revolution_per_minute;
+use uom::si::velocity::meter_per_second;
+
+/// Sends OutGauge packets built from the moments of any sim, for dashboards that only
+/// understand OutGauge.
+#[derive(Debug)]
+pub struct Emitter {
+    socket: UdpSocket,
+    interval: Duration,
+    id: Option<i32>,
+    start: Instant,
+}
+
+impl Emitter {
+    /// Send packets to `target`, such as `192.168.1.30:4444`, 60 times per second.
+    pub async fn connect(target: &str) -> Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0").await?;
+        socket.connect(target).await?;
+        Ok(Self {
+            socket,
+            interval: Duration::from_secs(1) / 60,
+            id: None,
+            start: Instant::now(),
+        })
+    }
+
+    /// Time between packets.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// ID appended to every packet, like the "OutGauge ID" setting.
+    pub fn id(mut self, id: i32) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    /// Sends the latest moment of `simetry` at the configured rate, until the sim is done.
+    pub async fn run<S: Simetry + Send + ?Sized>(&self, simetry: &mut S) -> Result<()> {
+        let mut interval = tokio::time::interval(self.interval);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut latest = None;
+        // Kept across ticks, since clients may take longer than a tick and are not cancel safe.
+        let mut next_moment = simetry.next_moment();
+        loop {
+            tokio::select! {
+                moment = &mut next_moment => {
+                    let Some(moment) = moment else {
+                        return Ok(());
+                    };
+                    latest = Some(moment);
+                    drop(next_moment);
+                    next_moment = simetry.next_moment();
+                }
+                _ = interval.tick() => {
+                    if let Some(moment) = &latest {
+                        self.send_moment(moment.as_ref()).await?;
+                    }
+                }
+            }
+        }
+    }
+
+    pub async fn send_moment(&self, moment: &(dyn Moment + Sync)) -> Result<()> {
+        self.socket.send(&self.packet(moment).encode()).await?;
+        Ok(())
+    }
+
+    /// Packet for `moment`, with the dash lights which the moment knows about.
+    pub fn packet(&self, moment: &dyn Moment) -> OutGauge {
+        let rpm = moment
+            .vehicle_engine_rotation_speed()
+            .map_or(0.0, |v| v.get::<revolution_per_minute>());
+        let shift_light = match (moment.shift_point(), moment.rev_lights()) {
+            (_, Some(rev_lights)) => Some(rev_lights >= 1.0),
+            (Some(shift_point), None) => Some(rpm >= shift_point.get::<revolution_per_minute>()),
+            (None, None) => None,
+        };
+        let left = moment.is_left_turn_indicator_on();
+        let right = moment.is_right_turn_indicator_on();
+        let any_signal = match (left, right) {
+            (None, None) => None,
+            (left, right) => Some(left.unwrap_or_default() || right.unwrap_or_default()),
+        };
+        // The battery light is lit with the ignition on and the engine stopped.
+        let battery = moment
+            .is_ignition_on()
+            .map(|ignition| ignition && rpm <= 0.0);
+        let mut lights = Lights::default();
+        lights.set(dash_lights::SHIFT, shift_light);
+        lights.set(dash_lights::PIT_SPEED, moment.is_pit_limiter_engaged());
+        lights.set(dash_lights::SIGNAL_LEFT, left);
+        lights.set(dash_lights::SIGNAL_RIGHT, right);
+        lights.set(dash_lights::SIGNAL_ANY, any_signal);
+        lights.set(dash_lights::BATTERY, battery);
+        let pedals = moment.pedals().unwrap_or_default();
+        OutGauge {
+            time: self.start.elapsed().as_millis() as u32,
+            car: moment
+                .vehicle_model_id()
+                .map(|id| id.into_owned())
+                .unwrap_or_default(),
+            flags: outgauge_flags::KM | outgauge_flags::BAR,
+            gear: moment
+                .vehicle_gear()
+                .map_or(1, |gear| (gear + 1).max(0) as u8),
+            player_id: 0,
+            speed: moment
+                .vehicle_velocity()
+                .map_or(0.0, |v| v.get::<meter_per_second>()) as f32,
+            rpm: rpm as f32,
+            turbo: 0.0,
+            engine_temperature: 0.0,
+            fuel: 0.0,
+            oil_pressure: 0.0,
+            oil_temperature: 0.0,
+            dash_lights: lights.available,
+            show_lights: lights.shown,
+            throttle: pedals.throttle as f32,
+            brake: pedals.brake as f32,
+            clutch: pedals.clutch as f32,
+            display_1: String::new(),
+            display_2: String::new(),
+            id: self.id,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Lights {
+    available: u32,
+    shown: u32,
+}
+
+impl Lights {
+    fn set(&mut self, light: u32, value: Option<bool>) {
+        let Some(value) = value else {
+            return;
+        };
+        self.available |= light;
+        if value {
+            self.shown |= light;
+        }
+    }
+}
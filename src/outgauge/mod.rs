@@ -0,0 +1,205 @@
+//! Client for the OutGauge and OutSim UDP protocols of Live for Speed, also sent by BeamNG.drive.
+//!
+//! OutGauge carries the dashboard and OutSim the motion of the car. Both have to be enabled in
+//! the settings of the game, with the address and port the client listens on.
+//!
+//! [`Emitter`] sends OutGauge in the other direction, from any supported sim.
+
+use crate::{Moment, Pedals, Simetry};
+use anyhow::Result;
+use std::borrow::Cow;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use uom::si::angular_velocity::revolution_per_minute;
+use uom::si::f64::{AngularVelocity, Pressure, Velocity};
+use uom::si::pressure::bar;
+use uom::si::velocity::meter_per_second;
+
+pub use emitter::Emitter;
+pub use packets::{
+    dash_lights, outgauge_flags, outsim_options, OutGauge, OutSim, OutSimDistance, OutSimDrive,
+    OutSimExtra1, OutSimInputs, OutSimMain, OutSimWheel,
+};
+
+mod emitter;
+mod packets;
+
+/// Latest OutGauge packet, with the latest OutSim packet if OutSim is received.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimState {
+    pub outgauge: OutGauge,
+    pub outsim: Option<OutSim>,
+}
+
+impl SimState {
+    pub fn turbo_pressure(&self) -> Pressure {
+        Pressure::new::<bar>(self.outgauge.turbo as f64)
+    }
+
+    /// Fuel left, from 0 to 1.
+    pub fn fuel(&self) -> f64 {
+        self.outgauge.fuel as f64
+    }
+}
+
+#[derive(Debug)]
+struct OutSimListener {
+    socket: UdpSocket,
+    options: u32,
+}
+
+#[derive(Debug)]
+pub struct Client {
+    socket: UdpSocket,
+    outsim: Option<OutSimListener>,
+    latest_outsim: Option<OutSim>,
+}
+
+impl Client {
+    /// Port used by BeamNG.drive, Live for Speed has no default.
+    pub const DEFAULT_URI: &'static str = "0.0.0.0:4444";
+    pub const DEFAULT_OUTSIM_URI: &'static str = "0.0.0.0:4123";
+
+    pub async fn connect(uri: &str, retry_delay: Duration) -> Self {
+        loop {
+            if let Ok(client) = Self::try_connect(uri).await {
+                return client;
+            }
+            tokio::time::sleep(retry_delay).await;
+        }
+    }
+
+    /// Listen for OutGauge on `uri` until a packet is received.
+    pub async fn try_connect(uri: &str) -> Result<Self> {
+        Self::try_connect_with_socket(UdpSocket::bind(uri).await?).await
+    }
+
+    /// Listen for OutGauge on an already bound `socket` until a packet is received.
+    pub async fn try_connect_with_socket(socket: UdpSocket) -> Result<Self> {
+        let mut client = Self {
+            socket,
+            outsim: None,
+            latest_outsim: None,
+        };
+        client.next_sim_state().await?;
+        Ok(client)
+    }
+
+    /// Also listen for OutSim on `uri`, sent with the given [`outsim_options`].
+    pub async fn listen_outsim(mut self, uri: &str, options: u32) -> Result<Self> {
+        self.outsim = Some(OutSimListener {
+            socket: UdpSocket::bind(uri).await?,
+            options,
+        });
+        Ok(self)
+    }
+
+    /// Waits for the next OutGauge packet, keeping the latest OutSim packet received meanwhile.
+    pub async fn next_sim_state(&mut self) -> Result<SimState> {
+        let mut buffer = [0u8; 512];
+        let mut outsim_buffer = [0u8; 512];
+        loop {
+            let outsim_recv = async {
+                match &self.outsim {
+                    Some(outsim) => outsim.socket.recv(&mut outsim_buffer).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                len = self.socket.recv(&mut buffer) => {
+                    match OutGauge::decode(&buffer[..len?]) {
+                        Ok(outgauge) => {
+                            return Ok(SimState {
+                                outgauge,
+                                outsim: self.latest_outsim.clone(),
+                            })
+                        }
+                        Err(err) => log::debug!("Ignoring OutGauge packet: {err}"),
+                    }
+                }
+                len = outsim_recv => {
+                    let len = len?;
+                    let options = self.outsim.as_ref().map_or(0, |outsim| outsim.options);
+                    match OutSim::decode(&outsim_buffer[..len], options) {
+                        Ok(outsim) => self.latest_outsim = Some(outsim),
+                        Err(err) => log::debug!("Ignoring OutSim packet: {err}"),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Simetry for Client {
+    fn name(&self) -> &str {
+        "OutGauge"
+    }
+
+    async fn next_moment(&mut self) -> Option<Box<dyn Moment + Send + Sync + 'static>> {
+        Some(Box::new(self.next_sim_state().await.ok()?))
+    }
+}
+
+impl Moment for SimState {
+    fn vehicle_gear(&self) -> Option<i8> {
+        Some(self.outgauge.gear as i8 - 1)
+    }
+
+    fn vehicle_velocity(&self) -> Option<Velocity> {
+        Some(Velocity::new::<meter_per_second>(
+            self.outgauge.speed as f64,
+        ))
+    }
+
+    fn vehicle_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(
+            self.outgauge.rpm as f64,
+        ))
+    }
+
+    fn is_pit_limiter_engaged(&self) -> Option<bool> {
+        self.outgauge.light(dash_lights::PIT_SPEED)
+    }
+
+    fn vehicle_model_id(&self) -> Option<Cow<str>> {
+        Some(self.outgauge.car.as_str().into())
+    }
+
+    fn is_left_turn_indicator_on(&self) -> Option<bool> {
+        self.outgauge.light(dash_lights::SIGNAL_LEFT)
+    }
+
+    fn is_right_turn_indicator_on(&self) -> Option<bool> {
+        self.outgauge.light(dash_lights::SIGNAL_RIGHT)
+    }
+
+    /// The battery light is only lit with the ignition on and the engine stopped.
+    fn is_ignition_on(&self) -> Option<bool> {
+        let battery = self.outgauge.light(dash_lights::BATTERY)?;
+        Some(battery || self.outgauge.rpm > 0.0)
+    }
+
+    fn pedals(&self) -> Option<Pedals> {
+        Some(Pedals {
+            throttle: self.outgauge.throttle as f64,
+            brake: self.outgauge.brake as f64,
+            clutch: self.outgauge.clutch as f64,
+        })
+    }
+
+    fn pedals_raw(&self) -> Option<Pedals> {
+        let Some(inputs) = self
+            .outsim
+            .as_ref()
+            .and_then(|outsim| outsim.inputs.as_ref())
+        else {
+            return self.pedals();
+        };
+        Some(Pedals {
+            throttle: inputs.throttle as f64,
+            brake: inputs.brake as f64,
+            clutch: inputs.clutch as f64,
+        })
+    }
+}
@@ -0,0 +1,426 @@
+use crate::byte_reader::ByteReader;
+use crate::cp1252::{cp1252_to_string, string_to_cp1252};
+use anyhow::{bail, Result};
+
+const OUTGAUGE_SIZE: usize = 92;
+const OUTSIM_SIZE: usize = 64;
+const OUTSIM_HEADER: &[u8; 4] = b"LFST";
+
+/// Bits of [`OutGauge::flags`].
+pub mod outgauge_flags {
+    pub const SHIFT: u16 = 1 << 0;
+    pub const CTRL: u16 = 1 << 1;
+    pub const TURBO: u16 = 1 << 13;
+    /// The user prefers kilometers over miles.
+    pub const KM: u16 = 1 << 14;
+    /// The user prefers bar over psi.
+    pub const BAR: u16 = 1 << 15;
+}
+
+/// Bits of [`OutGauge::dash_lights`] and [`OutGauge::show_lights`].
+pub mod dash_lights {
+    pub const SHIFT: u32 = 1 << 0;
+    pub const FULL_BEAM: u32 = 1 << 1;
+    pub const HANDBRAKE: u32 = 1 << 2;
+    pub const PIT_SPEED: u32 = 1 << 3;
+    pub const TC: u32 = 1 << 4;
+    pub const SIGNAL_LEFT: u32 = 1 << 5;
+    pub const SIGNAL_RIGHT: u32 = 1 << 6;
+    pub const SIGNAL_ANY: u32 = 1 << 7;
+    pub const OIL_WARNING: u32 = 1 << 8;
+    pub const BATTERY: u32 = 1 << 9;
+    pub const ABS: u32 = 1 << 10;
+}
+
+/// Bits of the "OutSim Opts" setting, which selects the blocks of [`OutSim`].
+///
+/// Without any option, the original packet with the main block and time is sent.
+pub mod outsim_options {
+    pub const HEADER: u32 = 1 << 0;
+    pub const ID: u32 = 1 << 1;
+    pub const TIME: u32 = 1 << 2;
+    pub const MAIN: u32 = 1 << 3;
+    pub const INPUTS: u32 = 1 << 4;
+    pub const DRIVE: u32 = 1 << 5;
+    pub const DISTANCE: u32 = 1 << 6;
+    pub const WHEELS: u32 = 1 << 7;
+    pub const EXTRA_1: u32 = 1 << 8;
+}
+
+/// Dashboard packet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutGauge {
+    /// Milliseconds.
+    pub time: u32,
+    pub car: String,
+    /// See [`outgauge_flags`].
+    pub flags: u16,
+    /// 0 is reverse, 1 is neutral.
+    pub gear: u8,
+    /// Viewed player, 0 if none.
+    pub player_id: u8,
+    /// Meters per second.
+    pub speed: f32,
+    pub rpm: f32,
+    /// Bar.
+    pub turbo: f32,
+    /// Celsius.
+    pub engine_temperature: f32,
+    /// From 0 to 1.
+    pub fuel: f32,
+    /// Bar.
+    pub oil_pressure: f32,
+    /// Celsius.
+    pub oil_temperature: f32,
+    /// Lights which the car has, see [`dash_lights`].
+    pub dash_lights: u32,
+    /// Lights which are switched on.
+    pub show_lights: u32,
+    /// From 0 to 1.
+    pub throttle: f32,
+    pub brake: f32,
+    pub clutch: f32,
+    pub display_1: String,
+    pub display_2: String,
+    /// Only sent when an OutGauge ID is configured.
+    pub id: Option<i32>,
+}
+
+impl OutGauge {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        let has_id = match data.len() {
+            OUTGAUGE_SIZE => false,
+            len if len == OUTGAUGE_SIZE + 4 => true,
+            len => bail!("OutGauge packet has {len} bytes"),
+        };
+        let mut reader = ByteReader::new(data, "OutGauge packet");
+        Ok(Self {
+            time: reader.u32()?,
+            car: read_string(&mut reader, 4)?,
+            flags: reader.u16()?,
+            gear: reader.u8()?,
+            player_id: reader.u8()?,
+            speed: reader.f32()?,
+            rpm: reader.f32()?,
+            turbo: reader.f32()?,
+            engine_temperature: reader.f32()?,
+            fuel: reader.f32()?,
+            oil_pressure: reader.f32()?,
+            oil_temperature: reader.f32()?,
+            dash_lights: reader.u32()?,
+            show_lights: reader.u32()?,
+            throttle: reader.f32()?,
+            brake: reader.f32()?,
+            clutch: reader.f32()?,
+            display_1: read_string(&mut reader, 16)?,
+            display_2: read_string(&mut reader, 16)?,
+            id: has_id.then(|| reader.i32()).transpose()?,
+        })
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut data = Vec::with_capacity(OUTGAUGE_SIZE + 4);
+        data.extend(self.time.to_le_bytes());
+        put_string(&mut data, &self.car, 4);
+        data.extend(self.flags.to_le_bytes());
+        data.extend([self.gear, self.player_id]);
+        for value in [
+            self.speed,
+            self.rpm,
+            self.turbo,
+            self.engine_temperature,
+            self.fuel,
+            self.oil_pressure,
+            self.oil_temperature,
+        ] {
+            data.extend(value.to_le_bytes());
+        }
+        data.extend(self.dash_lights.to_le_bytes());
+        data.extend(self.show_lights.to_le_bytes());
+        for value in [self.throttle, self.brake, self.clutch] {
+            data.extend(value.to_le_bytes());
+        }
+        put_terminated_string(&mut data, &self.display_1, 16);
+        put_terminated_string(&mut data, &self.display_2, 16);
+        if let Some(id) = self.id {
+            data.extend(id.to_le_bytes());
+        }
+        data
+    }
+
+    /// Light is available in the car and switched on, `None` if the car does not have it.
+    pub fn light(&self, light: u32) -> Option<bool> {
+        if self.dash_lights & light == 0 {
+            return None;
+        }
+        Some(self.show_lights & light != 0)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutSimMain {
+    /// Radians per second.
+    pub angular_velocity: [f32; 3],
+    /// Radians, 0 is world Y direction.
+    pub heading: f32,
+    pub pitch: f32,
+    pub roll: f32,
+    /// Meters per second squared.
+    pub acceleration: [f32; 3],
+    /// Meters per second.
+    pub velocity: [f32; 3],
+    /// 1 meter is 65536.
+    pub position: [i32; 3],
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutSimInputs {
+    /// From 0 to 1.
+    pub throttle: f32,
+    pub brake: f32,
+    /// Radians.
+    pub input_steer: f32,
+    pub clutch: f32,
+    pub handbrake: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutSimDrive {
+    /// 0 is reverse, 1 is neutral.
+    pub gear: u8,
+    /// Radians per second.
+    pub engine_angular_velocity: f32,
+    pub max_torque_at_velocity: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutSimDistance {
+    /// Meters.
+    pub current_lap_distance: f32,
+    pub indexed_distance: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutSimWheel {
+    /// Meters, compression from the unloaded position.
+    pub suspension_deflection: f32,
+    /// Radians, including Ackermann and toe.
+    pub steer: f32,
+    /// Newtons.
+    pub x_force: f32,
+    pub y_force: f32,
+    pub vertical_load: f32,
+    /// Radians per second.
+    pub angular_velocity: f32,
+    /// Radians.
+    pub lean_relative_to_road: f32,
+    /// Celsius.
+    pub air_temperature: u8,
+    pub slip_fraction: u8,
+    pub touching: bool,
+    pub slip_ratio: f32,
+    pub tan_slip_angle: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutSimExtra1 {
+    /// Newton meters.
+    pub steer_torque: f32,
+}
+
+/// Motion packet, with the blocks selected by [`outsim_options`].
+///
+/// Wheel arrays are ordered rear left, rear right, front left, front right.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutSim {
+    pub id: Option<i32>,
+    /// Milliseconds.
+    pub time: Option<u32>,
+    pub main: Option<OutSimMain>,
+    pub inputs: Option<OutSimInputs>,
+    pub drive: Option<OutSimDrive>,
+    pub distance: Option<OutSimDistance>,
+    pub wheels: Option<[OutSimWheel; 4]>,
+    pub extra_1: Option<OutSimExtra1>,
+}
+
+impl OutSim {
+    /// Decode a packet sent with the given [`outsim_options`].
+    pub fn decode(data: &[u8], options: u32) -> Result<Self> {
+        if options == 0 {
+            return Self::decode_original(data);
+        }
+        let has = |option: u32| options & option != 0;
+        let mut reader = ByteReader::new(data, "OutSim packet");
+        if has(outsim_options::HEADER) && reader.take(4)? != OUTSIM_HEADER {
+            bail!("OutSim packet has no LFST header");
+        }
+        let packet = Self {
+            id: has(outsim_options::ID).then(|| reader.i32()).transpose()?,
+            time: has(outsim_options::TIME)
+                .then(|| reader.u32())
+                .transpose()?,
+            main: has(outsim_options::MAIN)
+                .then(|| OutSimMain::read(&mut reader))
+                .transpose()?,
+            inputs: has(outsim_options::INPUTS)
+                .then(|| OutSimInputs::read(&mut reader))
+                .transpose()?,
+            drive: has(outsim_options::DRIVE)
+                .then(|| OutSimDrive::read(&mut reader))
+                .transpose()?,
+            distance: has(outsim_options::DISTANCE)
+                .then(|| OutSimDistance::read(&mut reader))
+                .transpose()?,
+            wheels: has(outsim_options::WHEELS)
+                .then(|| read_wheels(&mut reader))
+                .transpose()?,
+            extra_1: has(outsim_options::EXTRA_1)
+                .then(|| OutSimExtra1::read(&mut reader))
+                .transpose()?,
+        };
+        if reader.remaining() > 0 {
+            bail!(
+                "OutSim packet has {} bytes more than options {options:#x} describe",
+                reader.remaining()
+            );
+        }
+        Ok(packet)
+    }
+
+    /// Packet without options, with the ID detected by length.
+    fn decode_original(data: &[u8]) -> Result<Self> {
+        let has_id = match data.len() {
+            OUTSIM_SIZE => false,
+            len if len == OUTSIM_SIZE + 4 => true,
+            len => bail!("OutSim packet has {len} bytes"),
+        };
+        let mut reader = ByteReader::new(data, "OutSim packet");
+        let time = reader.u32()?;
+        let main = OutSimMain::read(&mut reader)?;
+        Ok(Self {
+            id: has_id.then(|| reader.i32()).transpose()?,
+            time: Some(time),
+            main: Some(main),
+            inputs: None,
+            drive: None,
+            distance: None,
+            wheels: None,
+            extra_1: None,
+        })
+    }
+}
+
+/// Writes the string into a buffer of `len` bytes, truncating it if needed.
+fn put_string(data: &mut Vec<u8>, string: &str, len: usize) {
+    let mut bytes = string_to_cp1252(string);
+    bytes.resize(len, 0);
+    data.extend(bytes);
+}
+
+/// Writes the string into a buffer of `len` bytes, truncating it to keep a null terminator.
+fn put_terminated_string(data: &mut Vec<u8>, string: &str, len: usize) {
+    let mut bytes = string_to_cp1252(string);
+    bytes.truncate(len - 1);
+    bytes.resize(len, 0);
+    data.extend(bytes);
+}
+
+/// Null terminated string in a buffer of `len` bytes.
+fn read_string(r: &mut ByteReader, len: usize) -> Result<String> {
+    Ok(cp1252_to_string(r.take(len)?).unwrap_or_default())
+}
+
+fn read_wheels(r: &mut ByteReader) -> Result<[OutSimWheel; 4]> {
+    Ok([
+        OutSimWheel::read(r)?,
+        OutSimWheel::read(r)?,
+        OutSimWheel::read(r)?,
+        OutSimWheel::read(r)?,
+    ])
+}
+
+impl OutSimMain {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        Ok(Self {
+            angular_velocity: r.f32s()?,
+            heading: r.f32()?,
+            pitch: r.f32()?,
+            roll: r.f32()?,
+            acceleration: r.f32s()?,
+            velocity: r.f32s()?,
+            position: [r.i32()?, r.i32()?, r.i32()?],
+        })
+    }
+}
+
+impl OutSimInputs {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        Ok(Self {
+            throttle: r.f32()?,
+            brake: r.f32()?,
+            input_steer: r.f32()?,
+            clutch: r.f32()?,
+            handbrake: r.f32()?,
+        })
+    }
+}
+
+impl OutSimDrive {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        let gear = r.u8()?;
+        r.take(3)?;
+        Ok(Self {
+            gear,
+            engine_angular_velocity: r.f32()?,
+            max_torque_at_velocity: r.f32()?,
+        })
+    }
+}
+
+impl OutSimDistance {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        Ok(Self {
+            current_lap_distance: r.f32()?,
+            indexed_distance: r.f32()?,
+        })
+    }
+}
+
+impl OutSimWheel {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        let suspension_deflection = r.f32()?;
+        let steer = r.f32()?;
+        let x_force = r.f32()?;
+        let y_force = r.f32()?;
+        let vertical_load = r.f32()?;
+        let angular_velocity = r.f32()?;
+        let lean_relative_to_road = r.f32()?;
+        let air_temperature = r.u8()?;
+        let slip_fraction = r.u8()?;
+        let touching = r.u8()? != 0;
+        r.take(1)?;
+        Ok(Self {
+            suspension_deflection,
+            steer,
+            x_force,
+            y_force,
+            vertical_load,
+            angular_velocity,
+            lean_relative_to_road,
+            air_temperature,
+            slip_fraction,
+            touching,
+            slip_ratio: r.f32()?,
+            tan_slip_angle: r.f32()?,
+        })
+    }
+}
+
+impl OutSimExtra1 {
+    fn read(r: &mut ByteReader) -> Result<Self> {
+        let steer_torque = r.f32()?;
+        r.take(4)?;
+        Ok(Self { steer_torque })
+    }
+}
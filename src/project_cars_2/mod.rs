@@ -0,0 +1,306 @@
+//! Client for the "Project CARS 2" UDP protocol, sent by Project CARS 2 and Automobilista 2.
+//!
+//! The UDP output has to be enabled in the game settings with the protocol version set to
+//! "Project CARS 2". The game broadcasts the packets on the local network.
+
+use crate::{Moment, Pedals, RacingFlags, Simetry};
+use anyhow::Result;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use uom::si::angular_velocity::revolution_per_minute;
+use uom::si::f64::{AngularVelocity, Velocity};
+use uom::si::velocity::meter_per_second;
+
+pub use packets::{
+    car_flags, ClassInfo, GameState, Packet, PacketData, PacketHeader, ParticipantName,
+    ParticipantStats, ParticipantTiming, Participants, RaceDefinition, Telemetry, TimeStats,
+    Timings, VehicleInfo, MAX_PARTICIPANTS,
+};
+
+mod packets;
+pub mod shared_memory;
+
+/// Value of [`Telemetry::gear`] for reverse.
+const REVERSE_GEAR: u8 = 15;
+
+/// Participant combined from the participants and vehicle names packets.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Participant {
+    pub name: String,
+    pub nationality: u32,
+    pub vehicle_name: Option<String>,
+    pub vehicle_class: Option<String>,
+}
+
+/// Telemetry of the viewed car, with the latest of the packets sent less often.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimState {
+    pub header: PacketHeader,
+    pub telemetry: Telemetry,
+    pub race_definition: Option<RaceDefinition>,
+    pub timings: Option<Timings>,
+    pub game_state: Option<GameState>,
+    pub time_stats: Option<TimeStats>,
+    /// Participants by index.
+    pub participants: BTreeMap<u16, Participant>,
+}
+
+impl SimState {
+    /// Index of the participant whose car is described by the telemetry.
+    pub fn viewed_participant_index(&self) -> Option<usize> {
+        usize::try_from(self.telemetry.viewed_participant_index).ok()
+    }
+
+    pub fn viewed_participant(&self) -> Option<&Participant> {
+        let index = self.viewed_participant_index()?;
+        self.participants.get(&u16::try_from(index).ok()?)
+    }
+
+    pub fn viewed_timing(&self) -> Option<&ParticipantTiming> {
+        let index = self.viewed_participant_index()?;
+        self.timings.as_ref()?.participants.get(index)
+    }
+
+    pub fn viewed_stats(&self) -> Option<&ParticipantStats> {
+        let index = self.viewed_participant_index()?;
+        self.time_stats.as_ref()?.stats.get(index)
+    }
+}
+
+#[derive(Debug)]
+pub struct Client {
+    socket: UdpSocket,
+    race_definition: Option<RaceDefinition>,
+    timings: Option<Timings>,
+    game_state: Option<GameState>,
+    time_stats: Option<TimeStats>,
+    participants_changed_timestamp: Option<u32>,
+    participants: BTreeMap<u16, Participant>,
+    class_names: HashMap<u32, String>,
+}
+
+impl Client {
+    pub const DEFAULT_URI: &'static str = "0.0.0.0:5606";
+
+    pub async fn connect(uri: &str, retry_delay: Duration) -> Self {
+        loop {
+            if let Ok(client) = Self::try_connect(uri).await {
+                return client;
+            }
+            tokio::time::sleep(retry_delay).await;
+        }
+    }
+
+    /// Listen on `uri` until a packet is received.
+    pub async fn try_connect(uri: &str) -> Result<Self> {
+        Self::try_connect_with_socket(UdpSocket::bind(uri).await?).await
+    }
+
+    /// Listen on an already bound `socket` until a packet is received.
+    pub async fn try_connect_with_socket(socket: UdpSocket) -> Result<Self> {
+        let mut client = Self {
+            socket,
+            race_definition: None,
+            timings: None,
+            game_state: None,
+            time_stats: None,
+            participants_changed_timestamp: None,
+            participants: BTreeMap::new(),
+            class_names: HashMap::new(),
+        };
+        client.next_packet().await?;
+        Ok(client)
+    }
+
+    pub fn race_definition(&self) -> Option<&RaceDefinition> {
+        self.race_definition.as_ref()
+    }
+
+    pub fn game_state(&self) -> Option<&GameState> {
+        self.game_state.as_ref()
+    }
+
+    /// Participants by index, as far as they have been received.
+    pub fn participants(&self) -> &BTreeMap<u16, Participant> {
+        &self.participants
+    }
+
+    /// Waits for the next packet, ignoring packets that can not be decoded.
+    pub async fn next_packet(&mut self) -> Result<Packet> {
+        let mut buffer = vec![0u8; 2048];
+        loop {
+            let len = self.socket.recv(&mut buffer).await?;
+            let packet = match Packet::decode(&buffer[..len]) {
+                Ok(packet) => packet,
+                Err(err) => {
+                    log::debug!("Ignoring Project CARS 2 packet: {err}");
+                    continue;
+                }
+            };
+            self.update(&packet.data);
+            return Ok(packet);
+        }
+    }
+
+    /// Waits for the next telemetry packet and combines it with the latest other packets.
+    pub async fn next_sim_state(&mut self) -> Result<SimState> {
+        loop {
+            let packet = self.next_packet().await?;
+            let PacketData::Telemetry(telemetry) = packet.data else {
+                continue;
+            };
+            return Ok(SimState {
+                header: packet.header,
+                telemetry: *telemetry,
+                race_definition: self.race_definition.clone(),
+                timings: self.timings.clone(),
+                game_state: self.game_state.clone(),
+                time_stats: self.time_stats.clone(),
+                participants: self.participants.clone(),
+            });
+        }
+    }
+
+    fn update(&mut self, data: &PacketData) {
+        match data {
+            PacketData::RaceDefinition(race_definition) => {
+                self.race_definition = Some(*race_definition.clone())
+            }
+            PacketData::Timings(timings) => {
+                self.participants_changed(timings.participants_changed_timestamp);
+                self.timings = Some(*timings.clone());
+            }
+            PacketData::GameState(game_state) => self.game_state = Some(game_state.clone()),
+            PacketData::TimeStats(time_stats) => {
+                self.participants_changed(time_stats.participants_changed_timestamp);
+                self.time_stats = Some(time_stats.clone());
+            }
+            PacketData::Participants(participants) => {
+                self.participants_changed(participants.participants_changed_timestamp);
+                for name in &participants.names {
+                    let participant = self.participants.entry(name.index).or_default();
+                    participant.name = name.name.clone();
+                    participant.nationality = name.nationality;
+                }
+            }
+            PacketData::VehicleNames(vehicles) => {
+                for vehicle in vehicles {
+                    let participant = self.participants.entry(vehicle.index).or_default();
+                    participant.vehicle_name = Some(vehicle.name.clone());
+                    participant.vehicle_class = self.class_names.get(&vehicle.class).cloned();
+                }
+            }
+            PacketData::VehicleClassNames(classes) => {
+                for class in classes {
+                    self.class_names
+                        .insert(class.class_index, class.name.clone());
+                }
+            }
+            PacketData::Telemetry(_) | PacketData::Other(_) => {}
+        }
+    }
+
+    /// Forgets the participants once the game reports that they changed.
+    fn participants_changed(&mut self, timestamp: u32) {
+        if self.participants_changed_timestamp != Some(timestamp) {
+            if self.participants_changed_timestamp.is_some() {
+                self.participants.clear();
+            }
+            self.participants_changed_timestamp = Some(timestamp);
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Simetry for Client {
+    fn name(&self) -> &str {
+        "ProjectCars2"
+    }
+
+    async fn next_moment(&mut self) -> Option<Box<dyn Moment + Send + Sync + 'static>> {
+        Some(Box::new(self.next_sim_state().await.ok()?))
+    }
+}
+
+impl Moment for SimState {
+    fn vehicle_gear(&self) -> Option<i8> {
+        match self.telemetry.gear {
+            REVERSE_GEAR => Some(-1),
+            gear => Some(gear as i8),
+        }
+    }
+
+    fn vehicle_velocity(&self) -> Option<Velocity> {
+        Some(Velocity::new::<meter_per_second>(
+            self.telemetry.speed as f64,
+        ))
+    }
+
+    fn vehicle_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(
+            self.telemetry.rpm as f64,
+        ))
+    }
+
+    fn vehicle_max_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(
+            self.telemetry.max_rpm as f64,
+        ))
+    }
+
+    fn is_pit_limiter_engaged(&self) -> Option<bool> {
+        Some(self.telemetry.car_flags & car_flags::SPEED_LIMITER != 0)
+    }
+
+    fn is_vehicle_in_pit_lane(&self) -> Option<bool> {
+        Some(matches!(self.viewed_timing()?.pit_mode(), 1..=3))
+    }
+
+    fn flags(&self) -> Option<RacingFlags> {
+        Some(racing_flags(self.viewed_timing()?.flag_colour().into()))
+    }
+
+    fn vehicle_model_id(&self) -> Option<Cow<str>> {
+        let name = self.viewed_participant()?.vehicle_name.as_ref()?;
+        Some(name.as_str().into())
+    }
+
+    fn is_ignition_on(&self) -> Option<bool> {
+        Some(self.telemetry.car_flags & car_flags::ENGINE_ACTIVE != 0)
+    }
+
+    fn pedals(&self) -> Option<Pedals> {
+        Some(Pedals {
+            throttle: self.telemetry.throttle as f64 / 255.0,
+            brake: self.telemetry.brake as f64 / 255.0,
+            clutch: self.telemetry.clutch as f64 / 255.0,
+        })
+    }
+
+    fn pedals_raw(&self) -> Option<Pedals> {
+        Some(Pedals {
+            throttle: self.telemetry.unfiltered_throttle as f64 / 255.0,
+            brake: self.telemetry.unfiltered_brake as f64 / 255.0,
+            clutch: self.telemetry.unfiltered_clutch as f64 / 255.0,
+        })
+    }
+}
+
+/// Flags from the colour of the highest flag, which is the same in the UDP packets and the
+/// shared memory.
+fn racing_flags(colour: u32) -> RacingFlags {
+    RacingFlags {
+        green: colour == 1,
+        blue: colour == 2,
+        white: matches!(colour, 3 | 4),
+        red: colour == 5,
+        yellow: matches!(colour, 6 | 7),
+        black_and_white: colour == 8,
+        meatball: colour == 9,
+        black: colour == 10,
+        checkered: colour == 11,
+        ..Default::default()
+    }
+}
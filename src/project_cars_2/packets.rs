@@ -0,0 +1,605 @@
+//! Packets of the Project CARS 2 UDP protocol, version 2, laid out as in `SMS_UDP_Definitions.hpp`.
+//!
+//! Wheel arrays are ordered front left, front right, rear left, rear right.
+
+use anyhow::{bail, Result};
+use byteorder::{ByteOrder, LittleEndian};
+
+pub const MAX_PARTICIPANTS: usize = 32;
+const PARTICIPANTS_PER_PACKET: usize = 16;
+const HEADER_SIZE: usize = 12;
+const NAME_LEN: usize = 64;
+const TYRE_COMPOUND_LEN: usize = 40;
+const CLASS_NAME_LEN: usize = 20;
+const CLASSES_PER_PACKET: usize = 60;
+
+const TELEMETRY: u8 = 0;
+const RACE_DEFINITION: u8 = 1;
+const PARTICIPANTS: u8 = 2;
+const TIMINGS: u8 = 3;
+const GAME_STATE: u8 = 4;
+const TIME_STATS: u8 = 7;
+const PARTICIPANT_VEHICLE_NAMES: u8 = 8;
+
+const TELEMETRY_SIZE: usize = 559;
+const RACE_DEFINITION_SIZE: usize = 308;
+const PARTICIPANTS_SIZE: usize = 1136;
+const TIMINGS_SIZE: usize = 1063;
+const GAME_STATE_SIZE: usize = 24;
+const TIME_STATS_SIZE: usize = 1040;
+const VEHICLE_NAMES_SIZE: usize = 1164;
+const CLASS_NAMES_SIZE: usize = 1452;
+
+/// Bits of [`Telemetry::car_flags`].
+pub mod car_flags {
+    pub const HEADLIGHT: u8 = 1 << 0;
+    pub const ENGINE_ACTIVE: u8 = 1 << 1;
+    pub const ENGINE_WARNING: u8 = 1 << 2;
+    pub const SPEED_LIMITER: u8 = 1 << 3;
+    pub const ABS: u8 = 1 << 4;
+    pub const HANDBRAKE: u8 = 1 << 5;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PacketHeader {
+    /// Counter of all packets.
+    pub packet_number: u32,
+    /// Counter of the packets of this type.
+    pub category_packet_number: u32,
+    /// 1 based index of the part, for types that are split across packets.
+    pub partial_packet_index: u8,
+    pub partial_packet_number: u8,
+    pub packet_type: u8,
+    pub packet_version: u8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Telemetry {
+    /// Participant whose car is described, usually the player.
+    pub viewed_participant_index: i8,
+    /// From 0 to 255, before assists.
+    pub unfiltered_throttle: u8,
+    pub unfiltered_brake: u8,
+    /// From -127 to 127.
+    pub unfiltered_steering: i8,
+    pub unfiltered_clutch: u8,
+    /// See [`car_flags`].
+    pub car_flags: u8,
+    pub oil_temp_celsius: i16,
+    pub oil_pressure_kpa: u16,
+    pub water_temp_celsius: i16,
+    pub water_pressure_kpa: u16,
+    pub fuel_pressure_kpa: u16,
+    /// Liters.
+    pub fuel_capacity: u8,
+    /// From 0 to 255, after assists.
+    pub brake: u8,
+    pub throttle: u8,
+    pub clutch: u8,
+    /// From 0 to 1.
+    pub fuel_level: f32,
+    /// Meters per second.
+    pub speed: f32,
+    pub rpm: u16,
+    pub max_rpm: u16,
+    pub steering: i8,
+    /// 0 is neutral, 15 is reverse.
+    pub gear: u8,
+    pub num_gears: u8,
+    pub boost_amount: u8,
+    pub crash_state: u8,
+    pub odometer_km: f32,
+    pub orientation: [f32; 3],
+    pub local_velocity: [f32; 3],
+    pub world_velocity: [f32; 3],
+    pub angular_velocity: [f32; 3],
+    pub local_acceleration: [f32; 3],
+    pub world_acceleration: [f32; 3],
+    pub extents_centre: [f32; 3],
+    pub tyre_flags: [u8; 4],
+    pub terrain: [u8; 4],
+    pub tyre_y: [f32; 4],
+    pub tyre_rps: [f32; 4],
+    /// Celsius.
+    pub tyre_temp: [u8; 4],
+    pub tyre_height_above_ground: [f32; 4],
+    pub tyre_wear: [u8; 4],
+    pub brake_damage: [u8; 4],
+    pub suspension_damage: [u8; 4],
+    pub brake_temp_celsius: [i16; 4],
+    pub tyre_tread_temp: [u16; 4],
+    pub tyre_layer_temp: [u16; 4],
+    pub tyre_carcass_temp: [u16; 4],
+    pub tyre_rim_temp: [u16; 4],
+    pub tyre_internal_air_temp: [u16; 4],
+    pub tyre_temp_left: [u16; 4],
+    pub tyre_temp_center: [u16; 4],
+    pub tyre_temp_right: [u16; 4],
+    pub wheel_local_position_y: [f32; 4],
+    pub ride_height: [f32; 4],
+    pub suspension_travel: [f32; 4],
+    pub suspension_velocity: [f32; 4],
+    pub suspension_ride_height: [u16; 4],
+    pub air_pressure: [u16; 4],
+    pub engine_speed: f32,
+    pub engine_torque: f32,
+    pub wings: [u8; 2],
+    pub hand_brake: u8,
+    pub aero_damage: u8,
+    pub engine_damage: u8,
+    pub joy_pad: u32,
+    pub d_pad: u8,
+    pub tyre_compound: [String; 4],
+    pub turbo_boost_pressure: f32,
+    pub full_position: [f32; 3],
+    pub brake_bias: u8,
+    pub tick_count: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RaceDefinition {
+    /// Seconds.
+    pub world_fastest_lap_time: f32,
+    pub personal_fastest_lap_time: f32,
+    pub personal_fastest_sector_times: [f32; 3],
+    pub world_fastest_sector_times: [f32; 3],
+    /// Meters.
+    pub track_length: f32,
+    pub track_location: String,
+    pub track_variation: String,
+    pub translated_track_location: String,
+    pub translated_track_variation: String,
+    /// Laps, or minutes for timed sessions.
+    pub laps_time_in_event: u16,
+    pub is_timed_session: bool,
+    pub enforced_pit_stop_lap: i8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParticipantName {
+    pub index: u16,
+    pub name: String,
+    pub nationality: u32,
+}
+
+/// Half of the participants, split by [`PacketHeader::partial_packet_index`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Participants {
+    pub participants_changed_timestamp: u32,
+    pub names: Vec<ParticipantName>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParticipantTiming {
+    pub world_position: [i16; 3],
+    pub orientation: [i16; 3],
+    /// Meters.
+    pub current_lap_distance: u16,
+    pub race_position: u8,
+    pub is_active: bool,
+    pub sector: u8,
+    /// Colour in the lower 4 bits and reason in the upper 4 bits.
+    pub highest_flag: u8,
+    /// Pit mode in the lower 4 bits and pit schedule in the upper 4 bits.
+    pub pit_mode_schedule: u8,
+    pub car_index: u16,
+    /// Race state in the lower 3 bits, with bit 3 set for an invalid lap.
+    pub race_state: u8,
+    pub current_lap: u8,
+    /// Seconds.
+    pub current_time: f32,
+    pub current_sector_time: f32,
+    pub mp_participant_index: u16,
+}
+
+impl ParticipantTiming {
+    /// 0 is none, 1 green, 2 blue, 3 white for a slow car, 4 white for the final lap, 5 red,
+    /// 6 yellow, 7 double yellow, 8 black and white, 9 black with orange circle, 10 black,
+    /// 11 chequered.
+    pub fn flag_colour(&self) -> u8 {
+        self.highest_flag & 0x0F
+    }
+
+    /// 0 is none, 1 driving into the pits, 2 in the pits, 3 driving out of the pits, 4 in the
+    /// garage, 5 driving out of the garage.
+    pub fn pit_mode(&self) -> u8 {
+        self.pit_mode_schedule & 0x0F
+    }
+
+    pub fn is_lap_invalidated(&self) -> bool {
+        self.race_state & 0x08 != 0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Timings {
+    pub num_participants: i8,
+    pub participants_changed_timestamp: u32,
+    /// Seconds.
+    pub event_time_remaining: f32,
+    pub split_time_ahead: f32,
+    pub split_time_behind: f32,
+    pub split_time: f32,
+    pub participants: Vec<ParticipantTiming>,
+    pub local_participant_index: u16,
+    pub tick_count: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameState {
+    pub build_version_number: u16,
+    /// 0 is exited, 1 front end, 2 in game playing, 3 in game paused, 4 in game menu,
+    /// 5 in game restarting, 6 in game replay, 7 front end replay.
+    pub game_state: u8,
+    /// 0 is invalid, 1 practice, 2 test, 3 qualify, 4 formation lap, 5 race, 6 time attack.
+    pub session_state: u8,
+    /// Celsius.
+    pub ambient_temperature: i8,
+    pub track_temperature: i8,
+    pub rain_density: u8,
+    pub snow_density: u8,
+    pub wind_speed: i8,
+    pub wind_direction_x: i8,
+    pub wind_direction_y: i8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParticipantStats {
+    /// Seconds.
+    pub fastest_lap_time: f32,
+    pub last_lap_time: f32,
+    pub last_sector_time: f32,
+    pub fastest_sector_times: [f32; 3],
+    pub participant_online_rep: u32,
+    pub mp_participant_index: u16,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TimeStats {
+    pub participants_changed_timestamp: u32,
+    pub stats: Vec<ParticipantStats>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VehicleInfo {
+    /// Participant driving the vehicle.
+    pub index: u16,
+    pub class: u32,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClassInfo {
+    pub class_index: u32,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PacketData {
+    Telemetry(Box<Telemetry>),
+    RaceDefinition(Box<RaceDefinition>),
+    Participants(Participants),
+    Timings(Box<Timings>),
+    GameState(GameState),
+    TimeStats(TimeStats),
+    /// Half of the vehicles, split by [`PacketHeader::partial_packet_index`].
+    VehicleNames(Vec<VehicleInfo>),
+    /// Part of the vehicle classes, split by [`PacketHeader::partial_packet_index`].
+    VehicleClassNames(Vec<ClassInfo>),
+    /// Packet which is not decoded, by type.
+    Other(u8),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Packet {
+    pub header: PacketHeader,
+    pub data: PacketData,
+}
+
+impl Packet {
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < HEADER_SIZE {
+            bail!("Packet has {} bytes", data.len());
+        }
+        let header = PacketHeader {
+            packet_number: LittleEndian::read_u32(&data[0..]),
+            category_packet_number: LittleEndian::read_u32(&data[4..]),
+            partial_packet_index: data[8],
+            partial_packet_number: data[9],
+            packet_type: data[10],
+            packet_version: data[11],
+        };
+        let expected_size = match header.packet_type {
+            TELEMETRY => TELEMETRY_SIZE,
+            RACE_DEFINITION => RACE_DEFINITION_SIZE,
+            PARTICIPANTS => PARTICIPANTS_SIZE,
+            TIMINGS => TIMINGS_SIZE,
+            GAME_STATE => GAME_STATE_SIZE,
+            TIME_STATS => TIME_STATS_SIZE,
+            PARTICIPANT_VEHICLE_NAMES if data.len() == CLASS_NAMES_SIZE => CLASS_NAMES_SIZE,
+            PARTICIPANT_VEHICLE_NAMES => VEHICLE_NAMES_SIZE,
+            other => {
+                return Ok(Self {
+                    header,
+                    data: PacketData::Other(other),
+                })
+            }
+        };
+        if data.len() != expected_size {
+            bail!(
+                "Packet of type {} has {} bytes, expected {expected_size}",
+                header.packet_type,
+                data.len()
+            );
+        }
+        let b = Bytes(data);
+        let data = match header.packet_type {
+            TELEMETRY => PacketData::Telemetry(Box::new(parse_telemetry(b))),
+            RACE_DEFINITION => PacketData::RaceDefinition(Box::new(parse_race_definition(b))),
+            PARTICIPANTS => PacketData::Participants(parse_participants(b)),
+            TIMINGS => PacketData::Timings(Box::new(parse_timings(b))),
+            GAME_STATE => PacketData::GameState(parse_game_state(b)),
+            TIME_STATS => PacketData::TimeStats(parse_time_stats(b)),
+            _ if expected_size == CLASS_NAMES_SIZE => {
+                PacketData::VehicleClassNames(parse_class_names(b))
+            }
+            _ => PacketData::VehicleNames(parse_vehicle_names(b)),
+        };
+        Ok(Self { header, data })
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Bytes<'a>(&'a [u8]);
+
+impl Bytes<'_> {
+    fn u8(self, offset: usize) -> u8 {
+        self.0[offset]
+    }
+
+    fn i8(self, offset: usize) -> i8 {
+        self.0[offset] as i8
+    }
+
+    fn u16(self, offset: usize) -> u16 {
+        LittleEndian::read_u16(&self.0[offset..])
+    }
+
+    fn i16(self, offset: usize) -> i16 {
+        LittleEndian::read_i16(&self.0[offset..])
+    }
+
+    fn u32(self, offset: usize) -> u32 {
+        LittleEndian::read_u32(&self.0[offset..])
+    }
+
+    fn f32(self, offset: usize) -> f32 {
+        LittleEndian::read_f32(&self.0[offset..])
+    }
+
+    fn bytes<const N: usize>(self, offset: usize) -> [u8; N] {
+        std::array::from_fn(|idx| self.0[offset + idx])
+    }
+
+    fn i16s<const N: usize>(self, offset: usize) -> [i16; N] {
+        std::array::from_fn(|idx| self.i16(offset + idx * 2))
+    }
+
+    fn u16s<const N: usize>(self, offset: usize) -> [u16; N] {
+        std::array::from_fn(|idx| self.u16(offset + idx * 2))
+    }
+
+    fn f32s<const N: usize>(self, offset: usize) -> [f32; N] {
+        std::array::from_fn(|idx| self.f32(offset + idx * 4))
+    }
+
+    /// Null terminated string.
+    fn string(self, offset: usize, len: usize) -> String {
+        let bytes = &self.0[offset..offset + len];
+        let len = bytes.iter().position(|b| *b == 0).unwrap_or(len);
+        String::from_utf8_lossy(&bytes[..len]).into_owned()
+    }
+}
+
+fn parse_telemetry(b: Bytes) -> Telemetry {
+    Telemetry {
+        viewed_participant_index: b.i8(12),
+        unfiltered_throttle: b.u8(13),
+        unfiltered_brake: b.u8(14),
+        unfiltered_steering: b.i8(15),
+        unfiltered_clutch: b.u8(16),
+        car_flags: b.u8(17),
+        oil_temp_celsius: b.i16(18),
+        oil_pressure_kpa: b.u16(20),
+        water_temp_celsius: b.i16(22),
+        water_pressure_kpa: b.u16(24),
+        fuel_pressure_kpa: b.u16(26),
+        fuel_capacity: b.u8(28),
+        brake: b.u8(29),
+        throttle: b.u8(30),
+        clutch: b.u8(31),
+        fuel_level: b.f32(32),
+        speed: b.f32(36),
+        rpm: b.u16(40),
+        max_rpm: b.u16(42),
+        steering: b.i8(44),
+        gear: b.u8(45) & 0x0F,
+        num_gears: b.u8(45) >> 4,
+        boost_amount: b.u8(46),
+        crash_state: b.u8(47),
+        odometer_km: b.f32(48),
+        orientation: b.f32s(52),
+        local_velocity: b.f32s(64),
+        world_velocity: b.f32s(76),
+        angular_velocity: b.f32s(88),
+        local_acceleration: b.f32s(100),
+        world_acceleration: b.f32s(112),
+        extents_centre: b.f32s(124),
+        tyre_flags: b.bytes(136),
+        terrain: b.bytes(140),
+        tyre_y: b.f32s(144),
+        tyre_rps: b.f32s(160),
+        tyre_temp: b.bytes(176),
+        tyre_height_above_ground: b.f32s(180),
+        tyre_wear: b.bytes(196),
+        brake_damage: b.bytes(200),
+        suspension_damage: b.bytes(204),
+        brake_temp_celsius: b.i16s(208),
+        tyre_tread_temp: b.u16s(216),
+        tyre_layer_temp: b.u16s(224),
+        tyre_carcass_temp: b.u16s(232),
+        tyre_rim_temp: b.u16s(240),
+        tyre_internal_air_temp: b.u16s(248),
+        tyre_temp_left: b.u16s(256),
+        tyre_temp_center: b.u16s(264),
+        tyre_temp_right: b.u16s(272),
+        wheel_local_position_y: b.f32s(280),
+        ride_height: b.f32s(296),
+        suspension_travel: b.f32s(312),
+        suspension_velocity: b.f32s(328),
+        suspension_ride_height: b.u16s(344),
+        air_pressure: b.u16s(352),
+        engine_speed: b.f32(360),
+        engine_torque: b.f32(364),
+        wings: b.bytes(368),
+        hand_brake: b.u8(370),
+        aero_damage: b.u8(371),
+        engine_damage: b.u8(372),
+        joy_pad: b.u32(373),
+        d_pad: b.u8(377),
+        tyre_compound: std::array::from_fn(|idx| {
+            b.string(378 + idx * TYRE_COMPOUND_LEN, TYRE_COMPOUND_LEN)
+        }),
+        turbo_boost_pressure: b.f32(538),
+        full_position: b.f32s(542),
+        brake_bias: b.u8(554),
+        tick_count: b.u32(555),
+    }
+}
+
+fn parse_race_definition(b: Bytes) -> RaceDefinition {
+    let laps_time_in_event = b.u16(304);
+    RaceDefinition {
+        world_fastest_lap_time: b.f32(12),
+        personal_fastest_lap_time: b.f32(16),
+        personal_fastest_sector_times: b.f32s(20),
+        world_fastest_sector_times: b.f32s(32),
+        track_length: b.f32(44),
+        track_location: b.string(48, NAME_LEN),
+        track_variation: b.string(112, NAME_LEN),
+        translated_track_location: b.string(176, NAME_LEN),
+        translated_track_variation: b.string(240, NAME_LEN),
+        laps_time_in_event: laps_time_in_event & 0x7FFF,
+        is_timed_session: laps_time_in_event & 0x8000 != 0,
+        enforced_pit_stop_lap: b.i8(306),
+    }
+}
+
+fn parse_participants(b: Bytes) -> Participants {
+    Participants {
+        participants_changed_timestamp: b.u32(12),
+        names: (0..PARTICIPANTS_PER_PACKET)
+            .map(|idx| ParticipantName {
+                name: b.string(16 + idx * NAME_LEN, NAME_LEN),
+                nationality: b.u32(1040 + idx * 4),
+                index: b.u16(1104 + idx * 2),
+            })
+            .filter(|participant| !participant.name.is_empty())
+            .collect(),
+    }
+}
+
+fn parse_timings(b: Bytes) -> Timings {
+    Timings {
+        num_participants: b.i8(12),
+        participants_changed_timestamp: b.u32(13),
+        event_time_remaining: b.f32(17),
+        split_time_ahead: b.f32(21),
+        split_time_behind: b.f32(25),
+        split_time: b.f32(29),
+        participants: (0..MAX_PARTICIPANTS)
+            .map(|idx| {
+                let offset = 33 + idx * 32;
+                ParticipantTiming {
+                    world_position: b.i16s(offset),
+                    orientation: b.i16s(offset + 6),
+                    current_lap_distance: b.u16(offset + 12),
+                    race_position: b.u8(offset + 14) & 0x7F,
+                    is_active: b.u8(offset + 14) & 0x80 != 0,
+                    sector: b.u8(offset + 15),
+                    highest_flag: b.u8(offset + 16),
+                    pit_mode_schedule: b.u8(offset + 17),
+                    car_index: b.u16(offset + 18),
+                    race_state: b.u8(offset + 20),
+                    current_lap: b.u8(offset + 21),
+                    current_time: b.f32(offset + 22),
+                    current_sector_time: b.f32(offset + 26),
+                    mp_participant_index: b.u16(offset + 30),
+                }
+            })
+            .collect(),
+        local_participant_index: b.u16(1057),
+        tick_count: b.u32(1059),
+    }
+}
+
+fn parse_game_state(b: Bytes) -> GameState {
+    GameState {
+        build_version_number: b.u16(12),
+        game_state: b.u8(14) & 0x07,
+        session_state: (b.u8(14) >> 3) & 0x07,
+        ambient_temperature: b.i8(15),
+        track_temperature: b.i8(16),
+        rain_density: b.u8(17),
+        snow_density: b.u8(18),
+        wind_speed: b.i8(19),
+        wind_direction_x: b.i8(20),
+        wind_direction_y: b.i8(21),
+    }
+}
+
+fn parse_time_stats(b: Bytes) -> TimeStats {
+    TimeStats {
+        participants_changed_timestamp: b.u32(12),
+        stats: (0..MAX_PARTICIPANTS)
+            .map(|idx| {
+                let offset = 16 + idx * 32;
+                ParticipantStats {
+                    fastest_lap_time: b.f32(offset),
+                    last_lap_time: b.f32(offset + 4),
+                    last_sector_time: b.f32(offset + 8),
+                    fastest_sector_times: b.f32s(offset + 12),
+                    participant_online_rep: b.u32(offset + 24),
+                    mp_participant_index: b.u16(offset + 28),
+                }
+            })
+            .collect(),
+    }
+}
+
+fn parse_vehicle_names(b: Bytes) -> Vec<VehicleInfo> {
+    (0..PARTICIPANTS_PER_PACKET)
+        .map(|idx| {
+            let offset = HEADER_SIZE + idx * 72;
+            VehicleInfo {
+                index: b.u16(offset),
+                class: b.u32(offset + 4),
+                name: b.string(offset + 8, NAME_LEN),
+            }
+        })
+        .filter(|vehicle| !vehicle.name.is_empty())
+        .collect()
+}
+
+fn parse_class_names(b: Bytes) -> Vec<ClassInfo> {
+    (0..CLASSES_PER_PACKET)
+        .map(|idx| {
+            let offset = HEADER_SIZE + idx * 24;
+            ClassInfo {
+                class_index: b.u32(offset),
+                name: b.string(offset + 4, CLASS_NAME_LEN),
+            }
+        })
+        .filter(|class| !class.name.is_empty())
+        .collect()
+}
@@ -0,0 +1,370 @@
+//! Client for the `$pcars2$` shared memory of Project CARS 2 and Automobilista 2.
+//!
+//! The shared memory has to be enabled in the game settings, set to "Project CARS 2" in AMS2.
+//! The layout follows `SharedMemory.h` up to version 9, AMS2 appends fields after it.
+
+use super::{car_flags, racing_flags};
+use crate::{Moment, Pedals, RacingFlags};
+use anyhow::{bail, Result};
+use std::borrow::Cow;
+use uom::si::angular_velocity::revolution_per_minute;
+use uom::si::f64::{AngularVelocity, Velocity};
+use uom::si::velocity::meter_per_second;
+
+#[cfg(target_family = "windows")]
+pub use windows::Client;
+
+pub const SHARED_MEMORY_NAME: &[u8] = b"$pcars2$\0";
+/// Oldest version which has every field of [`SharedMemoryData`].
+pub const MIN_SHARED_MEMORY_VERSION: u32 = 9;
+pub const STORED_PARTICIPANTS_MAX: usize = 64;
+const STRING_LEN: usize = 64;
+const TYRE_COMPOUND_LEN: usize = 40;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ParticipantInfo {
+    pub is_active: u8,
+    pub name: [u8; STRING_LEN],
+    pub world_position: [f32; 3],
+    pub current_lap_distance: f32,
+    pub race_position: u32,
+    pub laps_completed: u32,
+    pub current_lap: u32,
+    pub current_sector: i32,
+}
+
+/// Raw shared memory, with wheel arrays ordered front left, front right, rear left, rear right.
+///
+/// Strings are null terminated UTF-8 and booleans are 0 or 1.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SharedMemoryData {
+    pub version: u32,
+    pub build_version_number: u32,
+    pub game_state: u32,
+    pub session_state: u32,
+    pub race_state: u32,
+    pub viewed_participant_index: i32,
+    pub num_participants: i32,
+    pub participant_info: [ParticipantInfo; STORED_PARTICIPANTS_MAX],
+    pub unfiltered_throttle: f32,
+    pub unfiltered_brake: f32,
+    pub unfiltered_steering: f32,
+    pub unfiltered_clutch: f32,
+    pub car_name: [u8; STRING_LEN],
+    pub car_class_name: [u8; STRING_LEN],
+    pub laps_in_event: u32,
+    pub track_location: [u8; STRING_LEN],
+    pub track_variation: [u8; STRING_LEN],
+    pub track_length: f32,
+    pub num_sectors: i32,
+    pub lap_invalidated: u8,
+    pub best_lap_time: f32,
+    pub last_lap_time: f32,
+    pub current_time: f32,
+    pub split_time_ahead: f32,
+    pub split_time_behind: f32,
+    pub split_time: f32,
+    pub event_time_remaining: f32,
+    pub personal_fastest_lap_time: f32,
+    pub world_fastest_lap_time: f32,
+    pub current_sector_times: [f32; 3],
+    pub fastest_sector_times: [f32; 3],
+    pub personal_fastest_sector_times: [f32; 3],
+    pub world_fastest_sector_times: [f32; 3],
+    pub highest_flag_colour: u32,
+    pub highest_flag_reason: u32,
+    pub pit_mode: u32,
+    pub pit_schedule: u32,
+    /// See [`car_flags`].
+    pub car_flags: u32,
+    pub oil_temp_celsius: f32,
+    pub oil_pressure_kpa: f32,
+    pub water_temp_celsius: f32,
+    pub water_pressure_kpa: f32,
+    pub fuel_pressure_kpa: f32,
+    pub fuel_level: f32,
+    pub fuel_capacity: f32,
+    /// Meters per second.
+    pub speed: f32,
+    pub rpm: f32,
+    pub max_rpm: f32,
+    /// From 0 to 1, after assists.
+    pub brake: f32,
+    pub throttle: f32,
+    pub clutch: f32,
+    pub steering: f32,
+    /// -1 is reverse, 0 is neutral.
+    pub gear: i32,
+    pub num_gears: i32,
+    pub odometer_km: f32,
+    pub anti_lock_active: u8,
+    pub last_opponent_collision_index: i32,
+    pub last_opponent_collision_magnitude: f32,
+    pub boost_active: u8,
+    pub boost_amount: f32,
+    pub orientation: [f32; 3],
+    pub local_velocity: [f32; 3],
+    pub world_velocity: [f32; 3],
+    pub angular_velocity: [f32; 3],
+    pub local_acceleration: [f32; 3],
+    pub world_acceleration: [f32; 3],
+    pub extents_centre: [f32; 3],
+    pub tyre_flags: [u32; 4],
+    pub terrain: [u32; 4],
+    pub tyre_y: [f32; 4],
+    pub tyre_rps: [f32; 4],
+    pub tyre_slip_speed: [f32; 4],
+    pub tyre_temp: [f32; 4],
+    pub tyre_grip: [f32; 4],
+    pub tyre_height_above_ground: [f32; 4],
+    pub tyre_lateral_stiffness: [f32; 4],
+    pub tyre_wear: [f32; 4],
+    pub brake_damage: [f32; 4],
+    pub suspension_damage: [f32; 4],
+    pub brake_temp_celsius: [f32; 4],
+    pub tyre_tread_temp: [f32; 4],
+    pub tyre_layer_temp: [f32; 4],
+    pub tyre_carcass_temp: [f32; 4],
+    pub tyre_rim_temp: [f32; 4],
+    pub tyre_internal_air_temp: [f32; 4],
+    pub crash_state: u32,
+    pub aero_damage: f32,
+    pub engine_damage: f32,
+    pub ambient_temperature: f32,
+    pub track_temperature: f32,
+    pub rain_density: f32,
+    pub wind_speed: f32,
+    pub wind_direction_x: f32,
+    pub wind_direction_y: f32,
+    pub cloud_brightness: f32,
+    /// Odd while the game is writing.
+    pub sequence_number: u32,
+    pub wheel_local_position_y: [f32; 4],
+    pub suspension_travel: [f32; 4],
+    pub suspension_velocity: [f32; 4],
+    pub air_pressure: [f32; 4],
+    pub engine_speed: f32,
+    pub engine_torque: f32,
+    pub wings: [f32; 2],
+    pub hand_brake: f32,
+    pub current_sector_1_times: [f32; STORED_PARTICIPANTS_MAX],
+    pub current_sector_2_times: [f32; STORED_PARTICIPANTS_MAX],
+    pub current_sector_3_times: [f32; STORED_PARTICIPANTS_MAX],
+    pub fastest_sector_1_times: [f32; STORED_PARTICIPANTS_MAX],
+    pub fastest_sector_2_times: [f32; STORED_PARTICIPANTS_MAX],
+    pub fastest_sector_3_times: [f32; STORED_PARTICIPANTS_MAX],
+    pub fastest_lap_times: [f32; STORED_PARTICIPANTS_MAX],
+    pub last_lap_times: [f32; STORED_PARTICIPANTS_MAX],
+    pub laps_invalidated: [u8; STORED_PARTICIPANTS_MAX],
+    pub race_states: [u32; STORED_PARTICIPANTS_MAX],
+    pub pit_modes: [u32; STORED_PARTICIPANTS_MAX],
+    pub orientations: [[f32; 3]; STORED_PARTICIPANTS_MAX],
+    pub speeds: [f32; STORED_PARTICIPANTS_MAX],
+    pub car_names: [[u8; STRING_LEN]; STORED_PARTICIPANTS_MAX],
+    pub car_class_names: [[u8; STRING_LEN]; STORED_PARTICIPANTS_MAX],
+    pub enforced_pit_stop_lap: i32,
+    pub translated_track_location: [u8; STRING_LEN],
+    pub translated_track_variation: [u8; STRING_LEN],
+    pub brake_bias: f32,
+    pub turbo_boost_pressure: f32,
+    pub tyre_compound: [[u8; TYRE_COMPOUND_LEN]; 4],
+    pub pit_schedules: [u32; STORED_PARTICIPANTS_MAX],
+    pub highest_flag_colours: [u32; STORED_PARTICIPANTS_MAX],
+    pub highest_flag_reasons: [u32; STORED_PARTICIPANTS_MAX],
+    pub nationalities: [u32; STORED_PARTICIPANTS_MAX],
+    pub snow_density: f32,
+}
+
+impl SharedMemoryData {
+    pub const SIZE: usize = std::mem::size_of::<Self>();
+
+    /// Decode the start of a copy of the shared memory.
+    pub fn decode(data: &[u8]) -> Result<Self> {
+        if data.len() < Self::SIZE {
+            bail!(
+                "Shared memory has {} bytes, expected at least {}",
+                data.len(),
+                Self::SIZE
+            );
+        }
+        // Every field is valid for any bit pattern.
+        Ok(unsafe { std::ptr::read_unaligned(data.as_ptr() as *const Self) })
+    }
+
+    pub fn car_name(&self) -> String {
+        extract_string(&self.car_name)
+    }
+
+    pub fn car_class_name(&self) -> String {
+        extract_string(&self.car_class_name)
+    }
+
+    pub fn track_location(&self) -> String {
+        extract_string(&self.track_location)
+    }
+
+    pub fn track_variation(&self) -> String {
+        extract_string(&self.track_variation)
+    }
+
+    /// Participant whose car is described, usually the player.
+    pub fn viewed_participant(&self) -> Option<&ParticipantInfo> {
+        let index = usize::try_from(self.viewed_participant_index).ok()?;
+        self.participant_info.get(index)
+    }
+}
+
+impl ParticipantInfo {
+    pub fn name(&self) -> String {
+        extract_string(&self.name)
+    }
+}
+
+fn extract_string(data: &[u8]) -> String {
+    let length = data.iter().position(|v| *v == 0).unwrap_or(data.len());
+    String::from_utf8_lossy(&data[..length]).into_owned()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SimState {
+    pub data: Box<SharedMemoryData>,
+}
+
+impl Moment for SimState {
+    fn vehicle_gear(&self) -> Option<i8> {
+        Some(self.data.gear as i8)
+    }
+
+    fn vehicle_velocity(&self) -> Option<Velocity> {
+        Some(Velocity::new::<meter_per_second>(self.data.speed as f64))
+    }
+
+    fn vehicle_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(
+            self.data.rpm as f64,
+        ))
+    }
+
+    fn vehicle_max_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(
+            self.data.max_rpm as f64,
+        ))
+    }
+
+    fn is_pit_limiter_engaged(&self) -> Option<bool> {
+        Some(self.data.car_flags & car_flags::SPEED_LIMITER as u32 != 0)
+    }
+
+    fn is_vehicle_in_pit_lane(&self) -> Option<bool> {
+        Some(matches!(self.data.pit_mode, 1..=3))
+    }
+
+    fn flags(&self) -> Option<RacingFlags> {
+        Some(racing_flags(self.data.highest_flag_colour))
+    }
+
+    fn vehicle_model_id(&self) -> Option<Cow<str>> {
+        Some(self.data.car_name().into())
+    }
+
+    fn is_ignition_on(&self) -> Option<bool> {
+        Some(self.data.car_flags & car_flags::ENGINE_ACTIVE as u32 != 0)
+    }
+
+    fn pedals(&self) -> Option<Pedals> {
+        Some(Pedals {
+            throttle: self.data.throttle as f64,
+            brake: self.data.brake as f64,
+            clutch: self.data.clutch as f64,
+        })
+    }
+
+    fn pedals_raw(&self) -> Option<Pedals> {
+        Some(Pedals {
+            throttle: self.data.unfiltered_throttle as f64,
+            brake: self.data.unfiltered_brake as f64,
+            clutch: self.data.unfiltered_clutch as f64,
+        })
+    }
+}
+
+#[cfg(target_family = "windows")]
+mod windows {
+    use super::{SharedMemoryData, SimState, MIN_SHARED_MEMORY_VERSION, SHARED_MEMORY_NAME};
+    use crate::windows_util::SharedMemory;
+    use crate::{Moment, Simetry};
+    use anyhow::{bail, Result};
+    use std::time::Duration;
+
+    pub struct Client {
+        shared_memory: SharedMemory,
+        last_sequence_number: u32,
+    }
+
+    impl Client {
+        pub async fn connect(retry_delay: Duration) -> Self {
+            loop {
+                if let Ok(v) = Self::try_connect().await {
+                    return v;
+                }
+                tokio::time::sleep(retry_delay).await
+            }
+        }
+
+        pub async fn try_connect() -> Result<Self> {
+            let poll_delay = Duration::from_millis(250);
+            let shared_memory = SharedMemory::connect(SHARED_MEMORY_NAME, poll_delay).await;
+            Ok(Self {
+                shared_memory,
+                last_sequence_number: 0,
+            })
+        }
+
+        pub async fn next_sim_state(&mut self) -> Result<SimState> {
+            let poll_delay = Duration::from_millis(1);
+            loop {
+                let sequence_number = self.sequence_number();
+                let data = unsafe { self.shared_memory.copy_as::<SharedMemoryData>() };
+                if sequence_number % 2 != 0 || sequence_number != self.sequence_number() {
+                    // Retry until we are sure we didn't catch shared memory mid-write
+                    continue;
+                }
+                if data.version < MIN_SHARED_MEMORY_VERSION {
+                    bail!(
+                        "Shared memory version {} is older than the supported {}",
+                        data.version,
+                        MIN_SHARED_MEMORY_VERSION,
+                    );
+                }
+                if self.last_sequence_number == sequence_number {
+                    tokio::time::sleep(poll_delay).await;
+                    continue;
+                }
+                self.last_sequence_number = sequence_number;
+                return Ok(SimState {
+                    data: Box::new(data),
+                });
+            }
+        }
+
+        fn sequence_number(&self) -> u32 {
+            unsafe {
+                let data = self.shared_memory.get_as::<SharedMemoryData>();
+                std::ptr::read_volatile(&data.sequence_number)
+            }
+        }
+    }
+
+    #[async_trait::async_trait]
+    impl Simetry for Client {
+        fn name(&self) -> &str {
+            "ProjectCars2SharedMemory"
+        }
+
+        async fn next_moment(&mut self) -> Option<Box<dyn Moment + Send + Sync + 'static>> {
+            Some(Box::new(self.next_sim_state().await.ok()?))
+        }
+    }
+}
@@ -1,3 +1,6 @@
+#[cfg(target_family = "unix")]
+use crate::unix_util::{SharedMemory, SharedMemoryPath};
+#[cfg(target_family = "windows")]
 use crate::windows_util::SharedMemory;
 use crate::{Moment, RacingFlags, Simetry};
 use anyhow::{bail, Result};
@@ -24,6 +27,7 @@ impl Client {
         }
     }
 
+    #[cfg(target_family = "windows")]
     pub async fn try_connect() -> Result<Self> {
         let poll_delay = Duration::from_millis(250);
         let shared_memory =
@@ -34,6 +38,35 @@ impl Client {
         })
     }
 
+    /// Connects to `/$R3E`, see [`Client::try_connect_with_path`].
+    #[cfg(target_family = "unix")]
+    pub async fn try_connect() -> Result<Self> {
+        let path = SharedMemoryPath::windows_name(bindings::R3E_SHARED_MEMORY_NAME);
+        Self::try_connect_with_path(&path).await
+    }
+
+    #[cfg(target_family = "unix")]
+    pub async fn connect_with_path(path: &SharedMemoryPath, retry_delay: Duration) -> Self {
+        loop {
+            if let Ok(v) = Self::try_connect_with_path(path).await {
+                return v;
+            }
+            tokio::time::sleep(retry_delay).await
+        }
+    }
+
+    /// Connects to the shared memory exposed at `path`.
+    #[cfg(target_family = "unix")]
+    pub async fn try_connect_with_path(path: &SharedMemoryPath) -> Result<Self> {
+        let poll_delay = Duration::from_millis(250);
+        let min_len = std::mem::size_of::<bindings::r3e_shared>();
+        let shared_memory = SharedMemory::connect_path(path, min_len, poll_delay).await;
+        Ok(Self {
+            shared_memory,
+            last_ticks: 0,
+        })
+    }
+
     pub async fn next_sim_state(&mut self) -> Result<SimState> {
         loop {
             let r3e_shared = unsafe { self.shared_memory.copy_as::<bindings::r3e_shared>() };
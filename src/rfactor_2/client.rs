@@ -6,7 +6,12 @@ use crate::rfactor_2::shared_memory_data::{
 use crate::rfactor_2::{
     Extended, ForceFeedback, MultiRules, PitInfo, Rules, Scoring, SimState, Telemetry, Weather,
 };
+#[cfg(target_family = "unix")]
+use crate::unix_util::{SharedMemory, SharedMemoryPath};
+#[cfg(target_family = "windows")]
 use crate::windows_util::SharedMemory;
+#[cfg(target_family = "unix")]
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -35,23 +40,20 @@ pub struct Client {
 
 impl Client {
     pub async fn connect() -> Self {
-        Self::connect_with_config(&Config {
-            dedicated_server_pid: None,
-            dedicated_server_global: false,
-        })
-        .await
+        Self::connect_with_config(&Config::default()).await
     }
 
     pub async fn connect_with_config(config: &Config) -> Self {
         let poll_delay = Duration::from_millis(250);
-        let telemetry = open_file("Telemetry", config, poll_delay).await;
-        let scoring = open_file("Scoring", config, poll_delay).await;
-        let rules = open_file("Rules", config, poll_delay).await;
-        let multi_rules = open_file("MultiRules", config, poll_delay).await;
-        let force_feedback = open_file("ForceFeedback", config, poll_delay).await;
-        let pit_info = open_file("PitInfo", config, poll_delay).await;
-        let weather = open_file("Weather", config, poll_delay).await;
-        let extended = open_file("Extended", config, poll_delay).await;
+        let telemetry = open_file::<PageTelemetry>(Buffer::Telemetry, config, poll_delay).await;
+        let scoring = open_file::<PageScoring>(Buffer::Scoring, config, poll_delay).await;
+        let rules = open_file::<PageRules>(Buffer::Rules, config, poll_delay).await;
+        let multi_rules = open_file::<PageMultiRules>(Buffer::MultiRules, config, poll_delay).await;
+        let force_feedback =
+            open_file::<PageForceFeedback>(Buffer::ForceFeedback, config, poll_delay).await;
+        let pit_info = open_file::<PagePitInfo>(Buffer::PitInfo, config, poll_delay).await;
+        let weather = open_file::<PageWeather>(Buffer::Weather, config, poll_delay).await;
+        let extended = open_file::<PageExtended>(Buffer::Extended, config, poll_delay).await;
         Self {
             sim_state_cache: SimState {
                 telemetry: read_when_ready::<PageTelemetry, Telemetry>(&telemetry),
@@ -150,12 +152,65 @@ fn read_when_ready<Page: Copy, Data: TryFrom<Box<Page>>>(memory: &SharedMemory)
     }
 }
 
+/// Buffer of the plugin, each of which is a separate shared memory.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum Buffer {
+    Telemetry,
+    Scoring,
+    Rules,
+    MultiRules,
+    ForceFeedback,
+    PitInfo,
+    Weather,
+    Extended,
+}
+
+impl Buffer {
+    fn name(self) -> &'static str {
+        match self {
+            Buffer::Telemetry => "Telemetry",
+            Buffer::Scoring => "Scoring",
+            Buffer::Rules => "Rules",
+            Buffer::MultiRules => "MultiRules",
+            Buffer::ForceFeedback => "ForceFeedback",
+            Buffer::PitInfo => "PitInfo",
+            Buffer::Weather => "Weather",
+            Buffer::Extended => "Extended",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
 pub struct Config {
     dedicated_server_pid: Option<String>,
     dedicated_server_global: bool,
+    #[cfg(target_family = "unix")]
+    paths: BTreeMap<Buffer, SharedMemoryPath>,
 }
 
-async fn open_file(buffer_type: &str, config: &Config, poll_delay: Duration) -> SharedMemory {
+impl Config {
+    /// Connect to the dedicated server with this process ID.
+    pub fn dedicated_server_pid(mut self, pid: String) -> Self {
+        self.dedicated_server_pid = Some(pid);
+        self
+    }
+
+    /// Use the global buffers of a dedicated server, which are shared across sessions.
+    pub fn dedicated_server_global(mut self, global: bool) -> Self {
+        self.dedicated_server_global = global;
+        self
+    }
+
+    /// Read `buffer` from `path`, instead of POSIX shared memory with the name of the buffer,
+    /// such as `/$rFactor2SMMP_Telemetry$`.
+    #[cfg(target_family = "unix")]
+    pub fn buffer_path(mut self, buffer: Buffer, path: SharedMemoryPath) -> Self {
+        self.paths.insert(buffer, path);
+        self
+    }
+}
+
+async fn open_file<Page>(buffer: Buffer, config: &Config, poll_delay: Duration) -> SharedMemory {
     let name = format!(
         "{global}$rFactor2SMMP_{buffer_type}${pid}\0",
         global = if config.dedicated_server_global {
@@ -163,7 +218,18 @@ async fn open_file(buffer_type: &str, config: &Config, poll_delay: Duration) ->
         } else {
             ""
         },
+        buffer_type = buffer.name(),
         pid = config.dedicated_server_pid.as_deref().unwrap_or(""),
     );
+    #[cfg(target_family = "unix")]
+    {
+        let path = config
+            .paths
+            .get(&buffer)
+            .cloned()
+            .unwrap_or_else(|| SharedMemoryPath::windows_name(name.as_bytes()));
+        SharedMemory::connect_path(&path, std::mem::size_of::<Page>(), poll_delay).await
+    }
+    #[cfg(target_family = "windows")]
     SharedMemory::connect(name.as_bytes(), poll_delay).await
 }
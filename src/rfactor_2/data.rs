@@ -1,3 +1,4 @@
+use crate::cp1252::cp1252_to_string;
 use crate::rfactor_2::shared_memory_data::{
     PageExtended, PageForceFeedback, PageHeader, PageMultiRules, PagePhysicsOptions, PagePitInfo,
     PageRules, PageScoring, PageScoringInfo, PageSessionTransitionCapture, PageTelemetry,
@@ -5,7 +6,6 @@ use crate::rfactor_2::shared_memory_data::{
     PageVehScoringCapture, PageVehicleScoring, PageVehicleTelemetry, PageWeather,
     PageWheelTelemetry, MAX_MAPPED_IDS, MAX_MAPPED_VEHICLES,
 };
-use crate::windows_util::cp1252_to_string;
 use anyhow::{bail, Error, Result};
 
 #[derive(Copy, Clone, Debug, Hash, Ord, PartialOrd, Eq, PartialEq)]
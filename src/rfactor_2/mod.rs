@@ -7,7 +7,7 @@ mod data;
 mod shared_memory_data;
 
 use crate::{Moment, RacingFlags, Simetry};
-pub use client::{Client, Config};
+pub use client::{Buffer, Client, Config};
 pub use data::{Extended, ForceFeedback, MultiRules, PitInfo, Rules, Scoring, Telemetry, Weather};
 use std::borrow::Cow;
 use std::sync::Arc;
@@ -0,0 +1,127 @@
+use super::{bindings, Event, Game, SimState};
+#[cfg(target_family = "unix")]
+use crate::unix_util::SharedMemory;
+#[cfg(target_family = "windows")]
+use crate::windows_util::SharedMemory;
+use crate::{Moment, Simetry};
+use anyhow::{bail, Result};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Name of the shared memory the scs-sdk-plugin writes to.
+#[cfg(target_family = "unix")]
+const SHARED_MEMORY_NAME: &[u8] = b"/SCSTelemetry\0";
+#[cfg(target_family = "windows")]
+const SHARED_MEMORY_NAME: &[u8] = b"Local\\SCSTelemetry\0";
+
+pub struct Client {
+    shared_memory: SharedMemory,
+    last_simulated_time: u64,
+    game: Game,
+    last_read: SimState,
+    /// Events are only queued once [`Client::next_event`] was called, so they do not pile up
+    /// for callers that only read sim states.
+    pending_events: Option<VecDeque<Event>>,
+}
+
+impl Client {
+    pub async fn connect(retry_delay: Duration) -> Self {
+        loop {
+            if let Ok(v) = Self::try_connect().await {
+                return v;
+            }
+            tokio::time::sleep(retry_delay).await
+        }
+    }
+
+    pub async fn try_connect() -> Result<Self> {
+        let poll_delay = Duration::from_millis(250);
+        let shared_memory = SharedMemory::connect(SHARED_MEMORY_NAME, poll_delay).await;
+        let sim_state = Self::inner_next_sim_state(&shared_memory)?;
+        if !sim_state.shared.sdkActive {
+            bail!("SDK is not active");
+        }
+        Ok(Self {
+            shared_memory,
+            last_simulated_time: sim_state.shared.simulatedTime,
+            game: sim_state.game,
+            last_read: sim_state,
+            pending_events: None,
+        })
+    }
+
+    fn inner_next_sim_state(shared_memory: &SharedMemory) -> Result<SimState> {
+        loop {
+            let shared = unsafe { shared_memory.copy_as::<bindings::scsTelemetryMap_t>() };
+            let shared_retry = unsafe { shared_memory.copy_as::<bindings::scsTelemetryMap_t>() };
+            if shared != shared_retry {
+                // Retry until we are sure we didn't catch shared memory mid-write
+                continue;
+            }
+            if shared.scs_values.telemetry_plugin_revision != bindings::PLUGIN_REVID {
+                bail!(
+                    "Plugin revision {} is incompatible with {} version from the DLL",
+                    bindings::PLUGIN_REVID,
+                    shared.scs_values.telemetry_plugin_revision,
+                );
+            }
+            let game = Game::from_id(shared.scs_values.game)?;
+            return Ok(SimState { shared, game });
+        }
+    }
+
+    pub async fn next_sim_state(&mut self) -> Result<SimState> {
+        loop {
+            let sim_state = self.read()?;
+            if sim_state.shared.simulatedTime == self.last_simulated_time {
+                // Querying too frequently
+                continue;
+            }
+            self.last_simulated_time = sim_state.shared.simulatedTime;
+            return Ok(sim_state);
+        }
+    }
+
+    /// Waits for the next gameplay event, reporting each one once although the plugin keeps its
+    /// flag set for a while. Events already active when connecting are not reported.
+    ///
+    /// Another event of the same kind while the plugin still holds the flag of the first one is
+    /// merged into it, as the shared memory only keeps the latest. Events are recorded from the
+    /// first call on, including while reading sim states in between.
+    pub async fn next_event(&mut self) -> Result<Event> {
+        loop {
+            let pending_events = self.pending_events.get_or_insert_with(VecDeque::new);
+            if let Some(event) = pending_events.pop_front() {
+                return Ok(event);
+            }
+            self.read()?;
+            if self.pending_events.as_ref().is_some_and(|v| v.is_empty()) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+    }
+
+    /// Reads the shared memory, queueing the events that appeared since the last read.
+    fn read(&mut self) -> Result<SimState> {
+        let sim_state = Self::inner_next_sim_state(&self.shared_memory)?;
+        if let Some(pending_events) = &mut self.pending_events {
+            pending_events.extend(sim_state.new_events(&self.last_read));
+        }
+        self.last_read = sim_state.clone();
+        Ok(sim_state)
+    }
+}
+
+#[async_trait::async_trait]
+impl Simetry for Client {
+    fn name(&self) -> &str {
+        match self.game {
+            Game::Ets2 => "ETS2",
+            Game::Ats => "ATS",
+        }
+    }
+
+    async fn next_moment(&mut self) -> Option<Box<dyn Moment + Send + Sync + 'static>> {
+        Some(Box::new(self.next_sim_state().await.ok()?))
+    }
+}
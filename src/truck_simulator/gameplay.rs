@@ -0,0 +1,90 @@
+/// City and company at either end of a job.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Place {
+    pub city_id: String,
+    pub city: String,
+    pub company_id: String,
+    pub company: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cargo {
+    pub id: String,
+    pub name: String,
+    /// Kilograms.
+    pub mass: f32,
+    /// From 0 to 1.
+    pub damage: f32,
+    pub loaded: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Job {
+    pub cargo: Cargo,
+    pub source: Place,
+    pub destination: Place,
+    pub income: u64,
+    /// Such as `cargo_market` or `freight_market`.
+    pub market: String,
+    pub special: bool,
+    /// In-game minutes since the start of the game.
+    pub delivery_time: u32,
+    pub planned_distance_km: u32,
+}
+
+/// Route of the navigation, all zero without one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Navigation {
+    /// Meters.
+    pub distance: f32,
+    /// Seconds.
+    pub time: f32,
+    /// Meters per second, 0 where there is no limit.
+    pub speed_limit: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobDelivered {
+    pub revenue: i64,
+    pub earned_xp: i32,
+    /// From 0 to 1.
+    pub cargo_damage: f32,
+    pub distance_km: f32,
+    /// In-game minutes the delivery took.
+    pub delivery_time: u32,
+    pub autopark_used: bool,
+    pub autoload_used: bool,
+}
+
+/// Ride on a ferry or train.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Transport {
+    pub source_id: String,
+    pub source_name: String,
+    pub target_id: String,
+    pub target_name: String,
+    pub amount: i64,
+}
+
+/// Gameplay event reported by the plugin.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    JobStarted(Box<Job>),
+    JobDelivered(JobDelivered),
+    JobCancelled {
+        penalty: i64,
+    },
+    Fined {
+        offence: String,
+        amount: i64,
+    },
+    TollgatePaid {
+        amount: i64,
+    },
+    Ferry(Transport),
+    Train(Transport),
+    RefuelPaid {
+        /// Liters.
+        amount: f32,
+    },
+}
@@ -1,77 +1,18 @@
-use crate::windows_util::SharedMemory;
-use crate::{Moment, Pedals, Simetry};
+use crate::{Moment, Pedals};
 use anyhow::{bail, Result};
 use std::borrow::Cow;
-use std::time::Duration;
+use std::os::raw::c_char;
 use uom::si::angular_velocity::revolution_per_minute;
 use uom::si::f64::{AngularVelocity, Velocity};
 use uom::si::velocity::kilometer_per_hour;
 
 pub mod bindings;
+mod client;
+mod gameplay;
 pub mod json_client;
 
-pub struct Client {
-    shared_memory: SharedMemory,
-    last_simulated_time: u64,
-    game: Game,
-}
-
-impl Client {
-    pub async fn connect(retry_delay: Duration) -> Self {
-        loop {
-            if let Ok(v) = Self::try_connect().await {
-                return v;
-            }
-            tokio::time::sleep(retry_delay).await
-        }
-    }
-
-    pub async fn try_connect() -> Result<Self> {
-        let poll_delay = Duration::from_millis(250);
-        let shared_memory = SharedMemory::connect(b"Local\\SCSTelemetry\0", poll_delay).await;
-        let sim_state = Self::inner_next_sim_state(&shared_memory)?;
-        if !sim_state.shared.sdkActive {
-            bail!("SDK is not active");
-        }
-        Ok(Self {
-            shared_memory,
-            last_simulated_time: sim_state.shared.simulatedTime,
-            game: sim_state.game,
-        })
-    }
-
-    fn inner_next_sim_state(shared_memory: &SharedMemory) -> Result<SimState> {
-        loop {
-            let shared = unsafe { shared_memory.copy_as::<bindings::scsTelemetryMap_t>() };
-            let shared_retry = unsafe { shared_memory.copy_as::<bindings::scsTelemetryMap_t>() };
-            if shared != shared_retry {
-                // Retry until we are sure we didn't catch shared memory mid-write
-                continue;
-            }
-            if shared.scs_values.telemetry_plugin_revision != bindings::PLUGIN_REVID {
-                bail!(
-                    "Plugin revision {} is incompatible with {} version from the DLL",
-                    bindings::PLUGIN_REVID,
-                    shared.scs_values.telemetry_plugin_revision,
-                );
-            }
-            let game = Game::from_id(shared.scs_values.game)?;
-            return Ok(SimState { shared, game });
-        }
-    }
-
-    pub async fn next_sim_state(&mut self) -> Result<SimState> {
-        loop {
-            let sim_state = Self::inner_next_sim_state(&self.shared_memory)?;
-            if sim_state.shared.simulatedTime == self.last_simulated_time {
-                // Querying too frequently
-                continue;
-            }
-            self.last_simulated_time = sim_state.shared.simulatedTime;
-            return Ok(sim_state);
-        }
-    }
-}
+pub use client::Client;
+pub use gameplay::{Cargo, Event, Job, JobDelivered, Navigation, Place, Transport};
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Game {
@@ -89,7 +30,7 @@ impl Game {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct SimState {
     pub game: Game,
     pub shared: bindings::scsTelemetryMap_t,
@@ -104,19 +45,137 @@ impl SimState {
         )
         .unwrap()
     }
-}
 
-#[async_trait::async_trait]
-impl Simetry for Client {
-    fn name(&self) -> &str {
-        match self.game {
-            Game::Ets2 => "ETS2",
-            Game::Ats => "ATS",
+    /// Text up to the NUL, which the plugin writes as UTF-8 for names of places and cargo.
+    fn parse_utf8(data: &[c_char]) -> String {
+        let bytes = data
+            .iter()
+            .map(|v| *v as u8)
+            .take_while(|v| *v != 0)
+            .collect::<Vec<_>>();
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+
+    /// Current job, if any.
+    pub fn job(&self) -> Option<Job> {
+        if !self.shared.special_b.onJob {
+            return None;
+        }
+        let config = &self.shared.config_s;
+        Some(Job {
+            cargo: Cargo {
+                id: Self::parse_utf8(&config.cargoId),
+                name: Self::parse_utf8(&config.cargo),
+                mass: self.shared.config_f.cargoMass,
+                damage: self.shared.job_f.cargoDamage,
+                loaded: self.shared.config_b.isCargoLoaded,
+            },
+            source: Place {
+                city_id: Self::parse_utf8(&config.citySrcId),
+                city: Self::parse_utf8(&config.citySrc),
+                company_id: Self::parse_utf8(&config.compSrcId),
+                company: Self::parse_utf8(&config.compSrc),
+            },
+            destination: Place {
+                city_id: Self::parse_utf8(&config.cityDstId),
+                city: Self::parse_utf8(&config.cityDst),
+                company_id: Self::parse_utf8(&config.compDstId),
+                company: Self::parse_utf8(&config.compDst),
+            },
+            income: self.shared.config_ull.jobIncome,
+            market: Self::parse_utf8(&config.jobMarket),
+            special: self.shared.config_b.specialJob,
+            delivery_time: self.shared.config_ui.time_abs_delivery,
+            planned_distance_km: self.shared.config_ui.plannedDistanceKm,
+        })
+    }
+
+    /// Cargo of the current job, if any.
+    pub fn cargo(&self) -> Option<Cargo> {
+        self.job().map(|job| job.cargo)
+    }
+
+    pub fn navigation(&self) -> Navigation {
+        Navigation {
+            distance: self.shared.truck_f.routeDistance,
+            time: self.shared.truck_f.routeTime,
+            speed_limit: self.shared.truck_f.speedLimit,
         }
     }
 
-    async fn next_moment(&mut self) -> Option<Box<dyn Moment + Send + Sync + 'static>> {
-        Some(Box::new(self.next_sim_state().await.ok()?))
+    /// Events whose flags the plugin currently has set, which stay set for a while.
+    pub fn active_events(&self) -> Vec<Event> {
+        let special = &self.shared.special_b;
+        let gameplay = &self.shared.gameplay_s;
+        let amounts = &self.shared.gameplay_ll;
+        let mut events = Vec::new();
+        if let Some(job) = self.job() {
+            events.push(Event::JobStarted(Box::new(job)));
+        }
+        if special.jobDelivered {
+            events.push(Event::JobDelivered(JobDelivered {
+                revenue: amounts.jobDeliveredRevenue,
+                earned_xp: self.shared.gameplay_i.jobDeliveredEarnedXp,
+                cargo_damage: self.shared.gameplay_f.jobDeliveredCargoDamage,
+                distance_km: self.shared.gameplay_f.jobDeliveredDistanceKm,
+                delivery_time: self.shared.gameplay_ui.jobDeliveredDeliveryTime,
+                autopark_used: self.shared.gameplay_b.jobDeliveredAutoparkUsed,
+                autoload_used: self.shared.gameplay_b.jobDeliveredAutoloadUsed,
+            }));
+        }
+        if special.jobCancelled {
+            events.push(Event::JobCancelled {
+                penalty: amounts.jobCancelledPenalty,
+            });
+        }
+        if special.fined {
+            events.push(Event::Fined {
+                offence: Self::parse_utf8(&gameplay.fineOffence),
+                amount: amounts.fineAmount,
+            });
+        }
+        if special.tollgate {
+            events.push(Event::TollgatePaid {
+                amount: amounts.tollgatePayAmount,
+            });
+        }
+        if special.ferry {
+            events.push(Event::Ferry(Transport {
+                source_id: Self::parse_utf8(&gameplay.ferrySourceId),
+                source_name: Self::parse_utf8(&gameplay.ferrySourceName),
+                target_id: Self::parse_utf8(&gameplay.ferryTargetId),
+                target_name: Self::parse_utf8(&gameplay.ferryTargetName),
+                amount: amounts.ferryPayAmount,
+            }));
+        }
+        if special.train {
+            events.push(Event::Train(Transport {
+                source_id: Self::parse_utf8(&gameplay.trainSourceId),
+                source_name: Self::parse_utf8(&gameplay.trainSourceName),
+                target_id: Self::parse_utf8(&gameplay.trainTargetId),
+                target_name: Self::parse_utf8(&gameplay.trainTargetName),
+                amount: amounts.trainPayAmount,
+            }));
+        }
+        if special.refuelPayed {
+            events.push(Event::RefuelPaid {
+                amount: self.shared.gameplay_f.refuelAmount,
+            });
+        }
+        events
+    }
+
+    /// Events that became active since `previous`.
+    pub fn new_events(&self, previous: &SimState) -> Vec<Event> {
+        let previous = previous.active_events();
+        self.active_events()
+            .into_iter()
+            .filter(|event| {
+                !previous
+                    .iter()
+                    .any(|v| std::mem::discriminant(v) == std::mem::discriminant(event))
+            })
+            .collect()
     }
 }
 
@@ -0,0 +1,161 @@
+use anyhow::{bail, Context, Result};
+use libc::c_void;
+use std::ffi::CString;
+use std::fs::File;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Location of shared memory on Unix, such as one exposed from a Proton prefix by a bridge.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum SharedMemoryPath {
+    /// POSIX shared memory opened with `shm_open`, such as `/acpmf_physics`.
+    Name(String),
+    /// Any file that can be mapped, such as one in `/dev/shm`.
+    ///
+    /// The file must not shrink while it is mapped, as reading mapped pages past its end raises
+    /// `SIGBUS`. [`SharedMemory::available_len`] catches a file that has already shrunk, but not
+    /// one truncated between that check and the read.
+    File(PathBuf),
+}
+
+impl SharedMemoryPath {
+    /// POSIX shared memory with the name of a Windows file mapping, such as `/$R3E` for `$R3E`.
+    pub(crate) fn windows_name(name: &[u8]) -> Self {
+        let name = String::from_utf8_lossy(name.strip_suffix(b"\0").unwrap_or(name));
+        let name = name.rsplit('\\').next().unwrap_or_default();
+        Self::Name(format!("/{name}"))
+    }
+
+    fn open(&self) -> Result<OwnedFd> {
+        match self {
+            SharedMemoryPath::Name(name) => {
+                let c_name = CString::new(name.as_str())?;
+                let fd = unsafe { libc::shm_open(c_name.as_ptr(), libc::O_RDONLY, 0) };
+                if fd == -1 {
+                    return Err(std::io::Error::last_os_error())
+                        .with_context(|| format!("Opening shared memory {name:?} failed"));
+                }
+                Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+            }
+            SharedMemoryPath::File(path) => Ok(File::open(path)
+                .with_context(|| format!("Opening {path:?} failed"))?
+                .into()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SafeMapping {
+    inner: *mut c_void,
+    len: usize,
+}
+
+unsafe impl Send for SafeMapping {}
+unsafe impl Sync for SafeMapping {}
+
+impl SafeMapping {
+    pub fn new(fd: &OwnedFd, len: usize) -> Option<Self> {
+        let inner = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                fd.as_raw_fd(),
+                0,
+            )
+        };
+        if inner == libc::MAP_FAILED {
+            None
+        } else {
+            Some(Self { inner, len })
+        }
+    }
+}
+
+impl Drop for SafeMapping {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.inner, self.len);
+        }
+    }
+}
+
+/// Read-only view of shared memory, with the same interface as the Windows one.
+#[derive(Debug)]
+pub struct SharedMemory {
+    fd: OwnedFd,
+    mapping: SafeMapping,
+}
+
+impl SharedMemory {
+    /// Waits until the shared memory `name`, such as `b"/SCSTelemetry\0"`, exists and has data.
+    pub async fn connect(name: &[u8], poll_delay: Duration) -> Self {
+        let name = name.strip_suffix(b"\0").unwrap_or(name);
+        let path = SharedMemoryPath::Name(String::from_utf8_lossy(name).into_owned());
+        Self::connect_path(&path, 1, poll_delay).await
+    }
+
+    /// Waits until `path` exists and holds at least `min_len` bytes.
+    pub async fn connect_path(
+        path: &SharedMemoryPath,
+        min_len: usize,
+        poll_delay: Duration,
+    ) -> Self {
+        loop {
+            match Self::open(path) {
+                Ok(shared_memory) if shared_memory.len() >= min_len => return shared_memory,
+                _ => tokio::time::sleep(poll_delay).await,
+            }
+        }
+    }
+
+    /// Maps the whole of `path`, which must not be empty.
+    pub fn open(path: &SharedMemoryPath) -> Result<Self> {
+        let fd = path.open()?;
+        let mut stat = unsafe { std::mem::zeroed::<libc::stat>() };
+        if unsafe { libc::fstat(fd.as_raw_fd(), &mut stat) } == -1 {
+            return Err(std::io::Error::last_os_error()).context("Reading the size failed");
+        }
+        if stat.st_size <= 0 {
+            bail!("{path:?} is empty");
+        }
+        let Some(mapping) = SafeMapping::new(&fd, stat.st_size as usize) else {
+            return Err(std::io::Error::last_os_error())
+                .with_context(|| format!("Mapping {path:?} failed"));
+        };
+        Ok(Self { fd, mapping })
+    }
+
+    pub fn len(&self) -> usize {
+        self.mapping.len
+    }
+
+    /// Bytes that can currently be read, which is less than [`Self::len`] once the file shrank.
+    pub fn available_len(&self) -> usize {
+        let mut stat = unsafe { std::mem::zeroed::<libc::stat>() };
+        if unsafe { libc::fstat(self.fd.as_raw_fd(), &mut stat) } == -1 {
+            return 0;
+        }
+        usize::try_from(stat.st_size)
+            .unwrap_or_default()
+            .min(self.len())
+    }
+
+    pub unsafe fn get(&self) -> *const c_void {
+        self.mapping.inner
+    }
+
+    /// Panics if the shared memory is smaller than `T`.
+    pub unsafe fn get_as<T>(&self) -> &T {
+        assert!(std::mem::size_of::<T>() <= self.len());
+        &(*(self.get() as *const T))
+    }
+
+    /// Panics if the shared memory is smaller than `T`.
+    pub unsafe fn copy_as<T: Copy>(&self) -> T {
+        assert!(std::mem::size_of::<T>() <= self.len());
+        *(self.get() as *const T)
+    }
+}
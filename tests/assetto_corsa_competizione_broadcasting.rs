@@ -0,0 +1,188 @@
+use simetry::assetto_corsa_competizione::broadcasting::{
+    BroadcastingEventType, CarLocation, Client, Message, Registration, SessionPhase,
+};
+use simetry::Moment;
+use std::net::SocketAddr;
+use tokio::net::UdpSocket;
+use uom::si::velocity::kilometer_per_hour;
+
+fn string(data: &mut Vec<u8>, value: &str) {
+    data.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    data.extend_from_slice(value.as_bytes());
+}
+
+fn lap_info(data: &mut Vec<u8>, car_index: u16, lap_time: i32) {
+    data.extend_from_slice(&lap_time.to_le_bytes());
+    data.extend_from_slice(&car_index.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.push(3);
+    for split in [30_000, i32::MAX, i32::MAX] {
+        data.extend_from_slice(&split.to_le_bytes());
+    }
+    data.extend_from_slice(&[0, 1, 0, 0]);
+}
+
+fn registration_result() -> Vec<u8> {
+    let mut data = vec![1];
+    data.extend_from_slice(&7i32.to_le_bytes());
+    data.extend_from_slice(&[1, 1]);
+    string(&mut data, "");
+    data
+}
+
+fn realtime_update(focused_car_index: i32) -> Vec<u8> {
+    let mut data = vec![2];
+    data.extend_from_slice(&1u16.to_le_bytes());
+    data.extend_from_slice(&2u16.to_le_bytes());
+    data.extend_from_slice(&[10, 5]);
+    data.extend_from_slice(&60_000f32.to_le_bytes());
+    data.extend_from_slice(&3_600_000f32.to_le_bytes());
+    data.extend_from_slice(&focused_car_index.to_le_bytes());
+    string(&mut data, "Onboard");
+    string(&mut data, "Onboard0");
+    string(&mut data, "Broadcasting");
+    data.push(0);
+    data.extend_from_slice(&50_400f32.to_le_bytes());
+    data.extend_from_slice(&[22, 31, 3, 0, 0]);
+    lap_info(&mut data, focused_car_index as u16, i32::MAX);
+    data
+}
+
+fn car_update(car_index: u16, location: u8) -> Vec<u8> {
+    let mut data = vec![3];
+    data.extend_from_slice(&car_index.to_le_bytes());
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.extend_from_slice(&[1, 5]);
+    for value in [100f32, -20.0, 1.5] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.push(location);
+    for value in [187u16, 2, 1, 2] {
+        data.extend_from_slice(&value.to_le_bytes());
+    }
+    data.extend_from_slice(&0.4f32.to_le_bytes());
+    data.extend_from_slice(&4u16.to_le_bytes());
+    data.extend_from_slice(&(-350i32).to_le_bytes());
+    for lap_time in [101_000, 102_000, i32::MAX] {
+        lap_info(&mut data, car_index, lap_time);
+    }
+    data
+}
+
+fn entry_list_car(car_index: u16) -> Vec<u8> {
+    let mut data = vec![6];
+    data.extend_from_slice(&car_index.to_le_bytes());
+    data.push(25);
+    string(&mut data, "Team");
+    data.extend_from_slice(&911i32.to_le_bytes());
+    data.extend_from_slice(&[0, 0]);
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data.push(1);
+    string(&mut data, "Jörg");
+    string(&mut data, "Driver");
+    string(&mut data, "DRI");
+    data.push(2);
+    data.extend_from_slice(&0u16.to_le_bytes());
+    data
+}
+
+fn broadcasting_event() -> Vec<u8> {
+    let mut data = vec![7, 4];
+    string(&mut data, "Accident");
+    data.extend_from_slice(&61_000i32.to_le_bytes());
+    data.extend_from_slice(&12i32.to_le_bytes());
+    data
+}
+
+async fn expect_request(server: &UdpSocket, message_type: u8) -> (Vec<u8>, SocketAddr) {
+    let mut buffer = [0u8; 512];
+    let (len, origin) = server.recv_from(&mut buffer).await.unwrap();
+    assert_eq!(buffer[0], message_type);
+    (buffer[..len].to_vec(), origin)
+}
+
+#[tokio::test]
+async fn registers_and_tracks_focused_car() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let uri = server.local_addr().unwrap().to_string();
+    let registration = Registration {
+        command_password: "cmd".to_string(),
+        ..Default::default()
+    };
+    let client = tokio::spawn(async move { Client::try_connect(&uri, &registration).await });
+
+    let (register, origin) = expect_request(&server, 1).await;
+    assert_eq!(register[1], 4);
+    assert!(register.ends_with(b"\x03\x00cmd"));
+    server
+        .send_to(&registration_result(), origin)
+        .await
+        .unwrap();
+    let mut client = client.await.unwrap().unwrap();
+    assert_eq!(client.connection_id(), 7);
+    assert!(!client.is_read_only());
+    let (entry_list_request, _) = expect_request(&server, 10).await;
+    assert_eq!(entry_list_request[1..], 7i32.to_le_bytes());
+    expect_request(&server, 11).await;
+
+    server.send_to(&realtime_update(12), origin).await.unwrap();
+    server.send_to(&car_update(3, 1), origin).await.unwrap();
+    server.send_to(&entry_list_car(12), origin).await.unwrap();
+    server.send_to(&car_update(12, 2), origin).await.unwrap();
+    let state = client.next_sim_state().await.unwrap();
+    assert_eq!(state.realtime_update.phase, SessionPhase::Session);
+    assert_eq!(state.realtime_update.clouds, 0.3);
+    assert_eq!(state.car_update.car_location, CarLocation::Pitlane);
+    assert_eq!(state.car_update.last_lap.lap_time_ms, Some(102_000));
+    assert_eq!(state.car_update.current_lap.lap_time_ms, None);
+    assert_eq!(
+        state.car_update.best_session_lap.splits,
+        [Some(30_000), None, None]
+    );
+    assert_eq!(
+        state
+            .car_info
+            .as_ref()
+            .unwrap()
+            .current_driver()
+            .unwrap()
+            .first_name,
+        "Jörg"
+    );
+    assert_eq!(state.vehicle_gear(), Some(3));
+    assert_eq!(
+        state
+            .vehicle_velocity()
+            .unwrap()
+            .get::<kilometer_per_hour>(),
+        187.0
+    );
+    assert_eq!(state.is_vehicle_in_pit_lane(), Some(true));
+    assert_eq!(state.vehicle_model_id().as_deref(), Some("25"));
+    assert_eq!(client.car_updates().len(), 2);
+
+    server.send_to(&broadcasting_event(), origin).await.unwrap();
+    let event = client.next_broadcasting_event().await.unwrap();
+    assert_eq!(event.event_type, BroadcastingEventType::Accident);
+    assert_eq!(event.car_index, 12);
+
+    client
+        .set_focus(Some(3), Some(("set1", "Cam")))
+        .await
+        .unwrap();
+    let (focus, _) = expect_request(&server, 50).await;
+    assert_eq!(focus[5..8], [1, 3, 0]);
+    assert!(focus.ends_with(b"\x01\x04\x00set1\x03\x00Cam"));
+
+    drop(client);
+    let (unregister, _) = expect_request(&server, 9).await;
+    assert_eq!(unregister[1..], 7i32.to_le_bytes());
+}
+
+#[test]
+fn rejects_truncated_messages() {
+    let mut data = entry_list_car(1);
+    data.truncate(data.len() - 1);
+    assert!(Message::decode(&data).is_err());
+    assert!(Message::decode(&entry_list_car(1)).is_ok());
+}
@@ -0,0 +1,112 @@
+use byteorder::{ByteOrder, LittleEndian};
+use simetry::assetto_corsa::remote_telemetry::{Client, Event, Subscription};
+use simetry::Moment;
+use tokio::net::UdpSocket;
+use uom::si::angular_velocity::revolution_per_minute;
+use uom::si::velocity::kilometer_per_hour;
+
+fn write_string(data: &mut [u8], value: &str) {
+    for (idx, unit) in value.encode_utf16().chain([b'%' as u16]).enumerate() {
+        LittleEndian::write_u16(&mut data[idx * 2..], unit);
+    }
+}
+
+fn handshake_response() -> Vec<u8> {
+    let mut data = vec![0u8; 408];
+    write_string(&mut data[0..], "ks_mazda_mx5_cup");
+    write_string(&mut data[100..], "Jörg");
+    LittleEndian::write_i32(&mut data[200..], 4242);
+    LittleEndian::write_i32(&mut data[204..], 1);
+    write_string(&mut data[208..], "magione");
+    data
+}
+
+fn car_info() -> Vec<u8> {
+    let mut data = vec![0u8; 328];
+    data[0] = b'a';
+    LittleEndian::write_i32(&mut data[4..], 328);
+    LittleEndian::write_f32(&mut data[8..], 123.5);
+    data[24] = 1;
+    LittleEndian::write_i32(&mut data[52..], 7);
+    LittleEndian::write_f32(&mut data[56..], 0.75);
+    LittleEndian::write_f32(&mut data[60..], 0.25);
+    LittleEndian::write_f32(&mut data[68..], 6500.0);
+    LittleEndian::write_i32(&mut data[76..], 4);
+    LittleEndian::write_f32(&mut data[324..], -12.0);
+    data
+}
+
+fn lap() -> Vec<u8> {
+    let mut data = vec![0u8; 212];
+    LittleEndian::write_i32(&mut data[0..], 3);
+    LittleEndian::write_i32(&mut data[4..], 12);
+    write_string(&mut data[8..], "Other Driver");
+    write_string(&mut data[108..], "ks_mazda_mx5_cup");
+    LittleEndian::write_i32(&mut data[208..], 71_234);
+    data
+}
+
+async fn expect_operation(server: &UdpSocket, operation_id: i32) -> std::net::SocketAddr {
+    let mut buffer = [0u8; 64];
+    let (len, origin) = server.recv_from(&mut buffer).await.unwrap();
+    assert_eq!(len, 12);
+    assert_eq!(LittleEndian::read_i32(&buffer[8..]), operation_id);
+    origin
+}
+
+#[tokio::test]
+async fn handshakes_and_receives_updates() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let uri = server.local_addr().unwrap().to_string();
+    let client = tokio::spawn(async move { Client::try_connect(&uri, Subscription::Update).await });
+
+    let origin = expect_operation(&server, 0).await;
+    server.send_to(&handshake_response(), origin).await.unwrap();
+    expect_operation(&server, 1).await;
+    let client = client.await.unwrap().unwrap();
+    assert_eq!(client.handshake().car_name, "ks_mazda_mx5_cup");
+    assert_eq!(client.handshake().driver_name, "Jörg");
+    assert_eq!(client.handshake().track_name, "magione");
+    assert_eq!(client.handshake().track_config, "");
+
+    server.send_to(&[0u8; 5], origin).await.unwrap();
+    server.send_to(&lap(), origin).await.unwrap();
+    server.send_to(&car_info(), origin).await.unwrap();
+    match client.next_event().await.unwrap() {
+        Event::Lap(lap) => {
+            assert_eq!(
+                (lap.car_identifier_number, lap.lap, lap.time),
+                (3, 12, 71_234)
+            );
+            assert_eq!(lap.driver_name, "Other Driver");
+        }
+        other => panic!("Unexpected event {other:?}"),
+    }
+    let state = client.next_sim_state().await.unwrap();
+    assert_eq!(state.car_info.lap_count, 7);
+    assert_eq!(state.car_info.car_coordinates[2], -12.0);
+    assert_eq!(state.vehicle_gear(), Some(3));
+    assert_eq!(
+        state
+            .vehicle_velocity()
+            .unwrap()
+            .get::<kilometer_per_hour>(),
+        123.5
+    );
+    assert_eq!(
+        state
+            .vehicle_engine_rotation_speed()
+            .unwrap()
+            .get::<revolution_per_minute>(),
+        6500.0
+    );
+    assert_eq!(state.is_vehicle_in_pit_lane(), Some(true));
+    assert_eq!(state.pedals().unwrap().throttle, 0.75);
+    assert_eq!(
+        state.vehicle_unique_id().as_deref(),
+        Some("ks_mazda_mx5_cup")
+    );
+
+    drop(client);
+    expect_operation(&server, 3).await;
+}
@@ -0,0 +1,142 @@
+use simetry::assetto_corsa::server_plugin::{
+    CarUpdate, Collision, Command, Connection, Endpoint, Event, LapCompleted, LeaderboardEntry,
+    SessionInfo,
+};
+use simetry::assetto_corsa::SessionType;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+
+fn session_info() -> SessionInfo {
+    SessionInfo {
+        protocol_version: 4,
+        session_index: 1,
+        current_session_index: 1,
+        session_count: 3,
+        server_name: "Ligue Été".to_string(),
+        track: "ks_nordschleife".to_string(),
+        track_config: "endurance".to_string(),
+        name: "Qualify".to_string(),
+        session_type: SessionType::Qualify,
+        time: 15,
+        laps: 0,
+        wait_time: 60,
+        ambient_temp: 24,
+        road_temp: 31,
+        weather_graphics: "3_clear".to_string(),
+        elapsed_ms: -5000,
+    }
+}
+
+#[test]
+fn decodes_wire_format() {
+    let mut chat = vec![57, 3, 2];
+    chat.extend_from_slice(&('h' as u32).to_le_bytes());
+    chat.extend_from_slice(&('é' as u32).to_le_bytes());
+    assert_eq!(
+        Event::decode(&chat).unwrap(),
+        Event::Chat {
+            car_id: 3,
+            message: "hé".to_string()
+        }
+    );
+    assert!(Event::decode(&chat[..chat.len() - 1]).is_err());
+    assert_eq!(Command::GetSessionInfo(None).encode(), [204, 0xff, 0xff]);
+    assert_eq!(Command::Kick(7).encode(), [206, 7]);
+}
+
+#[test]
+fn round_trips_events() {
+    let events = [
+        Event::NewSession(session_info()),
+        Event::NewConnection(Connection {
+            driver_name: "Jörg".to_string(),
+            driver_guid: "76561198000000000".to_string(),
+            car_id: 2,
+            car_model: "ks_porsche_911_gt3_r_2016".to_string(),
+            car_skin: "red".to_string(),
+        }),
+        Event::CarUpdate(CarUpdate {
+            car_id: 2,
+            position: [1.0, 2.0, 3.0],
+            velocity: [0.0, 0.0, 40.0],
+            gear: 4,
+            engine_rpm: 7200,
+            normalized_spline_pos: 0.5,
+        }),
+        Event::LapCompleted(LapCompleted {
+            car_id: 2,
+            lap_time: 421_337,
+            cuts: 2,
+            leaderboard: vec![LeaderboardEntry {
+                car_id: 2,
+                lap_time: 421_337,
+                laps: 3,
+                has_completed_flag: false,
+            }],
+            grip_level: 0.98,
+        }),
+        Event::Collision(Collision {
+            car_id: 2,
+            other_car_id: Some(5),
+            impact_speed: 23.5,
+            world_position: [10.0, 0.0, -4.0],
+            relative_position: [0.5, 0.0, 1.5],
+        }),
+        Event::Collision(Collision {
+            car_id: 2,
+            other_car_id: None,
+            impact_speed: 80.0,
+            world_position: [10.0, 0.0, -4.0],
+            relative_position: [0.0, 0.0, 2.0],
+        }),
+        Event::EndSession("results/2026_10_18.json".to_string()),
+    ];
+    for event in events {
+        assert_eq!(Event::decode(&event.encode()).unwrap(), event);
+    }
+}
+
+#[tokio::test]
+async fn exchanges_with_server() {
+    let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let endpoint = Endpoint::bind("127.0.0.1:0", server.local_addr().unwrap())
+        .await
+        .unwrap();
+    let plugin = endpoint.local_addr().unwrap();
+
+    server.send_to(&[0xee, 1], plugin).await.unwrap();
+    server
+        .send_to(&Event::SessionInfo(session_info()).encode(), plugin)
+        .await
+        .unwrap();
+    assert_eq!(
+        endpoint.next_event().await.unwrap(),
+        Event::SessionInfo(session_info())
+    );
+
+    endpoint
+        .send(&Command::RealtimePosInterval(Duration::from_millis(250)))
+        .await
+        .unwrap();
+    endpoint.send_chat(2, "Track limits!").await.unwrap();
+    endpoint.kick(5).await.unwrap();
+    endpoint.admin_command("/next_session").await.unwrap();
+    let mut buffer = [0u8; 1024];
+    let mut commands = Vec::new();
+    for _ in 0..4 {
+        let (len, _) = server.recv_from(&mut buffer).await.unwrap();
+        commands.push(Command::decode(&buffer[..len]).unwrap());
+    }
+    assert_eq!(
+        commands,
+        [
+            Command::RealtimePosInterval(Duration::from_millis(250)),
+            Command::SendChat {
+                car_id: 2,
+                message: "Track limits!".to_string()
+            },
+            Command::Kick(5),
+            Command::AdminCommand("/next_session".to_string()),
+        ]
+    );
+}
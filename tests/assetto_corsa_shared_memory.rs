@@ -0,0 +1,50 @@
+#![cfg(target_family = "unix")]
+
+use simetry::assetto_corsa::{Client, SharedMemoryPages};
+use simetry::SharedMemoryPath;
+use std::fs::{self, OpenOptions};
+use std::os::unix::fs::FileExt;
+use std::path::Path;
+
+fn write_page(path: &Path, offset: usize, values: &[u8]) {
+    let mut data = vec![0u8; 64 * 1024];
+    data[offset..offset + values.len()].copy_from_slice(values);
+    fs::write(path, data).unwrap();
+}
+
+/// Writes in place, since truncating a mapped file would make reading it fail.
+fn update_page(path: &Path, offset: u64, values: &[u8]) {
+    let file = OpenOptions::new().write(true).open(path).unwrap();
+    file.write_all_at(values, offset).unwrap();
+}
+
+#[tokio::test]
+async fn reads_pages_from_files() {
+    let dir = std::env::temp_dir().join(format!("simetry-ac-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let pages = SharedMemoryPages {
+        physics: SharedMemoryPath::File(dir.join("physics")),
+        graphics: SharedMemoryPath::File(dir.join("graphics")),
+        static_data: SharedMemoryPath::File(dir.join("static")),
+    };
+    let version: Vec<u8> = "1.7".encode_utf16().flat_map(u16::to_le_bytes).collect();
+    write_page(&dir.join("static"), 0, &version);
+    write_page(&dir.join("physics"), 0, &0i32.to_le_bytes());
+    // Status is live.
+    write_page(&dir.join("graphics"), 4, &2i32.to_le_bytes());
+
+    let mut client = Client::try_connect_with_pages(&pages).await.unwrap();
+    update_page(&dir.join("physics"), 0, &1i32.to_le_bytes());
+    let sim_state = client.next_sim_state().await.unwrap();
+    assert_eq!(sim_state.physics.packet_id, 1);
+
+    update_page(&dir.join("graphics"), 4, &0i32.to_le_bytes());
+    assert!(client.next_sim_state().await.is_none());
+
+    let missing = SharedMemoryPages {
+        graphics: SharedMemoryPath::File(dir.join("missing")),
+        ..pages
+    };
+    assert!(Client::try_connect_with_pages(&missing).await.is_err());
+    fs::remove_dir_all(dir).unwrap();
+}
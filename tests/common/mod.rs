@@ -0,0 +1,128 @@
+//! Fixtures shared by the integration tests.
+#![allow(dead_code)]
+
+use simetry::iracing::{Header, SimState, VarHeader, VarHeaders, VarType};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::net::UdpSocket;
+use yaml_rust::Yaml;
+
+/// Variable headers of name, type and count, laid out one after another.
+pub fn variables(variables: &[(&str, VarType, usize)]) -> VarHeaders {
+    let mut offset = 0;
+    variables
+        .iter()
+        .map(|(name, var_type, count)| {
+            let header = VarHeader {
+                var_type: *var_type,
+                offset,
+                count: *count,
+                count_as_time: false,
+                name: name.to_string(),
+                desc: String::new(),
+                unit: String::new(),
+            };
+            offset += var_type.byte_count() * count;
+            (name.to_string(), header)
+        })
+        .collect()
+}
+
+/// Builds sim states of a fixed set of variables.
+pub struct Telemetry {
+    header: Arc<Header>,
+    variables: Arc<VarHeaders>,
+}
+
+impl Telemetry {
+    pub fn new(list: &[(&str, VarType, usize)]) -> Self {
+        Self::from_variables(variables(list))
+    }
+
+    pub fn from_variables(variables: VarHeaders) -> Self {
+        let buf_len = variables
+            .values()
+            .map(|v| v.offset + v.byte_count())
+            .max()
+            .unwrap_or_default();
+        let header = Header {
+            buf_len: buf_len as i32,
+            ..Default::default()
+        };
+        Self {
+            header: Arc::new(header),
+            variables: Arc::new(variables),
+        }
+    }
+
+    pub fn variables(&self) -> &Arc<VarHeaders> {
+        &self.variables
+    }
+
+    /// Row with all variables set to zero.
+    pub fn row(&self) -> Row<'_> {
+        Row {
+            telemetry: self,
+            data: vec![0u8; self.header.buf_len as usize],
+        }
+    }
+}
+
+pub struct Row<'a> {
+    telemetry: &'a Telemetry,
+    data: Vec<u8>,
+}
+
+impl Row<'_> {
+    /// Set raw bytes of a variable, starting at its first entry.
+    pub fn set(&mut self, name: &str, bytes: &[u8]) -> &mut Self {
+        let offset = self.telemetry.variables[name].offset;
+        self.data[offset..offset + bytes.len()].copy_from_slice(bytes);
+        self
+    }
+
+    pub fn sim_state(&self, session_info: Arc<Yaml>) -> SimState {
+        SimState::new(
+            Arc::clone(&self.telemetry.header),
+            Arc::clone(&self.telemetry.variables),
+            self.data.clone(),
+            session_info,
+        )
+    }
+}
+
+/// Directory for the files of one test, removed when dropped.
+///
+/// Readers of its files have to be dropped first, as Windows keeps open files from being deleted.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("simetry_{name}_{}", std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn file(&self, name: &str) -> PathBuf {
+        self.0.join(name)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Socket bound to a free loopback port for the client under test, and a socket sending to it.
+///
+/// Handing the bound socket to the client keeps the port from being taken in between.
+pub async fn udp_pair() -> (UdpSocket, UdpSocket) {
+    let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let sender = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    sender
+        .connect(receiver.local_addr().unwrap())
+        .await
+        .unwrap();
+    (receiver, sender)
+}
@@ -0,0 +1,50 @@
+use byteorder::{ByteOrder, LittleEndian};
+use simetry::dirt_rally_2::{ExtraData, SimState};
+use simetry::Moment;
+use uom::si::angular_velocity::revolution_per_minute;
+
+fn packet(extra_data: ExtraData) -> Vec<u8> {
+    let mut data = vec![0u8; extra_data.packet_len()];
+    for (idx, value) in (0..data.len() / 4).map(|idx| (idx, idx as f32)) {
+        LittleEndian::write_f32(&mut data[idx * 4..], value);
+    }
+    LittleEndian::write_f32(&mut data[33 * 4..], 10.0);
+    data
+}
+
+#[test]
+fn detects_extra_data_by_length() {
+    for extra_data in [
+        ExtraData::Level0,
+        ExtraData::Level1,
+        ExtraData::Level2,
+        ExtraData::Level3,
+    ] {
+        let state = SimState::decode(&packet(extra_data)).unwrap();
+        assert_eq!(state.extra_data, extra_data);
+        assert_eq!(state.speed_of_engine_rpm_div_10, 37.0);
+        assert_eq!(state.vehicle_gear(), Some(-1));
+        assert_eq!(
+            state.has(ExtraData::Level1),
+            extra_data >= ExtraData::Level1
+        );
+        let in_pits = state.is_vehicle_in_pit_lane();
+        assert_eq!(in_pits, state.has(ExtraData::Level1).then_some(true));
+        let brake_temperature = if state.has(ExtraData::Level2) {
+            51.0
+        } else {
+            0.0
+        };
+        assert_eq!(state.temperature_brake_rear_left, brake_temperature);
+        let max_rpm = state
+            .vehicle_max_engine_rotation_speed()
+            .map(|rpm| rpm.get::<revolution_per_minute>());
+        if extra_data == ExtraData::Level3 {
+            assert_eq!(state.maximum_gears, 65.0);
+            assert!((max_rpm.unwrap() - 630.0).abs() < 1e-9);
+        } else {
+            assert_eq!((state.maximum_gears, max_rpm), (0.0, None));
+        }
+    }
+    assert!(SimState::decode(&[0u8; 100]).is_err());
+}
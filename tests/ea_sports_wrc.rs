@@ -0,0 +1,108 @@
+mod common;
+
+use simetry::ea_sports_wrc::{Client, Structure, Value};
+use simetry::Moment;
+use uom::si::angular_velocity::revolution_per_minute;
+
+const CHANNELS: &str = r#"{
+    "versions": {"schema": 1, "data": 3},
+    "channels": [
+        {"id": "packet_4cc", "type": "fourcc", "units": "", "description": "Four character code."},
+        {"id": "packet_uid", "type": "uint64", "units": ""},
+        {"id": "vehicle_gear_index", "type": "uint8", "units": ""},
+        {"id": "vehicle_gear_index_neutral", "type": "uint8", "units": ""},
+        {"id": "vehicle_gear_index_reverse", "type": "uint8", "units": ""},
+        {"id": "vehicle_engine_rpm_current", "type": "float32", "units": "rpm"},
+        {"id": "vehicle_engine_rpm_max", "type": "float32", "units": "rpm"},
+        {"id": "vehicle_throttle", "type": "float32", "units": ""},
+        {"id": "vehicle_brake", "type": "float32", "units": ""},
+        {"id": "stage_progress", "type": "float64", "units": ""},
+        {"id": "game_paused", "type": "boolean", "units": ""}
+    ]
+}"#;
+
+const STRUCTURE: &str = r#"{
+    "id": "custom",
+    "packets": [
+        {
+            "id": "session_start",
+            "header": {"channels": ["packet_4cc", "packet_uid"]},
+            "channels": ["vehicle_gear_index_neutral", "vehicle_gear_index_reverse", "vehicle_engine_rpm_max"]
+        },
+        {
+            "id": "session_update",
+            "header": {"channels": ["packet_4cc", "packet_uid"]},
+            "channels": [
+                "vehicle_gear_index", "vehicle_engine_rpm_current", "vehicle_throttle",
+                "vehicle_brake", "stage_progress"
+            ]
+        },
+        {
+            "id": "session_pause",
+            "header": {"channels": ["packet_4cc", "packet_uid"]},
+            "channels": ["game_paused"]
+        }
+    ]
+}"#;
+
+fn session_start() -> Vec<u8> {
+    let mut data = b"sess".to_vec();
+    data.extend(1u64.to_le_bytes());
+    data.extend([0, 9]);
+    data.extend(7500f32.to_le_bytes());
+    data
+}
+
+fn session_update(gear: u8) -> Vec<u8> {
+    let mut data = b"sesu".to_vec();
+    data.extend(2u64.to_le_bytes());
+    data.push(gear);
+    data.extend(4000f32.to_le_bytes());
+    data.extend(0.75f32.to_le_bytes());
+    data.extend(0f32.to_le_bytes());
+    data.extend(0.5f64.to_le_bytes());
+    data
+}
+
+#[test]
+fn decodes_packets() {
+    let structure = Structure::from_json(CHANNELS, STRUCTURE).unwrap();
+    assert_eq!(structure.packets[1].size(), 33);
+    let packet = structure.decode(&session_update(3)).unwrap();
+    assert_eq!(packet.id, "session_update");
+    assert_eq!(packet.channels["vehicle_gear_index"], Value::Unsigned(3));
+    assert_eq!(packet.channels["packet_4cc"], Value::FourCc("sesu".into()));
+
+    let mut pause = b"sesp".to_vec();
+    pause.extend(3u64.to_le_bytes());
+    pause.push(1);
+    let packet = structure.decode(&pause).unwrap();
+    assert_eq!(packet.channels["game_paused"], Value::Boolean(true));
+    pause[..4].copy_from_slice(b"sesr");
+    assert!(structure.decode(&pause).is_err());
+
+    let unknown = STRUCTURE.replace("game_paused", "game_unknown");
+    assert!(Structure::from_json(CHANNELS, &unknown).is_err());
+}
+
+#[tokio::test]
+async fn receives_packets() {
+    let structure = Structure::from_json(CHANNELS, STRUCTURE).unwrap();
+    let (socket, sender) = common::udp_pair().await;
+    sender.send(&session_start()).await.unwrap();
+    let mut client = Client::try_connect_with_socket(socket, structure)
+        .await
+        .unwrap();
+    sender.send(&session_update(9)).await.unwrap();
+    let state = client.next_sim_state().await.unwrap();
+    assert_eq!(state.vehicle_gear(), Some(-1));
+    assert_eq!(
+        state
+            .vehicle_max_engine_rotation_speed()
+            .unwrap()
+            .get::<revolution_per_minute>(),
+        7500.0
+    );
+    assert_eq!(state.stage_progress(), Some(0.5));
+    assert_eq!(state.pedals().unwrap().throttle, 0.75);
+}
@@ -0,0 +1,295 @@
+mod common;
+
+use simetry::f1::{Client, FiaFlag, LiveryColour, Packet, PacketData, PacketFormat, MAX_CARS};
+use simetry::Moment;
+use uom::si::angular_velocity::revolution_per_minute;
+use uom::si::velocity::kilometer_per_hour;
+
+const PLAYER: usize = 1;
+
+fn header(format: u16, packet_id: u8) -> Vec<u8> {
+    let mut data = format.to_le_bytes().to_vec();
+    data.extend_from_slice(&[24, 1, 5, 1, packet_id]);
+    data.extend_from_slice(&42u64.to_le_bytes());
+    data.extend_from_slice(&12.5f32.to_le_bytes());
+    data.extend_from_slice(&[0; 8]);
+    data.extend_from_slice(&[PLAYER as u8, 255]);
+    assert_eq!(data.len(), 29);
+    data
+}
+
+fn packet(format: u16, packet_id: u8, car: &[u8], trailer: &[u8]) -> Vec<u8> {
+    packet_with_prefix(format, packet_id, &[], car, trailer)
+}
+
+/// Packet with `car` as the entry of the player, after `prefix`.
+fn packet_with_prefix(
+    format: u16,
+    packet_id: u8,
+    prefix: &[u8],
+    car: &[u8],
+    trailer: &[u8],
+) -> Vec<u8> {
+    let mut data = header(format, packet_id);
+    data.extend_from_slice(prefix);
+    for idx in 0..MAX_CARS {
+        if idx == PLAYER {
+            data.extend_from_slice(car);
+        } else {
+            data.resize(data.len() + car.len(), 0);
+        }
+    }
+    data.extend_from_slice(trailer);
+    data
+}
+
+fn lap_data(format: u16) -> Vec<u8> {
+    let mut car = 83_456u32.to_le_bytes().to_vec();
+    car.extend_from_slice(&1_000u32.to_le_bytes());
+    car.extend_from_slice(&[0x30, 0x75, 0, 0x10, 0x27, 1]);
+    if format >= 2024 {
+        car.extend_from_slice(&[0xe8, 0x03, 0, 0x0a, 0, 1]);
+    } else {
+        car.extend_from_slice(&[0xe8, 0x03, 0x6a, 0xea]);
+    }
+    car.extend_from_slice(&[0; 12]);
+    car.extend_from_slice(&[4, 7, 1, 1, 2, 0, 0, 0, 0, 0, 0, 6, 4, 2, 1]);
+    car.extend_from_slice(&[0; 5]);
+    if format >= 2024 {
+        car.extend_from_slice(&312.5f32.to_le_bytes());
+        car.push(6);
+    }
+    packet(format, 2, &car, &[255, 255])
+}
+
+fn car_telemetry() -> Vec<u8> {
+    let mut car = 287u16.to_le_bytes().to_vec();
+    for value in [0.9f32, -0.1, 0.0] {
+        car.extend_from_slice(&value.to_le_bytes());
+    }
+    car.extend_from_slice(&[50, 7]);
+    car.extend_from_slice(&11_500u16.to_le_bytes());
+    car.extend_from_slice(&[1, 80]);
+    car.extend_from_slice(&0x7fu16.to_le_bytes());
+    car.extend_from_slice(&[0; 8]);
+    car.extend_from_slice(&[95, 96, 97, 98, 100, 101, 102, 103]);
+    car.extend_from_slice(&[0; 22]);
+    assert_eq!(car.len(), 60);
+    packet(2024, 6, &car, &[255, 255, 8])
+}
+
+fn car_status() -> Vec<u8> {
+    let mut car = vec![0, 0, 1, 55, 1];
+    car.extend_from_slice(&[0; 12]);
+    car.extend_from_slice(&13_000u16.to_le_bytes());
+    car.extend_from_slice(&4_000u16.to_le_bytes());
+    car.extend_from_slice(&[8, 1, 0, 0, 16, 16, 3, 2]);
+    car.extend_from_slice(&[0; 26]);
+    assert_eq!(car.len(), 55);
+    packet(2024, 7, &car, &[])
+}
+
+#[test]
+fn decodes_lap_data_of_all_formats() {
+    let data_2023 = lap_data(2023);
+    assert_eq!(data_2023.len(), 1131);
+    let data_2024 = lap_data(2024);
+    assert_eq!(data_2024.len(), 1285);
+
+    for data in [data_2023, data_2024, lap_data(2025)] {
+        let packet = Packet::decode(&data).unwrap();
+        let PacketData::LapData(lap_data) = packet.data else {
+            panic!("Unexpected packet {packet:?}");
+        };
+        let lap_data = &lap_data[PLAYER];
+        assert_eq!(lap_data.last_lap_time, 83_456);
+        assert_eq!(lap_data.sector1_time, 30_000);
+        assert_eq!(lap_data.sector2_time, 70_000);
+        assert_eq!(lap_data.delta_to_car_in_front, 1_000);
+        assert_eq!(lap_data.delta_to_race_leader, 60_010);
+        assert_eq!(lap_data.car_position, 4);
+        assert_eq!(lap_data.grid_position, 6);
+        assert_eq!(lap_data.result_status, 2);
+        match packet.header.packet_format {
+            PacketFormat::Year2023 => assert_eq!(lap_data.speed_trap_fastest_speed, None),
+            PacketFormat::Year2024 | PacketFormat::Year2025 => {
+                assert_eq!(lap_data.speed_trap_fastest_speed, Some(312.5));
+                assert_eq!(lap_data.speed_trap_fastest_lap, Some(6));
+            }
+        }
+    }
+    assert!(Packet::decode(&lap_data(2024)[..1200]).is_err());
+    assert!(Packet::decode(&lap_data(2022)).is_err());
+}
+
+fn car_damage(format: u16) -> Vec<u8> {
+    let mut car = Vec::new();
+    for value in [10.5f32, 11.5, 12.5, 13.5] {
+        car.extend_from_slice(&value.to_le_bytes());
+    }
+    car.extend_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+    if format >= 2025 {
+        car.extend_from_slice(&[9, 10, 11, 12]);
+    }
+    car.extend_from_slice(&[20, 21, 22, 0, 0, 0, 1, 0, 30, 31]);
+    car.extend_from_slice(&[0; 8]);
+    packet(format, 10, &car, &[])
+}
+
+fn final_classification(format: u16) -> Vec<u8> {
+    let mut car = vec![3, 50, 5, 15, 1, 3];
+    if format >= 2025 {
+        car.push(2);
+    }
+    car.extend_from_slice(&91_234u32.to_le_bytes());
+    car.extend_from_slice(&5_400.5f64.to_le_bytes());
+    car.extend_from_slice(&[5, 1, 2]);
+    for stint in [[16, 17], [16, 17], [20, 50]] {
+        let mut stint = stint.to_vec();
+        stint.resize(8, 0);
+        car.extend(stint);
+    }
+    packet_with_prefix(format, 8, &[MAX_CARS as u8], &car, &[])
+}
+
+#[test]
+fn decodes_damage_and_classification_of_all_formats() {
+    assert_eq!(car_damage(2024).len(), 953);
+    assert_eq!(final_classification(2024).len(), 1020);
+
+    for (format, blisters, result_reason) in [
+        (2023, None, None),
+        (2024, None, None),
+        (2025, Some([9, 10, 11, 12]), Some(2)),
+    ] {
+        let packet = Packet::decode(&car_damage(format)).unwrap();
+        let PacketData::CarDamage(damage) = packet.data else {
+            panic!("Unexpected packet {packet:?}");
+        };
+        let damage = &damage[PLAYER];
+        assert_eq!(damage.tyres_wear, [10.5, 11.5, 12.5, 13.5]);
+        assert_eq!(damage.tyre_blisters, blisters);
+        assert_eq!(damage.rear_wing_damage, 22);
+        assert!(damage.drs_fault);
+        assert_eq!(damage.engine_damage, 31);
+
+        let packet = Packet::decode(&final_classification(format)).unwrap();
+        let PacketData::FinalClassification(classification) = packet.data else {
+            panic!("Unexpected packet {packet:?}");
+        };
+        let result = &classification[PLAYER];
+        assert_eq!(result.result_reason, result_reason);
+        assert_eq!(result.best_lap_time, 91_234);
+        assert_eq!(result.total_race_time, 5_400.5);
+        assert_eq!(result.num_penalties, 1);
+        assert_eq!(result.tyre_stints.len(), 2);
+        assert_eq!(result.tyre_stints[1].end_lap, 50);
+    }
+}
+
+#[test]
+fn decodes_additions_of_2025() {
+    let mut car = vec![0, 9, 1, 3, 0, 44, 10];
+    let mut name = b"Player".to_vec();
+    name.resize(32, 0);
+    car.extend(name);
+    car.extend_from_slice(&[1, 1]);
+    car.extend_from_slice(&3_000u16.to_le_bytes());
+    car.extend_from_slice(&[6, 2, 255, 0, 0, 0, 0, 255]);
+    car.extend_from_slice(&[0; 6]);
+    assert_eq!(car.len(), 57);
+    let packet = Packet::decode(&packet_with_prefix(2025, 4, &[2], &car, &[])).unwrap();
+    let PacketData::Participants(participants) = packet.data else {
+        panic!("Unexpected packet {packet:?}");
+    };
+    assert_eq!(participants.len(), 2);
+    let player = &participants[PLAYER];
+    assert_eq!(
+        (player.name.as_str(), player.tech_level),
+        ("Player", Some(3_000))
+    );
+    assert_eq!(
+        player.livery_colours,
+        Some(vec![
+            LiveryColour {
+                red: 255,
+                green: 0,
+                blue: 0
+            },
+            LiveryColour {
+                red: 0,
+                green: 0,
+                blue: 255
+            },
+        ])
+    );
+
+    let mut data = header(2025, 13);
+    for value in 0..61 {
+        data.extend_from_slice(&(value as f32).to_le_bytes());
+    }
+    let packet = Packet::decode(&data).unwrap();
+    let PacketData::MotionEx(motion) = packet.data else {
+        panic!("Unexpected packet {packet:?}");
+    };
+    assert_eq!(motion.wheel_vert_force, [43.0, 44.0, 45.0, 46.0]);
+    assert_eq!(motion.chassis_yaw, Some(51.0));
+    assert_eq!(motion.chassis_pitch, Some(52.0));
+    assert_eq!(motion.wheel_camber_gain, Some([57.0, 58.0, 59.0, 60.0]));
+
+    let mut data = header(2025, 15);
+    data.extend_from_slice(&[2, 0]);
+    for lap in 0..50 {
+        let mut positions = [0u8; MAX_CARS];
+        positions[PLAYER] = 3 - lap.min(1);
+        data.extend_from_slice(&positions);
+    }
+    let packet = Packet::decode(&data).unwrap();
+    let PacketData::LapPositions(lap_positions) = packet.data else {
+        panic!("Unexpected packet {packet:?}");
+    };
+    assert_eq!(lap_positions.positions.len(), 2);
+    assert_eq!(lap_positions.positions[1][PLAYER], 2);
+}
+
+#[tokio::test]
+async fn combines_packets_of_player_car() {
+    let (socket, sender) = common::udp_pair().await;
+    sender.send(&car_status()).await.unwrap();
+    let mut client = Client::try_connect_with_socket(socket).await.unwrap();
+    sender.send(&lap_data(2024)).await.unwrap();
+    sender.send(&car_telemetry()).await.unwrap();
+
+    let state = client.next_sim_state().await.unwrap();
+    assert_eq!(
+        state.status.as_ref().unwrap().vehicle_fia_flags,
+        FiaFlag::Blue
+    );
+    assert_eq!(
+        state.telemetry.tyres_inner_temperature,
+        [100, 101, 102, 103]
+    );
+    assert_eq!(state.vehicle_gear(), Some(7));
+    assert_eq!(
+        state
+            .vehicle_velocity()
+            .unwrap()
+            .get::<kilometer_per_hour>(),
+        287.0
+    );
+    assert_eq!(
+        state
+            .vehicle_max_engine_rotation_speed()
+            .unwrap()
+            .get::<revolution_per_minute>(),
+        13_000.0
+    );
+    assert_eq!(state.rev_lights(), Some(0.8));
+    assert_eq!(state.is_drs_engaged(), Some(true));
+    assert_eq!(state.is_pit_limiter_engaged(), Some(true));
+    assert_eq!(state.is_vehicle_in_pit_lane(), Some(true));
+    let flags = state.flags().unwrap();
+    assert!(flags.blue && !flags.yellow && !flags.checkered);
+    let pedals = state.pedals().unwrap();
+    assert_eq!((pedals.throttle as f32, pedals.clutch), (0.9, 0.5));
+}
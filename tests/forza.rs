@@ -0,0 +1,98 @@
+mod common;
+
+use byteorder::{ByteOrder, LittleEndian};
+use simetry::forza::{Client, PacketVariant, SimState};
+use simetry::Moment;
+use uom::si::thermodynamic_temperature::degree_celsius;
+use uom::si::velocity::meter_per_second;
+
+fn packet(variant: PacketVariant) -> Vec<u8> {
+    let (len, dash) = match variant {
+        PacketVariant::Sled => (232, None),
+        PacketVariant::Dash => (311, Some(232)),
+        PacketVariant::Horizon => (324, Some(244)),
+        PacketVariant::Motorsport2023 => (331, Some(232)),
+    };
+    let mut data = vec![0u8; len];
+    LittleEndian::write_i32(&mut data[0..], 1);
+    LittleEndian::write_f32(&mut data[8..], 8000.0);
+    LittleEndian::write_f32(&mut data[16..], 5500.0);
+    LittleEndian::write_f32(&mut data[40..], 30.0);
+    LittleEndian::write_i32(&mut data[120..], 1);
+    LittleEndian::write_i32(&mut data[212..], 2352);
+    LittleEndian::write_i32(&mut data[228..], 6);
+    if let Some(dash) = dash {
+        LittleEndian::write_f32(&mut data[dash + 12..], 30.5);
+        LittleEndian::write_f32(&mut data[dash + 24..], 212.0);
+        LittleEndian::write_u16(&mut data[dash + 68..], 3);
+        data[dash + 71] = 255;
+        data[dash + 72] = 51;
+        data[dash + 75] = 4;
+        data[dash + 76] = (-127i8) as u8;
+        if variant == PacketVariant::Motorsport2023 {
+            LittleEndian::write_f32(&mut data[dash + 83..], 0.25);
+            LittleEndian::write_i32(&mut data[dash + 95..], 860);
+        }
+    }
+    data
+}
+
+#[test]
+fn detects_variant_by_length() {
+    for variant in [
+        PacketVariant::Sled,
+        PacketVariant::Dash,
+        PacketVariant::Horizon,
+        PacketVariant::Motorsport2023,
+    ] {
+        let state = SimState::decode(&packet(variant)).unwrap();
+        assert_eq!(state.variant, variant);
+        assert!(state.sled.is_race_on);
+        assert_eq!(state.sled.velocity[2], 30.0);
+        assert_eq!(
+            state.sled.wheel_on_rumble_strip,
+            [false, true, false, false]
+        );
+        assert_eq!(state.sled.num_cylinders, 6);
+        assert_eq!(state.vehicle_model_id().as_deref(), Some("2352"));
+        if variant == PacketVariant::Sled {
+            assert!(state.dash.is_none());
+            assert_eq!(state.vehicle_gear(), None);
+            continue;
+        }
+        let dash = state.dash.as_ref().unwrap();
+        assert_eq!((dash.lap_number, dash.steer), (3, -127));
+        if variant == PacketVariant::Motorsport2023 {
+            assert_eq!(dash.tire_wear, Some([0.0, 0.25, 0.0, 0.0]));
+            assert_eq!(dash.track_ordinal, Some(860));
+        } else {
+            assert_eq!((dash.tire_wear, dash.track_ordinal), (None, None));
+        }
+        assert_eq!(state.vehicle_gear(), Some(4));
+        assert_eq!(
+            state.vehicle_velocity().unwrap().get::<meter_per_second>(),
+            30.5
+        );
+        let tire_temp = state.tire_temperatures().unwrap()[0].get::<degree_celsius>();
+        assert!((tire_temp - 100.0).abs() < 1e-9);
+        let pedals = state.pedals().unwrap();
+        assert_eq!((pedals.throttle, pedals.brake), (1.0, 0.2));
+    }
+    assert!(SimState::decode(&[0u8; 300]).is_err());
+}
+
+#[tokio::test]
+async fn receives_packets() {
+    let (socket, sender) = common::udp_pair().await;
+    sender.send(&packet(PacketVariant::Sled)).await.unwrap();
+    let client = Client::try_connect_with_socket(socket).await.unwrap();
+    sender.send(&[0u8; 12]).await.unwrap();
+    sender.send(&packet(PacketVariant::Horizon)).await.unwrap();
+    loop {
+        let state = client.next_sim_state().await.unwrap();
+        if state.variant == PacketVariant::Horizon {
+            assert_eq!(state.vehicle_gear(), Some(4));
+            break;
+        }
+    }
+}
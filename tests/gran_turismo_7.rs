@@ -0,0 +1,104 @@
+use byteorder::{ByteOrder, LittleEndian};
+use simetry::gran_turismo_7::{decrypt, encrypt, flags, Client, SimState};
+use simetry::Moment;
+use tokio::net::UdpSocket;
+use uom::si::angular_velocity::revolution_per_minute;
+use uom::si::velocity::meter_per_second;
+
+fn plain_packet() -> Vec<u8> {
+    let mut data = vec![0u8; 296];
+    LittleEndian::write_u32(&mut data[0..], 0x47375330);
+    LittleEndian::write_f32(&mut data[0x3C..], 6200.0);
+    LittleEndian::write_f32(&mut data[0x4C..], 41.5);
+    LittleEndian::write_f32(&mut data[0x60..], 82.0);
+    LittleEndian::write_i16(&mut data[0x74..], 2);
+    LittleEndian::write_u16(&mut data[0x88..], 7000);
+    LittleEndian::write_u16(&mut data[0x8A..], 7600);
+    LittleEndian::write_u16(&mut data[0x8E..], flags::CAR_ON_TRACK | flags::IN_GEAR);
+    data[0x90] = 0x43;
+    data[0x91] = 255;
+    LittleEndian::write_f32(&mut data[0xF4..], 0.5);
+    LittleEndian::write_f32(&mut data[0x120..], 3.1);
+    LittleEndian::write_i32(&mut data[0x124..], 3349);
+    data
+}
+
+#[test]
+fn decrypts_and_decodes() {
+    let packet = encrypt(&plain_packet(), 0x1234_5678);
+    assert_ne!(packet[..4], plain_packet()[..4]);
+    assert_eq!(LittleEndian::read_u32(&packet[0x40..]), 0x1234_5678);
+    let state = SimState::decode(&decrypt(&packet).unwrap()).unwrap();
+    assert_eq!(state.lap_count, 2);
+    assert_eq!(state.tire_temperature[0], 82.0);
+    assert_eq!((state.current_gear, state.suggested_gear), (3, 4));
+    assert_eq!(state.gear_ratios[7], 3.1);
+    assert!(state.has_flag(flags::CAR_ON_TRACK));
+    assert_eq!(state.vehicle_gear(), Some(3));
+    assert_eq!(
+        state.vehicle_velocity().unwrap().get::<meter_per_second>(),
+        41.5
+    );
+    assert_eq!(
+        state.shift_point().unwrap().get::<revolution_per_minute>(),
+        7000.0
+    );
+    assert_eq!(state.vehicle_model_id().as_deref(), Some("3349"));
+    let pedals = state.pedals().unwrap();
+    assert_eq!((pedals.throttle, pedals.clutch), (1.0, 0.5));
+
+    let mut tampered = packet.clone();
+    tampered[0x40] ^= 1;
+    assert!(decrypt(&tampered).is_err());
+    assert!(decrypt(&packet[..200]).is_err());
+}
+
+/// Packet encrypted by a Salsa20 implementation independent of this crate, with the values set
+/// below, so that a mistake shared by `encrypt` and `decrypt` cannot go unnoticed.
+const REFERENCE_PACKET: &[u8] = include_bytes!("fixtures/gran_turismo_7.bin");
+
+#[test]
+fn decodes_reference_packet() {
+    let state = SimState::decode(&decrypt(REFERENCE_PACKET).unwrap()).unwrap();
+    assert_eq!(state.engine_rpm, 6843.5);
+    assert_eq!(state.speed, 37.75);
+    assert_eq!(state.car_code, 3349);
+    assert_eq!(state.packet_id, 48213);
+    assert_eq!((state.lap_count, state.laps_in_race), (3, 5));
+    assert_eq!((state.race_position, state.total_positions), (2, 16));
+    assert_eq!(state.position, [-312.25, 4.5, 128.75]);
+    assert_eq!(state.tire_temperature, [86.0, 87.5, 91.0, 90.5]);
+    assert_eq!((state.current_gear, state.suggested_gear), (4, 5));
+    assert!(state.has_flag(flags::ASM_ACTIVE));
+    assert_eq!(
+        encrypt(&decrypt(REFERENCE_PACKET).unwrap(), 0x5B3A_21C7),
+        REFERENCE_PACKET
+    );
+}
+
+#[tokio::test]
+async fn sends_heartbeat_and_receives_packets() {
+    let console = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let console_uri = console.local_addr().unwrap().to_string();
+    let client =
+        tokio::spawn(async move { Client::try_connect_from("127.0.0.1:0", &console_uri).await });
+
+    let mut buffer = [0u8; 16];
+    let (len, origin) = console.recv_from(&mut buffer).await.unwrap();
+    assert_eq!(&buffer[..len], b"A");
+    console.send_to(&[0u8; 296], origin).await.unwrap();
+    let mut packet = plain_packet();
+    LittleEndian::write_i32(&mut packet[0x70..], 1);
+    console
+        .send_to(&encrypt(&packet, 99), origin)
+        .await
+        .unwrap();
+    let mut client = client.await.unwrap().unwrap();
+
+    LittleEndian::write_i32(&mut packet[0x70..], 2);
+    console
+        .send_to(&encrypt(&packet, 100), origin)
+        .await
+        .unwrap();
+    assert_eq!(client.next_sim_state().await.unwrap().packet_id, 2);
+}
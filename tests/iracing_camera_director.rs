@@ -0,0 +1,201 @@
+mod common;
+
+use common::{Telemetry, TempDir};
+use simetry::iracing::commands::{BroadcastMessage, RecordingSink};
+use simetry::iracing::{
+    CameraDirector, DirectorConfig, DiskClient, DiskWriter, ShotReason, SimState, VarType,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use yaml_rust::{Yaml, YamlLoader};
+
+const CAR_COUNT: usize = 4;
+
+const VARIABLES: [(&str, VarType, usize); 6] = [
+    ("SessionTime", VarType::Double, 1),
+    ("CamGroupNumber", VarType::Int, 1),
+    ("CarIdxPosition", VarType::Int, CAR_COUNT),
+    ("CarIdxLap", VarType::Int, CAR_COUNT),
+    ("CarIdxLapDistPct", VarType::Float, CAR_COUNT),
+    ("CarIdxTrackSurface", VarType::Int, CAR_COUNT),
+];
+
+/// Position, total distance in laps and whether the car is off track.
+type Car = (i32, f32, bool);
+
+/// Builds sim states of a race between cars 0 to 3, where car 3 is the pace car.
+struct Race {
+    telemetry: Telemetry,
+}
+
+impl Race {
+    fn new() -> Self {
+        Self {
+            telemetry: Telemetry::new(&VARIABLES),
+        }
+    }
+
+    fn session_info(incidents: [i32; CAR_COUNT]) -> Yaml {
+        let drivers = (0..CAR_COUNT)
+            .map(|idx| {
+                format!(
+                    "  - CarIdx: {idx}\n    CarNumberRaw: {}\n    CarIsPaceCar: {}\n    IsSpectator: 0\n    CurDriverIncidentCount: {}\n",
+                    idx + 10,
+                    (idx == 3) as i32,
+                    incidents[idx],
+                )
+            })
+            .collect::<String>();
+        let cameras = "CameraInfo:\n Groups:\n  - GroupNum: 1\n    GroupName: Nose\n  - GroupNum: 10\n    GroupName: TV1\n  - GroupNum: 11\n    GroupName: TV2\n  - GroupNum: 15\n    GroupName: Chase\n";
+        YamlLoader::load_from_str(&format!("{cameras}DriverInfo:\n Drivers:\n{drivers}"))
+            .unwrap()
+            .swap_remove(0)
+    }
+
+    fn state(
+        &self,
+        session_time: f64,
+        cars: [Car; CAR_COUNT],
+        incidents: [i32; CAR_COUNT],
+    ) -> SimState {
+        let per_car = |f: fn(&Car) -> [u8; 4]| cars.iter().flat_map(f).collect::<Vec<_>>();
+        self.telemetry
+            .row()
+            .set("SessionTime", &session_time.to_le_bytes())
+            .set("CamGroupNumber", &1i32.to_le_bytes())
+            .set("CarIdxPosition", &per_car(|v| v.0.to_le_bytes()))
+            .set(
+                "CarIdxLap",
+                &per_car(|v| (v.1.floor() as i32).to_le_bytes()),
+            )
+            .set("CarIdxLapDistPct", &per_car(|v| v.1.fract().to_le_bytes()))
+            .set(
+                "CarIdxTrackSurface",
+                &per_car(|v| (if v.2 { 0i32 } else { 3 }).to_le_bytes()),
+            )
+            .sim_state(Arc::new(Self::session_info(incidents)))
+    }
+}
+
+fn director() -> (CameraDirector, Arc<RecordingSink>) {
+    let sink = Arc::new(RecordingSink::new());
+    let config = DirectorConfig {
+        min_shot_duration: Duration::from_secs(5),
+        ..Default::default()
+    };
+    (CameraDirector::with_sink(config, sink.clone()), sink)
+}
+
+fn switched_to(sink: &RecordingSink) -> Vec<(u16, i64)> {
+    sink.take()
+        .into_iter()
+        .map(|BroadcastMessage { code, var1, var2 }| {
+            assert_eq!(code, 1);
+            (var1, var2 & 0xffff)
+        })
+        .collect()
+}
+
+#[test]
+fn follows_leader_then_battle() {
+    let race = Race::new();
+    let (mut director, sink) = director();
+    let spread = |t: f32| {
+        [
+            (1, 2.5 + t, false),
+            (2, 2.2 + t, false),
+            (3, 2.0 + t, false),
+            (0, 2.9 + t, false),
+        ]
+    };
+
+    let shot = director
+        .update(&race.state(0.0, spread(0.0), [0; 4]))
+        .unwrap();
+    assert_eq!((shot.car_idx, shot.reason), (0, ShotReason::Leader));
+    assert_eq!(switched_to(&sink), vec![(10, 10)]);
+
+    // Car 2 closes up on car 1, but the shot of the leader is kept for its minimum duration.
+    let battle = |t: f32| {
+        [
+            (1, 2.5 + t, false),
+            (2, 2.2 + t, false),
+            (3, 2.195 + t, false),
+            (0, 2.9 + t, false),
+        ]
+    };
+    assert_eq!(
+        director.update(&race.state(2.0, battle(0.01), [0; 4])),
+        None
+    );
+    let shot = director
+        .update(&race.state(6.0, battle(0.02), [0; 4]))
+        .unwrap();
+    assert_eq!(
+        (shot.car_idx, shot.reason),
+        (2, ShotReason::Battle { car_ahead_idx: 1 })
+    );
+    assert_eq!(switched_to(&sink), vec![(12, 11)]);
+    assert_eq!(director.current_shot(), Some(&shot));
+}
+
+#[test]
+fn incident_interrupts_shot() {
+    let race = Race::new();
+    let (mut director, sink) = director();
+    let cars = [
+        (1, 3.5, false),
+        (2, 3.2, false),
+        (3, 3.0, false),
+        (0, 3.9, false),
+    ];
+    director.update(&race.state(0.0, cars, [0; 4])).unwrap();
+    sink.take();
+
+    let shot = director
+        .update(&race.state(1.0, cars, [0, 0, 4, 0]))
+        .unwrap();
+    assert_eq!((shot.car_idx, shot.reason), (2, ShotReason::Incident));
+    assert_eq!(switched_to(&sink), vec![(12, 15)]);
+
+    // Once the incident is old, the leader is shown again and the pace car off track is ignored.
+    let mut off_track = cars;
+    off_track[3].2 = true;
+    let shot = director
+        .update(&race.state(20.0, off_track, [0, 0, 4, 0]))
+        .unwrap();
+    assert_eq!((shot.car_idx, shot.reason), (0, ShotReason::Leader));
+}
+
+#[tokio::test]
+async fn directs_recorded_race() {
+    let race = Race::new();
+    let dir = TempDir::new("director");
+    let path = dir.file("race.ibt");
+    let session_info = Race::session_info([0; 4]);
+    let mut writer =
+        DiskWriter::create(&path, race.telemetry.variables(), &session_info, 60).unwrap();
+    // Car 2 closes up on car 1 while car 0 leads.
+    for tick in 0..=600 {
+        let t = tick as f32 / 600.0;
+        let cars = [
+            (1, 2.5 + t * 0.1, false),
+            (2, 2.2 + t * 0.1, false),
+            (3, 2.1 + t * 0.2, false),
+            (0, 2.9 + t * 0.1, false),
+        ];
+        writer
+            .write_sim_state(&race.state(tick as f64 / 60.0, cars, [0; 4]))
+            .unwrap();
+    }
+    writer.finish().unwrap();
+
+    let (mut director, sink) = director();
+    let mut client = DiskClient::open(&path).unwrap();
+    director.run(&mut client).await;
+    assert_eq!(switched_to(&sink), vec![(10, 10), (12, 11)]);
+    assert_eq!(
+        director.current_shot().map(|v| v.reason),
+        Some(ShotReason::Battle { car_ahead_idx: 1 })
+    );
+}
@@ -0,0 +1,96 @@
+use anyhow::{bail, Result};
+use simetry::iracing::commands::{
+    self, BroadcastMessage, CommandReceiver, CommandSink, NetworkSink, RecordingSink,
+};
+use std::sync::Arc;
+
+struct UnreachableSink;
+
+impl CommandSink for UnreachableSink {
+    fn send(&self, _message: BroadcastMessage) -> Result<()> {
+        bail!("Receiver is unreachable")
+    }
+}
+
+#[test]
+fn commands_are_recorded_by_scoped_sink() {
+    let sink = Arc::new(RecordingSink::new());
+    commands::with_sink(sink.clone(), || {
+        commands::pit::fuel(31);
+        commands::camera::switch_to_car_number(12, 3, 1);
+        commands::force_feedback::set(2.5);
+        commands::replay::fast_rewind(4);
+    });
+    assert_eq!(
+        sink.take(),
+        vec![
+            BroadcastMessage {
+                code: 9,
+                var1: 2,
+                var2: 31,
+            },
+            BroadcastMessage {
+                code: 1,
+                var1: 12,
+                var2: 3 | (1 << 16),
+            },
+            BroadcastMessage {
+                code: 11,
+                var1: 0,
+                var2: 5 << 15,
+            },
+            BroadcastMessage {
+                code: 3,
+                var1: (-4i16) as u16,
+                var2: 0,
+            },
+        ],
+    );
+    assert!(sink.messages().is_empty());
+}
+
+#[test]
+fn scoped_sinks_nest_and_restore() {
+    let outer = Arc::new(RecordingSink::new());
+    let inner = Arc::new(RecordingSink::new());
+    commands::with_sink(outer.clone(), || {
+        commands::with_sink(inner.clone(), commands::telemetry::start);
+        commands::telemetry::stop();
+    });
+    assert_eq!(inner.messages().len(), 1);
+    assert_eq!(outer.messages().len(), 1);
+    assert_eq!(outer.messages()[0].var1, 0);
+}
+
+#[test]
+fn scoped_sink_reports_failed_commands() {
+    let sink = Arc::new(RecordingSink::new());
+    assert_eq!(
+        commands::try_with_sink(sink.clone(), || {
+            commands::pit::fuel(31);
+            7
+        })
+        .unwrap(),
+        7
+    );
+    let result = commands::try_with_sink(Arc::new(UnreachableSink), || {
+        commands::pit::fuel(31);
+        commands::with_sink(sink.clone(), commands::pit::clear_fuel);
+    });
+    assert!(result.is_err());
+    assert_eq!(sink.take().len(), 2);
+}
+
+#[tokio::test]
+async fn network_sink_reaches_receiver() {
+    let receiver = CommandReceiver::bind("127.0.0.1:0").await.unwrap();
+    let sink = NetworkSink::connect(receiver.local_addr().unwrap()).unwrap();
+    let message = BroadcastMessage {
+        code: 4,
+        var1: 1,
+        var2: -120,
+    };
+    sink.send(message).unwrap();
+    let (received, _origin) = receiver.recv().await.unwrap();
+    assert_eq!(received, message);
+}
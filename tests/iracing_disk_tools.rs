@@ -0,0 +1,148 @@
+mod common;
+
+use common::{Telemetry, TempDir};
+use simetry::iracing::disk_tools::{merge_files, split_file, trim_file, Segment};
+use simetry::iracing::{DiskClient, DiskWriter, VarType};
+use std::path::Path;
+use std::sync::Arc;
+use yaml_rust::YamlLoader;
+
+const VARIABLES: [(&str, VarType, usize); 5] = [
+    ("SessionNum", VarType::Int, 1),
+    ("SessionTime", VarType::Double, 1),
+    ("Lap", VarType::Int, 1),
+    ("OnPitRoad", VarType::Bool, 1),
+    ("Speed", VarType::Float, 1),
+];
+
+/// Writes rows of session time, lap and whether the car is on pit road.
+///
+/// The session info has results of the session after four laps.
+fn write_file(path: &Path, driver: &str, session_num: i32, rows: &[(f64, i32, bool)]) {
+    let telemetry = Telemetry::new(&VARIABLES);
+    let session_info = YamlLoader::load_from_str(&format!(
+        "DriverInfo:\n DriverUserName: {driver}\nSessionInfo:\n Sessions:\n - SessionNum: {session_num}\n   ResultsLapsComplete: 4\n   ResultsPositions:\n   - CarIdx: 0\n     Lap: 4\n     LapsComplete: 4\n     Time: 40.0000\n     LastTime: 10.0000\n     FastestLap: 3\n     FastestTime: 9.5000\n"
+    ))
+    .unwrap()
+    .swap_remove(0);
+    let mut writer = DiskWriter::create(path, telemetry.variables(), &session_info, 60).unwrap();
+    let session_info = Arc::new(session_info);
+    for (session_time, lap, on_pit_road) in rows {
+        let state = telemetry
+            .row()
+            .set("SessionNum", &session_num.to_le_bytes())
+            .set("SessionTime", &session_time.to_le_bytes())
+            .set("Lap", &lap.to_le_bytes())
+            .set("OnPitRoad", &[*on_pit_road as u8])
+            .set("Speed", &(*lap as f32 * 10.0).to_le_bytes())
+            .sim_state(Arc::clone(&session_info));
+        writer.write_sim_state(&state).unwrap();
+    }
+    writer.finish().unwrap();
+}
+
+fn read_rows(path: &Path) -> Vec<(f64, i32, Option<f32>)> {
+    let mut client = DiskClient::open(path).unwrap();
+    std::iter::from_fn(|| client.next_sim_state())
+        .map(|state| {
+            (
+                state.read_name("SessionTime").unwrap(),
+                state.read_name("Lap").unwrap(),
+                state.read_name("Speed"),
+            )
+        })
+        .collect()
+}
+
+/// Two stints of two laps each, with a pit stop in the second lap.
+fn stints() -> Vec<(f64, i32, bool)> {
+    (0..40)
+        .map(|tick| (tick as f64, tick / 10, (15..20).contains(&tick)))
+        .collect()
+}
+
+#[test]
+fn splits_by_lap_and_stint() {
+    let dir = TempDir::new("split");
+    let input = dir.file("race.ibt");
+    write_file(&input, "Driver", 0, &stints());
+
+    let laps = split_file(&input, Segment::Lap, &[], |idx| {
+        dir.file(&format!("lap{idx}.ibt"))
+    })
+    .unwrap();
+    assert_eq!(laps.len(), 4);
+    let client = DiskClient::open(&laps[2]).unwrap();
+    assert_eq!(client.sub_header().session_record_count, 10);
+    assert_eq!(client.sub_header().session_lap_count, 1);
+    assert_eq!(client.sub_header().session_start_time, 20.0);
+    let session = &client.session_info()["SessionInfo"]["Sessions"][0];
+    assert_eq!(session["ResultsLapsComplete"].as_i64(), Some(1));
+
+    let stints = split_file(&input, Segment::Stint, &["Speed"], |idx| {
+        dir.file(&format!("stint{idx}.ibt"))
+    })
+    .unwrap();
+    assert_eq!(stints.len(), 2);
+    let rows = read_rows(&stints[1]);
+    assert_eq!(rows.len(), 20);
+    assert_eq!(rows[0], (20.0, 2, None));
+}
+
+#[test]
+fn trims_rows() {
+    let dir = TempDir::new("trim");
+    let input = dir.file("race.ibt");
+    let output = dir.file("trimmed.ibt");
+    write_file(&input, "Driver", 0, &stints());
+    trim_file(&input, &output, &[], |state| {
+        state.read_name::<bool>("OnPitRoad") == Some(true)
+    })
+    .unwrap();
+    let rows = read_rows(&output);
+    assert_eq!(rows.len(), 5);
+    assert_eq!(rows[0], (15.0, 1, Some(10.0)));
+
+    // Only the first lap was completed before the last row.
+    let client = DiskClient::open(&output).unwrap();
+    let result = &client.session_info()["SessionInfo"]["Sessions"][0]["ResultsPositions"][0];
+    assert_eq!(result["LapsComplete"].as_i64(), Some(0));
+    assert_eq!(result["Time"].as_f64(), Some(-1.0));
+    assert_eq!(result["FastestLap"].as_i64(), Some(-1));
+}
+
+#[test]
+fn merges_stints_into_one_timeline() {
+    let dir = TempDir::new("merge");
+    let first = dir.file("first.ibt");
+    let second = dir.file("second.ibt");
+    let output = dir.file("merged.ibt");
+    write_file(&first, "First", 0, &[(0.0, 0, false), (1.0, 0, false)]);
+    // Recording restarted with the session time starting over.
+    write_file(&second, "Second", 1, &[(0.5, 1, false), (1.5, 2, false)]);
+
+    merge_files(&[&first, &second], &output, &["OnPitRoad"]).unwrap();
+    let client = DiskClient::open(&output).unwrap();
+    assert_eq!(
+        client.session_info()["DriverInfo"]["DriverUserName"].as_str(),
+        Some("Second")
+    );
+    let sessions = client.session_info()["SessionInfo"]["Sessions"]
+        .as_vec()
+        .unwrap()
+        .iter()
+        .map(|v| v["SessionNum"].as_i64().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(sessions, [0, 1]);
+    assert!(!client.variables().contains_key("OnPitRoad"));
+    let sub_header = client.sub_header().clone();
+    assert_eq!(sub_header.session_record_count, 4);
+    assert_eq!(sub_header.session_lap_count, 3);
+    let end = 1.0 + 1.0 / 60.0 + 1.0;
+    assert_eq!(sub_header.session_end_time, end);
+    let session_times = read_rows(&output)
+        .into_iter()
+        .map(|v| v.0)
+        .collect::<Vec<_>>();
+    assert_eq!(session_times, vec![0.0, 1.0, 1.0 + 1.0 / 60.0, end]);
+}
@@ -0,0 +1,119 @@
+mod common;
+
+use common::{Telemetry, TempDir};
+use simetry::iracing::{DiskClient, DiskWriter, SimState, Value, VarType};
+use std::sync::Arc;
+use yaml_rust::{Yaml, YamlLoader};
+
+const VARIABLES: [(&str, VarType, usize); 6] = [
+    ("SessionTime", VarType::Double, 1),
+    ("Lap", VarType::Int, 1),
+    ("Speed", VarType::Float, 1),
+    ("OnPitRoad", VarType::Bool, 1),
+    ("CarIdxLapDistPct", VarType::Float, 8),
+    ("EngineWarnings", VarType::BitField, 1),
+];
+
+fn telemetry() -> Telemetry {
+    let mut variables = common::variables(&VARIABLES);
+    for (name, var) in variables.iter_mut() {
+        var.desc = format!("Description of {name}");
+        var.unit = "°C".to_string();
+    }
+    Telemetry::from_variables(variables)
+}
+
+fn state(telemetry: &Telemetry, tick: i32, session_info: &Arc<Yaml>) -> SimState {
+    let pcts = (0..8)
+        .flat_map(|car| ((tick + car) as f32 / 100.0).to_le_bytes())
+        .collect::<Vec<_>>();
+    telemetry
+        .row()
+        .set("SessionTime", &(100.0 + tick as f64 / 60.0).to_le_bytes())
+        .set("Lap", &(tick / 30).to_le_bytes())
+        .set("Speed", &(tick as f32 * 0.5).to_le_bytes())
+        .set("OnPitRoad", &[(tick % 7 == 0) as u8])
+        .set("CarIdxLapDistPct", &pcts)
+        .set("EngineWarnings", &(tick as u32 * 0x11).to_le_bytes())
+        .sim_state(Arc::clone(session_info))
+}
+
+#[test]
+fn round_trips_through_disk_client() {
+    let telemetry = telemetry();
+    let variables = telemetry.variables();
+    let session_info = YamlLoader::load_from_str(
+        "WeekendInfo:\n TrackName: spa\n TrackDisplayName: Circuit de Spa-Francorchamps\nDriverInfo:\n DriverCarFuelMaxLtr: 104.500\n Drivers:\n - CarIdx: 0\n   UserName: Jörg Müller\n",
+    )
+    .unwrap()
+    .swap_remove(0);
+    let dir = TempDir::new("round_trip");
+    let path = dir.file("round_trip.ibt");
+
+    let mut writer = DiskWriter::create(&path, variables, &session_info, 60).unwrap();
+    writer.sub_header_mut().session_start_date = 1_700_000_000;
+    let shared_session_info = Arc::new(session_info.clone());
+    let states = (0..90)
+        .map(|tick| state(&telemetry, tick, &shared_session_info))
+        .collect::<Vec<_>>();
+    for state in &states {
+        writer.write_sim_state(state).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let mut client = DiskClient::open(&path).unwrap();
+    assert_eq!(client.session_info(), &session_info);
+    assert_eq!(client.header().tick_rate, 60);
+    let sub_header = client.sub_header().clone();
+    assert_eq!(sub_header.session_start_date, 1_700_000_000);
+    assert_eq!(sub_header.session_record_count, 90);
+    assert_eq!(sub_header.session_lap_count, 3);
+    assert_eq!(sub_header.session_start_time, 100.0);
+    assert_eq!(sub_header.session_end_time, 100.0 + 89.0 / 60.0);
+    for (name, var) in client.variables() {
+        let original = &variables[name];
+        assert_eq!(
+            (var.var_type, var.count, &var.desc, &var.unit),
+            (
+                original.var_type,
+                original.count,
+                &original.desc,
+                &original.unit
+            )
+        );
+    }
+
+    for expected in &states {
+        let state = client.next_sim_state().unwrap();
+        for name in variables.keys() {
+            assert_eq!(
+                state.read_name::<Vec<Value>>(name),
+                expected.read_name::<Vec<Value>>(name),
+                "{name}"
+            );
+        }
+    }
+    assert!(client.next_sim_state().is_none());
+}
+
+#[test]
+fn rejects_variables_with_other_layout() {
+    let telemetry = telemetry();
+    let mut writer = DiskWriter::new(
+        std::io::Cursor::new(Vec::new()),
+        telemetry.variables(),
+        &Yaml::Null,
+        60,
+    )
+    .unwrap();
+    let mut variables = common::variables(&VARIABLES);
+    variables.get_mut("CarIdxLapDistPct").unwrap().count = 4;
+    let state = Telemetry::from_variables(variables)
+        .row()
+        .sim_state(Arc::new(Yaml::Null));
+    let err = writer.write_sim_state(&state).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Variable CarIdxLapDistPct changed count from 8 to 4"
+    );
+}
@@ -0,0 +1,90 @@
+mod common;
+
+use common::Telemetry;
+use simetry::iracing::commands::{BroadcastMessage, RecordingSink};
+use simetry::iracing::{FfbCalibrationConfig, FfbCalibrator, SimState, VarType};
+use std::sync::Arc;
+use std::time::Duration;
+use uom::si::torque::newton_meter;
+use yaml_rust::Yaml;
+
+const VARIABLES: [(&str, VarType, usize); 4] = [
+    ("SessionTime", VarType::Double, 1),
+    ("IsOnTrack", VarType::Bool, 1),
+    ("SteeringWheelTorque", VarType::Float, 1),
+    ("SteeringWheelPctTorque", VarType::Float, 1),
+];
+
+struct Wheel(Telemetry);
+
+impl Wheel {
+    fn new() -> Self {
+        Self(Telemetry::new(&VARIABLES))
+    }
+
+    /// State with torque output through a wheel set to `max_force`.
+    fn state(&self, session_time: f64, on_track: bool, torque: f32, max_force: f32) -> SimState {
+        self.0
+            .row()
+            .set("SessionTime", &session_time.to_le_bytes())
+            .set("IsOnTrack", &[on_track as u8])
+            .set("SteeringWheelTorque", &torque.to_le_bytes())
+            .set(
+                "SteeringWheelPctTorque",
+                &(torque / max_force).clamp(-1.0, 1.0).to_le_bytes(),
+            )
+            .sim_state(Arc::new(Yaml::Null))
+    }
+}
+
+#[test]
+fn recommends_max_force_from_peaks() {
+    let wheel = Wheel::new();
+    let mut calibrator = FfbCalibrator::new(FfbCalibrationConfig {
+        window: Duration::from_secs(10),
+        target_peak: 0.8,
+        peak_percentile: 0.99,
+        ..Default::default()
+    });
+    assert_eq!(calibrator.recommendation(), None);
+
+    // Torque swings up to 20 Nm while the wheel is set to 15 Nm, and one spike in the pits.
+    calibrator.update(&wheel.state(0.0, false, 50.0, 15.0));
+    for step in 0..=1200 {
+        let session_time = 1.0 + step as f64 / 60.0;
+        let torque = 20.0 * (session_time as f32).sin();
+        calibrator.update(&wheel.state(session_time, true, torque, 15.0));
+    }
+    assert!(calibrator.is_window_full());
+
+    let recommendation = calibrator.recommendation().unwrap();
+    let peak = recommendation.peak_torque.get::<newton_meter>();
+    assert!((19.5..=20.0).contains(&peak), "{peak}");
+    // The output clips while the torque is above 15 Nm, so the peak might be higher.
+    assert!((0.3..0.6).contains(&recommendation.clipping));
+    let max_force = recommendation.max_force.get::<newton_meter>();
+    assert!((peak / 0.8 * 1.1 - max_force).abs() < 1e-9);
+    assert_eq!(recommendation.samples, 601);
+
+    let sink = Arc::new(RecordingSink::new());
+    calibrator.apply_with_sink(sink.clone()).unwrap();
+    assert_eq!(
+        sink.take(),
+        vec![BroadcastMessage {
+            code: 11,
+            var1: 0,
+            var2: (max_force as f32 * 65536.0) as i64,
+        }]
+    );
+
+    // Without clipping, the peak is scaled to the target only.
+    for step in 0..=1200 {
+        let session_time = 30.0 + step as f64 / 60.0;
+        let torque = 20.0 * (session_time as f32).sin();
+        calibrator.update(&wheel.state(session_time, true, torque, 30.0));
+    }
+    let recommendation = calibrator.recommendation().unwrap();
+    assert_eq!(recommendation.clipping, 0.0);
+    let peak = recommendation.peak_torque.get::<newton_meter>();
+    assert!((peak / 0.8 - recommendation.max_force.get::<newton_meter>()).abs() < 1e-9);
+}
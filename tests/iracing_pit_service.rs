@@ -0,0 +1,203 @@
+mod common;
+
+use common::Telemetry;
+use simetry::iracing::commands::{BroadcastMessage, RecordingSink};
+use simetry::iracing::{
+    PitServiceItem, PitServiceRejected, PitServiceRequest, SimState, SimStateSource, Tire, VarType,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use uom::si::f64::{Pressure, Volume};
+use uom::si::pressure::kilopascal;
+use uom::si::volume::liter;
+use yaml_rust::{Yaml, YamlLoader};
+
+const VARIABLES: [(&str, VarType, usize); 6] = [
+    ("PitSvFlags", VarType::BitField, 1),
+    ("PitSvFuel", VarType::Float, 1),
+    ("PitSvLFP", VarType::Float, 1),
+    ("PitSvRFP", VarType::Float, 1),
+    ("PitSvLRP", VarType::Float, 1),
+    ("PitSvRRP", VarType::Float, 1),
+];
+
+/// Pit service menu which reacts to recorded commands like iRacing does.
+struct FakePit {
+    sink: Arc<RecordingSink>,
+    telemetry: Telemetry,
+    session_info: Arc<Yaml>,
+    flags: u32,
+    fuel: f32,
+    pressures: [f32; 4],
+    /// Pressures are clamped to this, like the sim does for each car.
+    max_pressure: f32,
+}
+
+impl FakePit {
+    fn new(sink: Arc<RecordingSink>) -> Self {
+        let session_info = YamlLoader::load_from_str(
+            "DriverInfo:\n DriverCarFuelMaxLtr: 100.000\n DriverCarMaxFuelPct: 0.800\n",
+        )
+        .unwrap()
+        .swap_remove(0);
+        Self {
+            sink,
+            telemetry: Telemetry::new(&VARIABLES),
+            session_info: Arc::new(session_info),
+            flags: 0,
+            fuel: 0.0,
+            pressures: [150.0; 4],
+            max_pressure: 200.0,
+        }
+    }
+
+    fn handle(&mut self, message: BroadcastMessage) {
+        assert_eq!(message.code, 9);
+        let value = message.var2 as f32;
+        match message.var1 {
+            0 => self.flags = 0,
+            1 => self.flags |= 0x20,
+            2 => {
+                self.flags |= 0x10;
+                if value > 0.0 {
+                    self.fuel = value;
+                }
+            }
+            tire @ 3..=6 => {
+                let idx = tire as usize - 3;
+                self.flags |= 1 << idx;
+                if value > 0.0 {
+                    self.pressures[idx] = value.min(self.max_pressure);
+                }
+            }
+            7 => self.flags &= !0x0f,
+            8 => self.flags |= 0x40,
+            9 => self.flags &= !0x20,
+            10 => self.flags &= !0x40,
+            11 => self.flags &= !0x10,
+            other => panic!("Unexpected pit command {other}"),
+        }
+    }
+
+    fn sim_state(&self) -> SimState {
+        let mut row = self.telemetry.row();
+        row.set("PitSvFlags", &self.flags.to_le_bytes())
+            .set("PitSvFuel", &self.fuel.to_le_bytes());
+        for (name, pressure) in ["PitSvLFP", "PitSvRFP", "PitSvLRP", "PitSvRRP"]
+            .into_iter()
+            .zip(self.pressures)
+        {
+            row.set(name, &pressure.to_le_bytes());
+        }
+        row.sim_state(Arc::clone(&self.session_info))
+    }
+}
+
+#[async_trait::async_trait]
+impl SimStateSource for FakePit {
+    async fn next_sim_state(&mut self) -> Option<SimState> {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        for message in self.sink.take() {
+            self.handle(message);
+        }
+        Some(self.sim_state())
+    }
+}
+
+fn liters(value: f64) -> Volume {
+    Volume::new::<liter>(value)
+}
+
+fn kpa(value: f64) -> Pressure {
+    Pressure::new::<kilopascal>(value)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn applies_only_differences() {
+    let sink = Arc::new(RecordingSink::new());
+    let mut pit = FakePit::new(sink.clone());
+    let request = PitServiceRequest::new()
+        .fuel(liters(30.0))
+        .change_tire(Tire::LeftFront, kpa(160.0))
+        .windshield(true);
+    request
+        .apply_and_confirm_with_sink(&mut pit, sink.clone(), Duration::from_millis(200))
+        .await
+        .unwrap();
+    assert_eq!((pit.flags, pit.fuel, pit.pressures[0]), (0x31, 30.0, 160.0));
+
+    // Nothing is sent again, and only the fuel is changed afterwards.
+    let state = pit.sim_state();
+    let sent = request.apply_with_sink(&state, sink.clone()).unwrap();
+    assert_eq!(sent, vec![]);
+    let sent = request
+        .clone()
+        .fuel(liters(0.0))
+        .apply_with_sink(&state, sink.clone())
+        .unwrap();
+    assert_eq!(sent, vec![PitServiceItem::Fuel]);
+    assert_eq!(
+        sink.take(),
+        vec![BroadcastMessage {
+            code: 9,
+            var1: 11,
+            var2: 0
+        }]
+    );
+}
+
+#[tokio::test]
+async fn keeping_tire_restores_others() {
+    let sink = Arc::new(RecordingSink::new());
+    let mut pit = FakePit::new(sink.clone());
+    pit.flags = 0x0f;
+    pit.pressures = [150.0, 151.0, 152.0, 153.0];
+    let request = PitServiceRequest::new().keep_tire(Tire::RightFront);
+    request
+        .apply_and_confirm_with_sink(&mut pit, sink.clone(), Duration::from_millis(200))
+        .await
+        .unwrap();
+    assert_eq!(pit.flags, 0x0d);
+    assert_eq!(pit.pressures, [150.0, 151.0, 152.0, 153.0]);
+}
+
+#[tokio::test]
+async fn reports_rejected_parts() {
+    let sink = Arc::new(RecordingSink::new());
+    let mut pit = FakePit::new(sink.clone());
+    let request = PitServiceRequest::new()
+        .fuel(liters(10.0))
+        .change_tire(Tire::LeftRear, kpa(250.0));
+    let err = request
+        .apply_and_confirm_with_sink(&mut pit, sink.clone(), Duration::from_millis(100))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<PitServiceRejected>(),
+        Some(&PitServiceRejected {
+            items: vec![PitServiceItem::Tire(Tire::LeftRear)]
+        })
+    );
+
+    // More than the restricted tank size is refused before sending anything.
+    let state = pit.sim_state();
+    let request = PitServiceRequest::new().fuel(liters(90.0));
+    assert!(request.apply_with_sink(&state, sink.clone()).is_err());
+    assert_eq!(sink.take(), vec![]);
+}
+
+#[tokio::test]
+async fn requires_fuel_capacity_only_for_fuel() {
+    let sink = Arc::new(RecordingSink::new());
+    let mut pit = FakePit::new(sink.clone());
+    pit.session_info = Arc::new(Yaml::Null);
+    let state = pit.next_sim_state().await.unwrap();
+    let request = PitServiceRequest::new().fuel(liters(10.0));
+    assert!(request.apply_with_sink(&state, sink.clone()).is_err());
+    assert_eq!(sink.take(), vec![]);
+    let request = PitServiceRequest::new().change_all_tires(kpa(180.0));
+    assert_eq!(
+        request.apply_with_sink(&state, sink.clone()).unwrap().len(),
+        4
+    );
+}
@@ -0,0 +1,215 @@
+mod common;
+
+use common::Telemetry;
+use simetry::iracing::commands::{BroadcastMessage, RecordingSink};
+use simetry::iracing::{
+    Highlight, ReplayController, ReplayTimeout, SimState, SimStateSource, VarType,
+};
+use std::sync::Arc;
+use std::time::Duration;
+use yaml_rust::{Yaml, YamlLoader};
+
+const FRAMES_PER_LAP: i32 = 600;
+const CAR_COUNT: usize = 4;
+
+const VARIABLES: [(&str, VarType, usize); 8] = [
+    ("ReplayFrameNum", VarType::Int, 1),
+    ("ReplaySessionTime", VarType::Double, 1),
+    ("ReplaySessionNum", VarType::Int, 1),
+    ("ReplayPlaySpeed", VarType::Int, 1),
+    ("ReplayPlaySlowMotion", VarType::Bool, 1),
+    ("CamCarIdx", VarType::Int, 1),
+    ("CamGroupNumber", VarType::Int, 1),
+    ("CarIdxLap", VarType::Int, CAR_COUNT),
+];
+
+/// Minimal replay which reacts to recorded commands like iRacing does.
+struct FakeReplay {
+    sink: Arc<RecordingSink>,
+    telemetry: Telemetry,
+    session_info: Arc<Yaml>,
+    frame: i32,
+    speed: i32,
+    slow_motion: bool,
+    cam_car_idx: i32,
+    cam_group: i32,
+    /// Frame and car of each incident.
+    incidents: Vec<(i32, i32)>,
+}
+
+impl FakeReplay {
+    fn new(sink: Arc<RecordingSink>, incidents: Vec<(i32, i32)>) -> Self {
+        let drivers = (0..CAR_COUNT)
+            .map(|idx| format!("  - CarIdx: {idx}\n    CarNumberRaw: {}\n", idx + 10))
+            .collect::<String>();
+        let session_info = YamlLoader::load_from_str(&format!("DriverInfo:\n Drivers:\n{drivers}"))
+            .unwrap()
+            .swap_remove(0);
+        Self {
+            sink,
+            telemetry: Telemetry::new(&VARIABLES),
+            session_info: Arc::new(session_info),
+            frame: 0,
+            speed: 0,
+            slow_motion: false,
+            cam_car_idx: 0,
+            cam_group: 1,
+            incidents,
+        }
+    }
+
+    fn handle(&mut self, message: BroadcastMessage) {
+        let low = (message.var2 & 0xffff) as i32;
+        match message.code {
+            // Switch camera by car number.
+            1 => {
+                self.cam_car_idx = message.var1 as i32 - 10;
+                self.cam_group = low;
+            }
+            // Play speed.
+            3 => {
+                self.speed = message.var1 as i16 as i32;
+                self.slow_motion = message.var2 != 0;
+            }
+            // Play position, from the start or relative to current frame.
+            4 if message.var1 == 0 => self.frame = message.var2 as i32,
+            4 => self.frame += message.var2 as i32,
+            // Search.
+            5 => match message.var1 {
+                4 if self.frame % FRAMES_PER_LAP == 0 => {
+                    self.frame = (self.frame - FRAMES_PER_LAP).max(0)
+                }
+                4 => self.frame -= self.frame % FRAMES_PER_LAP,
+                5 => self.frame += FRAMES_PER_LAP - self.frame % FRAMES_PER_LAP,
+                9 => {
+                    let frame = self.frame;
+                    if let Some((incident, car)) = self.incidents.iter().find(|v| v.0 > frame) {
+                        self.frame = *incident;
+                        self.cam_car_idx = *car;
+                    }
+                }
+                other => panic!("Unexpected search {other}"),
+            },
+            // Search session time.
+            12 => self.frame = (message.var2 * 60 / 1000) as i32,
+            other => panic!("Unexpected command {other}"),
+        }
+    }
+
+    fn sim_state(&self) -> SimState {
+        let laps = (0..CAR_COUNT)
+            .flat_map(|_| (self.frame / FRAMES_PER_LAP).to_le_bytes())
+            .collect::<Vec<_>>();
+        self.telemetry
+            .row()
+            .set("ReplayFrameNum", &self.frame.to_le_bytes())
+            .set(
+                "ReplaySessionTime",
+                &(self.frame as f64 / 60.0).to_le_bytes(),
+            )
+            .set("ReplaySessionNum", &0i32.to_le_bytes())
+            .set("ReplayPlaySpeed", &self.speed.to_le_bytes())
+            .set("ReplayPlaySlowMotion", &[self.slow_motion as u8])
+            .set("CamCarIdx", &self.cam_car_idx.to_le_bytes())
+            .set("CamGroupNumber", &self.cam_group.to_le_bytes())
+            .set("CarIdxLap", &laps)
+            .sim_state(Arc::clone(&self.session_info))
+    }
+}
+
+#[async_trait::async_trait]
+impl SimStateSource for FakeReplay {
+    async fn next_sim_state(&mut self) -> Option<SimState> {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+        for message in self.sink.take() {
+            self.handle(message);
+        }
+        if !self.slow_motion {
+            self.frame += self.speed;
+        }
+        Some(self.sim_state())
+    }
+}
+
+fn controller(incidents: Vec<(i32, i32)>) -> ReplayController<FakeReplay> {
+    let sink = Arc::new(RecordingSink::new());
+    ReplayController::with_sink(FakeReplay::new(sink.clone(), incidents), sink)
+        .step_timeout(Duration::from_millis(200))
+}
+
+#[tokio::test]
+async fn jumps_to_lap_of_car() {
+    let mut controller = controller(vec![]);
+    controller.jump_to_lap(2, 3).await.unwrap();
+    let state = controller.last_state().unwrap();
+    assert_eq!(state.read_name_at::<i32>("CarIdxLap", 2), Some(3));
+    assert_eq!(state.read_name::<i32>("CamCarIdx"), Some(2));
+
+    controller.jump_to_lap(1, 1).await.unwrap();
+    let state = controller.last_state().unwrap();
+    assert_eq!(state.read_name_at::<i32>("CarIdxLap", 1), Some(1));
+    assert_eq!(
+        state.read_name::<i32>("ReplayFrameNum"),
+        Some(FRAMES_PER_LAP)
+    );
+}
+
+#[tokio::test]
+async fn cycles_incidents_with_pre_roll() {
+    let mut controller = controller(vec![(1000, 3), (5000, 1)]);
+    let pre_roll = Duration::from_secs(2);
+
+    let incident = controller.next_incident(pre_roll).await.unwrap();
+    assert_eq!((incident.frame, incident.car_idx), (1000, 3));
+    let frame = controller
+        .last_state()
+        .unwrap()
+        .read_name::<i32>("ReplayFrameNum")
+        .unwrap();
+    assert!((880..1000).contains(&frame), "{frame}");
+
+    let incident = controller.next_incident(pre_roll).await.unwrap();
+    assert_eq!((incident.frame, incident.car_idx), (5000, 1));
+
+    let err = controller.next_incident(pre_roll).await.unwrap_err();
+    assert_eq!(
+        err.downcast_ref::<ReplayTimeout>(),
+        Some(&ReplayTimeout {
+            step: "search incident"
+        }),
+    );
+}
+
+#[tokio::test]
+async fn plays_highlights() {
+    let mut controller = controller(vec![]);
+    let highlights = [
+        Highlight {
+            session_num: 0,
+            start: 20.0,
+            duration: Duration::from_millis(500),
+            car_idx: Some(1),
+            camera_group: Some(4),
+        },
+        Highlight {
+            session_num: 0,
+            start: 5.0,
+            duration: Duration::from_millis(500),
+            car_idx: None,
+            camera_group: None,
+        },
+        Highlight {
+            session_num: 0,
+            start: 10.0,
+            duration: Duration::from_millis(500),
+            car_idx: None,
+            camera_group: Some(2),
+        },
+    ];
+    controller.play_highlights(&highlights).await.unwrap();
+    let state = controller.last_state().unwrap();
+    let session_time = state.read_name::<f64>("ReplaySessionTime").unwrap();
+    assert!((10.5..11.0).contains(&session_time), "{session_time}");
+    assert_eq!(state.read_name::<i32>("CamCarIdx"), Some(1));
+    assert_eq!(state.read_name::<i32>("CamGroupNumber"), Some(2));
+}
@@ -0,0 +1,96 @@
+#![cfg(target_family = "unix")]
+
+use simetry::iracing::{Client, Header};
+use simetry::SharedMemoryPath;
+use std::fs::{self, OpenOptions};
+use std::os::unix::fs::FileExt;
+use std::time::Duration;
+
+const SESSION_INFO: &[u8] = b"WeekendInfo:\n TrackName: spa\n";
+
+fn header(num_vars: i32, var_header_offset: i32, tick_count: i32) -> Vec<u8> {
+    let header_len = std::mem::size_of::<Header>() as i32;
+    let mut header = Header {
+        ver: 2,
+        status: 1,
+        tick_rate: 60,
+        session_info_update: 1,
+        session_info_len: SESSION_INFO.len() as i32,
+        session_info_offset: header_len + 4,
+        num_vars,
+        var_header_offset,
+        num_buf: 1,
+        buf_len: 4,
+        ..Default::default()
+    };
+    header.var_buf[0].tick_count = tick_count;
+    header.var_buf[0].buf_offset = header_len;
+    let bytes = unsafe {
+        std::slice::from_raw_parts(&header as *const Header as *const u8, header_len as usize)
+    };
+    bytes.to_vec()
+}
+
+#[tokio::test]
+async fn rejects_ranges_outside_of_file() {
+    let path = std::env::temp_dir().join(format!("simetry-iracing-{}", std::process::id()));
+    let mut data = header(1, 1 << 20, 1);
+    data.extend([0u8; 4]);
+    data.extend(SESSION_INFO);
+    fs::write(&path, data).unwrap();
+
+    let mut client = Client::try_connect_with_path(&SharedMemoryPath::File(path.clone()))
+        .await
+        .unwrap();
+    let next = tokio::time::timeout(Duration::from_millis(300), client.next_sim_state()).await;
+    assert!(next.is_err());
+
+    let file = OpenOptions::new().write(true).open(&path).unwrap();
+    file.write_all_at(&header(0, 0, 1), 0).unwrap();
+    // The client waits for the tick count to advance past the first one it sees.
+    let next = tokio::time::timeout(Duration::from_millis(300), client.next_sim_state()).await;
+    assert!(next.is_err());
+    file.write_all_at(&header(0, 0, 2), 0).unwrap();
+    let sim_state = client.next_sim_state().await.unwrap();
+    assert_eq!(
+        sim_state.session_info()["WeekendInfo"]["TrackName"].as_str(),
+        Some("spa")
+    );
+    fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn waits_while_file_is_shorter_than_mapped() {
+    let path = std::env::temp_dir().join(format!("simetry-iracing-short-{}", std::process::id()));
+    // Moves the session info to the third page, past the end of the file once it is truncated.
+    let header = |tick_count| {
+        let mut header = header(0, 0, tick_count);
+        header[20..24].copy_from_slice(&8192i32.to_le_bytes());
+        header
+    };
+    let mut data = header(1);
+    data.resize(8192, 0);
+    data.extend(SESSION_INFO);
+    fs::write(&path, data).unwrap();
+
+    let mut client = Client::try_connect_with_path(&SharedMemoryPath::File(path.clone()))
+        .await
+        .unwrap();
+    let next = tokio::time::timeout(Duration::from_millis(300), client.next_sim_state()).await;
+    assert!(next.is_err());
+
+    let file = OpenOptions::new().write(true).open(&path).unwrap();
+    file.set_len(4096).unwrap();
+    file.write_all_at(&header(2), 0).unwrap();
+    let next = tokio::time::timeout(Duration::from_millis(300), client.next_sim_state()).await;
+    assert!(next.is_err());
+
+    file.write_all_at(SESSION_INFO, 8192).unwrap();
+    file.write_all_at(&header(3), 0).unwrap();
+    let sim_state = client.next_sim_state().await.unwrap();
+    assert_eq!(
+        sim_state.session_info()["WeekendInfo"]["TrackName"].as_str(),
+        Some("spa")
+    );
+    fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,103 @@
+mod common;
+
+use common::Telemetry;
+use simetry::iracing::{SimState, Subscriptions, Value, VarChange, VarType};
+use std::sync::Arc;
+use yaml_rust::Yaml;
+
+const VARIABLES: [(&str, VarType, usize); 4] = [
+    ("SessionTime", VarType::Double, 1),
+    ("Gear", VarType::Int, 1),
+    ("Speed", VarType::Float, 1),
+    ("CarIdxLap", VarType::Int, 3),
+];
+
+fn state(
+    telemetry: &Telemetry,
+    session_time: f64,
+    gear: i32,
+    speed: f32,
+    laps: [i32; 3],
+) -> SimState {
+    let laps = laps
+        .iter()
+        .flat_map(|v| v.to_le_bytes())
+        .collect::<Vec<_>>();
+    telemetry
+        .row()
+        .set("SessionTime", &session_time.to_le_bytes())
+        .set("Gear", &gear.to_le_bytes())
+        .set("Speed", &speed.to_le_bytes())
+        .set("CarIdxLap", &laps)
+        .sim_state(Arc::new(Yaml::Null))
+}
+
+fn changes(subscriptions: &mut Subscriptions) -> Vec<VarChange> {
+    std::iter::from_fn(|| subscriptions.next_change()).collect()
+}
+
+#[test]
+fn reports_changes_of_subscribed_variables() {
+    let telemetry = Telemetry::new(&VARIABLES);
+    let mut subscriptions = Subscriptions::new();
+    subscriptions.subscribe("Gear");
+    subscriptions.subscribe("CarIdxLap");
+    subscriptions.subscribe_with_dead_band("Speed", 1.0);
+
+    // The first observation only records values.
+    subscriptions.update(&state(&telemetry, 1.0, 1, 10.0, [0, 0, 0]));
+    assert_eq!(changes(&mut subscriptions), vec![]);
+
+    subscriptions.update(&state(&telemetry, 2.0, 2, 10.6, [0, 1, 0]));
+    assert_eq!(
+        changes(&mut subscriptions),
+        vec![
+            VarChange {
+                name: "Gear".to_string(),
+                entry: 0,
+                old: Value::Int(1),
+                new: Value::Int(2),
+                session_time: 2.0,
+            },
+            VarChange {
+                name: "CarIdxLap".to_string(),
+                entry: 1,
+                old: Value::Int(0),
+                new: Value::Int(1),
+                session_time: 2.0,
+            },
+        ]
+    );
+
+    // Within the dead-band of the last observed value, but not of the last reported one.
+    subscriptions.update(&state(&telemetry, 3.0, 2, 11.2, [0, 1, 0]));
+    let change = subscriptions.next_change().unwrap();
+    assert_eq!(
+        (change.old, change.new),
+        (Value::Float(10.0), Value::Float(11.2))
+    );
+    subscriptions.update(&state(&telemetry, 4.0, 2, 11.9, [0, 1, 0]));
+    assert_eq!(subscriptions.next_change(), None);
+}
+
+#[test]
+fn drops_pending_changes_when_unsubscribing() {
+    let telemetry = Telemetry::new(&VARIABLES);
+    let mut subscriptions = Subscriptions::new();
+    subscriptions.subscribe("Gear");
+    subscriptions.subscribe("CarIdxLap");
+    subscriptions.update(&state(&telemetry, 1.0, 1, 0.0, [0, 0, 0]));
+    subscriptions.update(&state(&telemetry, 2.0, 2, 0.0, [1, 0, 1]));
+
+    subscriptions.unsubscribe("CarIdxLap");
+    let names = changes(&mut subscriptions)
+        .into_iter()
+        .map(|v| v.name)
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Gear"]);
+    subscriptions.update(&state(&telemetry, 3.0, 2, 0.0, [2, 0, 2]));
+    assert_eq!(subscriptions.next_change(), None);
+
+    subscriptions.unsubscribe("Gear");
+    assert!(subscriptions.is_empty());
+}
@@ -0,0 +1,154 @@
+use simetry::iracing::{Value, VarData, VarHeader, VarType};
+
+/// Small deterministic generator, so failures are reproducible from the seed.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, max: usize) -> usize {
+        (self.next() % max as u64) as usize
+    }
+}
+
+const VAR_TYPES: [VarType; 6] = [
+    VarType::Char,
+    VarType::Bool,
+    VarType::Int,
+    VarType::BitField,
+    VarType::Float,
+    VarType::Double,
+];
+
+fn random_value(rng: &mut XorShift, var_type: VarType) -> Value {
+    let bits = rng.next();
+    match var_type {
+        VarType::Char => Value::Char(bits as u8),
+        VarType::Bool => Value::Bool(bits & 1 != 0),
+        VarType::Int => Value::Int(bits as i32),
+        VarType::BitField => Value::BitField(bits as u32),
+        VarType::Float => Value::Float((bits as i32) as f32 / 1024.0),
+        VarType::Double => Value::Double((bits as i64) as f64 / 1024.0),
+    }
+}
+
+fn write_value(buffer: &mut [u8], value: &Value) {
+    match value {
+        Value::Char(v) => buffer[0] = *v,
+        Value::Bool(v) => buffer[0] = *v as u8,
+        Value::Int(v) => buffer.copy_from_slice(&v.to_le_bytes()),
+        Value::BitField(v) => buffer.copy_from_slice(&v.to_le_bytes()),
+        Value::Float(v) => buffer.copy_from_slice(&v.to_le_bytes()),
+        Value::Double(v) => buffer.copy_from_slice(&v.to_le_bytes()),
+    }
+}
+
+/// Lays out random variables one after another in a row and fills them with random values.
+fn random_layout(rng: &mut XorShift) -> (Vec<(VarHeader, Vec<Value>)>, Vec<u8>) {
+    let mut variables = Vec::new();
+    let mut row = Vec::new();
+    for idx in 0..(1 + rng.below(12)) {
+        let var_type = VAR_TYPES[rng.below(VAR_TYPES.len())];
+        let count = 1 + rng.below(8);
+        // Random padding, so variables are not always aligned.
+        row.resize(row.len() + rng.below(4), 0xAA);
+        let header = VarHeader {
+            var_type,
+            offset: row.len(),
+            count,
+            count_as_time: false,
+            name: format!("Var{idx}"),
+            desc: String::new(),
+            unit: String::new(),
+        };
+        let values = (0..count)
+            .map(|_| random_value(rng, var_type))
+            .collect::<Vec<_>>();
+        for value in &values {
+            let start = row.len();
+            row.resize(start + var_type.byte_count(), 0);
+            write_value(&mut row[start..], value);
+        }
+        variables.push((header, values));
+    }
+    (variables, row)
+}
+
+#[test]
+fn parses_every_entry_of_random_layouts() {
+    let mut rng = XorShift(0x5EED_1234_ABCD_0001);
+    for _ in 0..500 {
+        let (variables, row) = random_layout(&mut rng);
+        for (header, values) in &variables {
+            for (entry, value) in values.iter().enumerate() {
+                let parsed = Value::parse_from_raw(entry, header, &row);
+                assert_eq!(parsed.as_ref(), Some(value), "{header:?} entry {entry}");
+                let tail = Vec::<Value>::parse_from_raw(entry, header, &row);
+                assert_eq!(tail.as_deref(), Some(&values[entry..]), "{header:?}");
+            }
+            assert_eq!(Value::parse_from_raw(header.count, header, &row), None);
+        }
+    }
+}
+
+#[test]
+fn truncated_rows_return_none() {
+    let mut rng = XorShift(0x5EED_1234_ABCD_0002);
+    for _ in 0..500 {
+        let (variables, row) = random_layout(&mut rng);
+        let truncated = &row[..rng.below(row.len())];
+        for (header, values) in &variables {
+            for entry in 0..values.len() {
+                let end = header.offset + header.count * header.var_type.byte_count();
+                let parsed = Vec::<Value>::parse_from_raw(entry, header, truncated);
+                assert_eq!(parsed.is_some(), end <= truncated.len(), "{header:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn parses_fixed_size_arrays() {
+    let mut rng = XorShift(0x5EED_1234_ABCD_0003);
+    for _ in 0..500 {
+        let (variables, row) = random_layout(&mut rng);
+        for (header, values) in &variables {
+            let array = <[Value; 4]>::parse_from_raw(0, header, &row);
+            if values.len() >= 4 {
+                assert_eq!(array.as_ref().map(|v| &v[..]), Some(&values[..4]));
+            } else {
+                assert_eq!(array, None);
+            }
+            let full = <[Value; 1]>::parse_from_raw(values.len() - 1, header, &row);
+            assert_eq!(full, Some([values[values.len() - 1].clone()]));
+        }
+    }
+}
+
+#[test]
+fn parses_char_arrays_as_strings() {
+    let header = VarHeader {
+        var_type: VarType::Char,
+        offset: 2,
+        count: 8,
+        count_as_time: false,
+        name: "Name".to_string(),
+        desc: String::new(),
+        unit: String::new(),
+    };
+    let row = b"xxAbc\xe9\0zzzyy";
+    assert_eq!(
+        String::parse_from_raw(0, &header, row).as_deref(),
+        Some("Abc\u{e9}")
+    );
+    assert_eq!(
+        String::parse_from_raw(1, &header, row).as_deref(),
+        Some("bc\u{e9}")
+    );
+    assert_eq!(String::parse_from_raw(0, &header, &row[..9]), None);
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use byteorder::{ByteOrder, LittleEndian};
+use simetry::outgauge::{dash_lights, outsim_options, Client, Emitter, OutGauge, OutSim};
+use simetry::{Moment, Simetry};
+use std::borrow::Cow;
+use std::time::Duration;
+use tokio::net::UdpSocket;
+use uom::si::angular_velocity::revolution_per_minute;
+use uom::si::f64::AngularVelocity;
+use uom::si::pressure::bar;
+
+fn outgauge(with_id: bool) -> Vec<u8> {
+    let mut data = vec![0u8; if with_id { 96 } else { 92 }];
+    data[4..7].copy_from_slice(b"XRT");
+    data[10] = 3;
+    LittleEndian::write_f32(&mut data[12..], 20.0);
+    LittleEndian::write_f32(&mut data[16..], 4500.0);
+    LittleEndian::write_f32(&mut data[20..], 0.5);
+    LittleEndian::write_f32(&mut data[28..], 0.25);
+    let available = dash_lights::PIT_SPEED | dash_lights::SIGNAL_LEFT | dash_lights::BATTERY;
+    LittleEndian::write_u32(&mut data[40..], available);
+    LittleEndian::write_u32(&mut data[44..], dash_lights::SIGNAL_LEFT);
+    LittleEndian::write_f32(&mut data[48..], 0.8);
+    data[60..64].copy_from_slice(b"Fuel");
+    if with_id {
+        LittleEndian::write_i32(&mut data[92..], 7);
+    }
+    data
+}
+
+#[test]
+fn decodes_packets() {
+    let packet = OutGauge::decode(&outgauge(false)).unwrap();
+    assert_eq!(
+        (packet.car.as_str(), packet.gear, packet.id),
+        ("XRT", 3, None)
+    );
+    assert_eq!(packet.display_1, "Fuel");
+    assert_eq!(OutGauge::decode(&outgauge(true)).unwrap().id, Some(7));
+    assert_eq!(packet.light(dash_lights::SIGNAL_LEFT), Some(true));
+    assert_eq!(packet.light(dash_lights::PIT_SPEED), Some(false));
+    assert_eq!(packet.light(dash_lights::ABS), None);
+    assert!(OutGauge::decode(&[0u8; 64]).is_err());
+    let long_display = OutGauge {
+        display_1: "Fuel remaining: 12 laps".to_string(),
+        ..packet.clone()
+    }
+    .encode();
+    assert_eq!(long_display[75], 0);
+    assert_eq!(
+        OutGauge::decode(&long_display).unwrap().display_1,
+        "Fuel remaining:"
+    );
+
+    let mut original = vec![0u8; 68];
+    LittleEndian::write_u32(&mut original[0..], 1000);
+    LittleEndian::write_i32(&mut original[52..], 65536);
+    LittleEndian::write_i32(&mut original[64..], 3);
+    let outsim = OutSim::decode(&original, 0).unwrap();
+    assert_eq!((outsim.time, outsim.id), (Some(1000), Some(3)));
+    assert_eq!(outsim.main.unwrap().position[0], 65536);
+
+    let options = outsim_options::HEADER | outsim_options::INPUTS | outsim_options::WHEELS;
+    let mut data = b"LFST".to_vec();
+    data.extend(0.5f32.to_le_bytes());
+    data.extend([0u8; 16]);
+    for wheel in 0..4u8 {
+        data.extend([0u8; 28]);
+        data.extend([wheel + 20, 0, 1, 0]);
+        data.extend([0u8; 8]);
+    }
+    let outsim = OutSim::decode(&data, options).unwrap();
+    assert_eq!(outsim.inputs.unwrap().throttle, 0.5);
+    let wheels = outsim.wheels.unwrap();
+    assert_eq!((wheels[3].air_temperature, wheels[3].touching), (23, true));
+    assert!(outsim.main.is_none());
+    assert!(OutSim::decode(&data, options | outsim_options::EXTRA_1).is_err());
+}
+
+#[tokio::test]
+async fn receives_packets() {
+    let (socket, sender) = common::udp_pair().await;
+    sender.send(&outgauge(false)).await.unwrap();
+    let mut client = Client::try_connect_with_socket(socket).await.unwrap();
+    sender.send(&outgauge(true)).await.unwrap();
+    let state = client.next_sim_state().await.unwrap();
+    assert_eq!(state.vehicle_gear(), Some(2));
+    assert_eq!(state.vehicle_model_id().as_deref(), Some("XRT"));
+    assert_eq!(state.turbo_pressure().get::<bar>(), 0.5);
+    assert_eq!(state.fuel(), 0.25);
+    assert_eq!(state.is_pit_limiter_engaged(), Some(false));
+    assert_eq!(state.is_left_turn_indicator_on(), Some(true));
+    assert_eq!(state.is_right_turn_indicator_on(), None);
+    assert_eq!(state.is_ignition_on(), Some(true));
+    assert!((state.pedals().unwrap().throttle - 0.8).abs() < 1e-6);
+}
+
+struct Pitting;
+
+impl Moment for Pitting {
+    fn vehicle_gear(&self) -> Option<i8> {
+        Some(-1)
+    }
+
+    fn vehicle_engine_rotation_speed(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(7000.0))
+    }
+
+    fn shift_point(&self) -> Option<AngularVelocity> {
+        Some(AngularVelocity::new::<revolution_per_minute>(6500.0))
+    }
+
+    fn is_pit_limiter_engaged(&self) -> Option<bool> {
+        Some(true)
+    }
+
+    fn is_right_turn_indicator_on(&self) -> Option<bool> {
+        Some(true)
+    }
+
+    fn vehicle_model_id(&self) -> Option<Cow<str>> {
+        Some("FBM".into())
+    }
+}
+
+struct PittingSim;
+
+#[async_trait::async_trait]
+impl Simetry for PittingSim {
+    fn name(&self) -> &str {
+        "Pitting"
+    }
+
+    async fn next_moment(&mut self) -> Option<Box<dyn Moment + Send + Sync + 'static>> {
+        // Slower than the interval of the emitter.
+        tokio::time::sleep(Duration::from_millis(25)).await;
+        Some(Box::new(Pitting))
+    }
+}
+
+#[tokio::test]
+async fn emits_moments() {
+    let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let target = receiver.local_addr().unwrap().to_string();
+    let emitter = Emitter::connect(&target)
+        .await
+        .unwrap()
+        .interval(Duration::from_millis(10))
+        .id(5);
+    let emitting = tokio::spawn(async move { emitter.run(&mut PittingSim).await });
+    let mut buffer = [0u8; 256];
+    let len = receiver.recv(&mut buffer).await.unwrap();
+    emitting.abort();
+
+    let packet = OutGauge::decode(&buffer[..len]).unwrap();
+    assert_eq!(
+        (packet.car.as_str(), packet.gear, packet.id),
+        ("FBM", 0, Some(5))
+    );
+    assert_eq!(packet.light(dash_lights::SHIFT), Some(true));
+    assert_eq!(packet.light(dash_lights::PIT_SPEED), Some(true));
+    assert_eq!(packet.light(dash_lights::SIGNAL_LEFT), None);
+    assert_eq!(packet.light(dash_lights::SIGNAL_ANY), Some(true));
+    assert_eq!(packet.rpm, 7000.0);
+}
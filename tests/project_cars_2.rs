@@ -0,0 +1,140 @@
+mod common;
+
+use byteorder::{ByteOrder, LittleEndian};
+use simetry::project_cars_2::{Client, Packet, PacketData};
+use simetry::Moment;
+use uom::si::velocity::meter_per_second;
+
+fn packet(packet_type: u8, len: usize, partial_packet_index: u8) -> Vec<u8> {
+    let mut data = vec![0u8; len];
+    data[8] = partial_packet_index;
+    data[9] = 2;
+    data[10] = packet_type;
+    data[11] = 1;
+    data
+}
+
+fn telemetry() -> Vec<u8> {
+    let mut data = packet(0, 559, 1);
+    data[12] = 17;
+    data[13] = 255;
+    data[17] = 0b1010;
+    data[30] = 51;
+    LittleEndian::write_f32(&mut data[36..], 42.5);
+    LittleEndian::write_u16(&mut data[40..], 6500);
+    LittleEndian::write_u16(&mut data[42..], 8000);
+    data[45] = 0x6F;
+    data[378..382].copy_from_slice(b"Soft");
+    LittleEndian::write_u32(&mut data[555..], 1234);
+    data
+}
+
+fn timings() -> Vec<u8> {
+    let mut data = packet(3, 1063, 1);
+    data[12] = 20;
+    LittleEndian::write_u32(&mut data[13..], 7);
+    let offset = 33 + 17 * 32;
+    data[offset + 14] = 0x80 | 3;
+    data[offset + 16] = 0x16;
+    data[offset + 17] = 2;
+    data[offset + 20] = 0x08;
+    LittleEndian::write_f32(&mut data[offset + 22..], 61.5);
+    LittleEndian::write_u16(&mut data[1057..], 17);
+    data
+}
+
+fn participants() -> Vec<u8> {
+    let mut data = packet(2, 1136, 2);
+    LittleEndian::write_u32(&mut data[12..], 7);
+    data[16 + 64..16 + 64 + 5].copy_from_slice(b"Alice");
+    LittleEndian::write_u32(&mut data[1040 + 4..], 44);
+    LittleEndian::write_u16(&mut data[1104 + 2..], 17);
+    data
+}
+
+fn vehicle_names() -> Vec<u8> {
+    let mut data = packet(8, 1164, 2);
+    LittleEndian::write_u16(&mut data[12..], 17);
+    LittleEndian::write_u32(&mut data[16..], 3);
+    data[20..29].copy_from_slice(b"Formula V");
+    data
+}
+
+fn class_names() -> Vec<u8> {
+    let mut data = packet(8, 1452, 1);
+    LittleEndian::write_u32(&mut data[12 + 24..], 3);
+    data[12 + 28..12 + 28 + 3].copy_from_slice(b"F-V");
+    data
+}
+
+#[test]
+fn decodes_packets() {
+    let data = telemetry();
+    let packet = Packet::decode(&data).unwrap();
+    assert_eq!(packet.header.packet_version, 1);
+    let PacketData::Telemetry(telemetry) = packet.data else {
+        panic!("Not telemetry");
+    };
+    assert_eq!((telemetry.gear, telemetry.num_gears), (15, 6));
+    assert_eq!(telemetry.tyre_compound[0], "Soft");
+    assert_eq!(telemetry.tick_count, 1234);
+
+    let PacketData::Timings(timings) = Packet::decode(&timings()).unwrap().data else {
+        panic!("Not timings");
+    };
+    let timing = &timings.participants[17];
+    assert_eq!((timing.race_position, timing.is_active), (3, true));
+    assert_eq!((timing.flag_colour(), timing.pit_mode()), (6, 2));
+    assert!(timing.is_lap_invalidated());
+    assert_eq!(timing.current_time, 61.5);
+    assert_eq!(timings.local_participant_index, 17);
+
+    let PacketData::VehicleClassNames(classes) = Packet::decode(&class_names()).unwrap().data
+    else {
+        panic!("Not class names");
+    };
+    assert_eq!(
+        (classes[0].class_index, classes[0].name.as_str()),
+        (3, "F-V")
+    );
+    assert!(Packet::decode(&data[..400]).is_err());
+}
+
+#[tokio::test]
+async fn reassembles_sim_state() {
+    let (socket, sender) = common::udp_pair().await;
+    sender.send(&telemetry()).await.unwrap();
+    let mut client = Client::try_connect_with_socket(socket).await.unwrap();
+    for data in [
+        class_names(),
+        vehicle_names(),
+        participants(),
+        timings(),
+        telemetry(),
+    ] {
+        sender.send(&data).await.unwrap();
+    }
+    let state = loop {
+        let state = client.next_sim_state().await.unwrap();
+        if state.timings.is_some() {
+            break state;
+        }
+    };
+    let participant = state.viewed_participant().unwrap();
+    assert_eq!(
+        (participant.name.as_str(), participant.nationality),
+        ("Alice", 44)
+    );
+    assert_eq!(participant.vehicle_class.as_deref(), Some("F-V"));
+    assert_eq!(state.vehicle_model_id().as_deref(), Some("Formula V"));
+    assert_eq!(state.vehicle_gear(), Some(-1));
+    assert_eq!(
+        state.vehicle_velocity().unwrap().get::<meter_per_second>(),
+        42.5
+    );
+    assert_eq!(state.is_pit_limiter_engaged(), Some(true));
+    assert_eq!(state.is_vehicle_in_pit_lane(), Some(true));
+    assert!(state.flags().unwrap().yellow);
+    assert_eq!(state.pedals().unwrap().throttle, 0.2);
+    assert_eq!(state.pedals_raw().unwrap().throttle, 1.0);
+}
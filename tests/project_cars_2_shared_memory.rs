@@ -0,0 +1,50 @@
+use byteorder::{ByteOrder, LittleEndian};
+use simetry::project_cars_2::shared_memory::{SharedMemoryData, SimState};
+use simetry::Moment;
+use uom::si::velocity::meter_per_second;
+
+#[test]
+fn decodes_shared_memory() {
+    assert_eq!(SharedMemoryData::SIZE, 20576);
+    let mut data = vec![0u8; SharedMemoryData::SIZE + 100];
+    LittleEndian::write_u32(&mut data[0..], 13);
+    LittleEndian::write_i32(&mut data[20..], 1);
+    data[28 + 100] = 1;
+    data[28 + 101..28 + 106].copy_from_slice(b"Alice");
+    LittleEndian::write_u32(&mut data[28 + 100 + 84..], 2);
+    data[6444..6453].copy_from_slice(b"Formula V");
+    LittleEndian::write_u32(&mut data[6800..], 11);
+    LittleEndian::write_u32(&mut data[6808..], 2);
+    LittleEndian::write_u32(&mut data[6816..], 0b1010);
+    LittleEndian::write_f32(&mut data[6848..], 42.5);
+    LittleEndian::write_f32(&mut data[6864..], 0.75);
+    LittleEndian::write_i32(&mut data[6876..], -1);
+    LittleEndian::write_u32(&mut data[7320..], 42);
+    LittleEndian::write_u32(&mut data[20316 + 4..], 44);
+    LittleEndian::write_f32(&mut data[20572..], 0.5);
+
+    let data = SharedMemoryData::decode(&data).unwrap();
+    assert_eq!((data.version, data.sequence_number), (13, 42));
+    let participant = data.viewed_participant().unwrap();
+    assert_eq!(
+        (participant.name().as_str(), participant.race_position),
+        ("Alice", 2)
+    );
+    assert_eq!(data.nationalities[1], 44);
+    assert_eq!(data.snow_density, 0.5);
+
+    let state = SimState {
+        data: Box::new(data),
+    };
+    assert_eq!(state.vehicle_model_id().as_deref(), Some("Formula V"));
+    assert_eq!(state.vehicle_gear(), Some(-1));
+    assert_eq!(
+        state.vehicle_velocity().unwrap().get::<meter_per_second>(),
+        42.5
+    );
+    assert_eq!(state.is_pit_limiter_engaged(), Some(true));
+    assert_eq!(state.is_vehicle_in_pit_lane(), Some(true));
+    assert!(state.flags().unwrap().checkered);
+    assert_eq!(state.pedals().unwrap().throttle, 0.75);
+    assert!(SharedMemoryData::decode(&[0u8; 7000]).is_err());
+}
@@ -0,0 +1,58 @@
+use simetry::truck_simulator::{bindings, Event, Game, SimState};
+use std::os::raw::c_char;
+
+fn set_string(target: &mut [c_char], value: &str) {
+    for (target, byte) in target.iter_mut().zip(value.bytes()) {
+        *target = byte as c_char;
+    }
+}
+
+fn empty_sim_state() -> SimState {
+    SimState {
+        game: Game::Ets2,
+        shared: unsafe { std::mem::zeroed::<bindings::scsTelemetryMap_t>() },
+    }
+}
+
+#[test]
+fn reads_job() {
+    let mut sim_state = empty_sim_state();
+    assert_eq!(sim_state.job(), None);
+
+    sim_state.shared.special_b.onJob = true;
+    set_string(&mut sim_state.shared.config_s.cargo, "Apples");
+    set_string(&mut sim_state.shared.config_s.citySrc, "Kraków");
+    set_string(&mut sim_state.shared.config_s.cityDst, "Berlin");
+    sim_state.shared.config_f.cargoMass = 12000.0;
+    sim_state.shared.config_ull.jobIncome = 4200;
+    let job = sim_state.job().unwrap();
+    assert_eq!(job.cargo.name, "Apples");
+    assert_eq!(job.cargo.mass, 12000.0);
+    assert_eq!(job.source.city, "Kraków");
+    assert_eq!(job.destination.city, "Berlin");
+    assert_eq!(job.income, 4200);
+}
+
+#[test]
+fn reports_new_events_only() {
+    let previous = empty_sim_state();
+    let mut current = previous.clone();
+    current.shared.special_b.fined = true;
+    current.shared.gameplay_ll.fineAmount = 300;
+    set_string(&mut current.shared.gameplay_s.fineOffence, "speeding");
+    let fined = Event::Fined {
+        offence: "speeding".to_string(),
+        amount: 300,
+    };
+    assert_eq!(current.new_events(&previous), vec![fined.clone()]);
+
+    // The flag staying set does not report the event again.
+    let mut next = current.clone();
+    next.shared.special_b.tollgate = true;
+    next.shared.gameplay_ll.tollgatePayAmount = 12;
+    assert_eq!(next.active_events().len(), 2);
+    assert_eq!(
+        next.new_events(&current),
+        vec![Event::TollgatePaid { amount: 12 }]
+    );
+}